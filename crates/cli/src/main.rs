@@ -41,7 +41,7 @@ enum Commands {
 
     /// Search memories (hybrid: keyword + vector similarity)
     Search {
-        /// Search query
+        /// Search query ("phrases", AND/OR/NOT, tag:/type:/agent:/user:/session: filters)
         query: String,
         /// Filter by agent
         #[arg(long)]
@@ -78,6 +78,73 @@ enum Commands {
         addr: String,
     },
 
+    /// Start a session (memories tagged with its ID are summarized when it ends)
+    SessionStart {
+        /// Agent ID
+        #[arg(long)]
+        agent: String,
+        /// User ID
+        #[arg(long)]
+        user: Option<String>,
+        /// Session ID (generated if omitted)
+        #[arg(long)]
+        session: Option<String>,
+        /// HiveMindDB address
+        #[arg(long, default_value = "http://127.0.0.1:8100")]
+        addr: String,
+    },
+
+    /// End a session and record it as an episode
+    SessionEnd {
+        /// Session ID
+        session_id: String,
+        /// Agent ID (needed if the session was not started with session-start)
+        #[arg(long)]
+        agent: Option<String>,
+        /// Summary (derived from the session's memories if omitted)
+        #[arg(long)]
+        summary: Option<String>,
+        /// Outcome (e.g. success, partial, failed)
+        #[arg(long)]
+        outcome: Option<String>,
+        /// Key decisions (comma-separated)
+        #[arg(long)]
+        decisions: Option<String>,
+        /// Tools used (comma-separated)
+        #[arg(long)]
+        tools: Option<String>,
+        /// Summarize with the LLM pipeline
+        #[arg(long)]
+        summarize: bool,
+        /// HiveMindDB address
+        #[arg(long, default_value = "http://127.0.0.1:8100")]
+        addr: String,
+    },
+
+    /// List or search recorded episodes
+    Episodes {
+        /// Search text (lists all episodes if omitted)
+        query: Option<String>,
+        /// Filter by agent
+        #[arg(long)]
+        agent: Option<String>,
+        /// Filter by user
+        #[arg(long)]
+        user: Option<String>,
+        /// Only episodes that ended at or after this time (RFC 3339)
+        #[arg(long)]
+        since: Option<String>,
+        /// Only episodes that started at or before this time (RFC 3339)
+        #[arg(long)]
+        until: Option<String>,
+        /// Max results
+        #[arg(long, default_value = "10")]
+        limit: usize,
+        /// HiveMindDB address
+        #[arg(long, default_value = "http://127.0.0.1:8100")]
+        addr: String,
+    },
+
     /// Show memory history (audit trail)
     History {
         /// Memory ID
@@ -133,6 +200,73 @@ enum Commands {
         #[arg(long, default_value = "http://127.0.0.1:8100")]
         addr: String,
     },
+
+    /// List recurring task templates
+    Templates {
+        /// HiveMindDB address
+        #[arg(long, default_value = "http://127.0.0.1:8100")]
+        addr: String,
+    },
+
+    /// Add a recurring task template (--cron or --every)
+    TemplateAdd {
+        /// Title of the tasks it creates
+        title: String,
+        /// Task description
+        #[arg(long, default_value = "")]
+        description: String,
+        /// Cron expression in UTC (e.g. "0 2 * * *" for 02:00 nightly)
+        #[arg(long, conflicts_with = "every", required_unless_present = "every")]
+        cron: Option<String>,
+        /// Interval in seconds
+        #[arg(long)]
+        every: Option<u64>,
+        /// Don't create tasks before this time (RFC 3339)
+        #[arg(long)]
+        not_before: Option<String>,
+        /// Task priority
+        #[arg(long, default_value = "0")]
+        priority: u32,
+        /// Required capabilities (comma-separated)
+        #[arg(long)]
+        capabilities: Option<String>,
+        /// Each task is due this many seconds after it is created
+        #[arg(long)]
+        deadline_secs: Option<u64>,
+        /// Creator recorded on the template and its tasks
+        #[arg(long, default_value = "cli")]
+        created_by: String,
+        /// HiveMindDB address
+        #[arg(long, default_value = "http://127.0.0.1:8100")]
+        addr: String,
+    },
+
+    /// Stop a task template from creating tasks
+    TemplatePause {
+        /// Template ID
+        id: u64,
+        /// HiveMindDB address
+        #[arg(long, default_value = "http://127.0.0.1:8100")]
+        addr: String,
+    },
+
+    /// Resume a paused task template
+    TemplateResume {
+        /// Template ID
+        id: u64,
+        /// HiveMindDB address
+        #[arg(long, default_value = "http://127.0.0.1:8100")]
+        addr: String,
+    },
+
+    /// Delete a task template (tasks it created are kept)
+    TemplateRm {
+        /// Template ID
+        id: u64,
+        /// HiveMindDB address
+        #[arg(long, default_value = "http://127.0.0.1:8100")]
+        addr: String,
+    },
 }
 
 #[tokio::main]
@@ -205,7 +339,7 @@ async fn main() -> Result<()> {
                 .map(|t| t.split(',').map(|s| s.trim().to_string()).collect())
                 .unwrap_or_default();
 
-            let resp: Vec<Value> = client
+            let resp = client
                 .post(format!("{}/api/v1/search", addr))
                 .json(&serde_json::json!({
                     "query": query,
@@ -213,12 +347,16 @@ async fn main() -> Result<()> {
                     "user_id": user,
                     "tags": tags_vec,
                     "limit": limit,
+                    "structured": true,
                 }))
                 .send()
                 .await
-                .context("Failed to connect")?
-                .json()
-                .await?;
+                .context("Failed to connect")?;
+
+            if !resp.status().is_success() {
+                anyhow::bail!("{}", resp.text().await.unwrap_or_default());
+            }
+            let resp: Vec<Value> = resp.json().await?;
 
             if resp.is_empty() {
                 println!("No memories found.");
@@ -308,6 +446,130 @@ async fn main() -> Result<()> {
             }
         }
 
+        Commands::SessionStart {
+            agent,
+            user,
+            session,
+            addr,
+        } => {
+            let resp = client
+                .post(format!("{}/api/v1/sessions/start", addr))
+                .json(&serde_json::json!({
+                    "agent_id": agent,
+                    "user_id": user,
+                    "session_id": session,
+                }))
+                .send()
+                .await
+                .context("Failed to connect")?;
+
+            if !resp.status().is_success() {
+                anyhow::bail!("{}", resp.text().await.unwrap_or_default());
+            }
+            let resp: Value = resp.json().await?;
+            println!("Session {} started", resp["session_id"]);
+        }
+
+        Commands::SessionEnd {
+            session_id,
+            agent,
+            summary,
+            outcome,
+            decisions,
+            tools,
+            summarize,
+            addr,
+        } => {
+            let split = |list: Option<String>| -> Vec<String> {
+                list.map(|t| t.split(',').map(|s| s.trim().to_string()).collect())
+                    .unwrap_or_default()
+            };
+
+            let resp = client
+                .post(format!("{}/api/v1/sessions/{}/end", addr, session_id))
+                .json(&serde_json::json!({
+                    "agent_id": agent,
+                    "summary": summary,
+                    "outcome": outcome,
+                    "key_decisions": split(decisions),
+                    "tools_used": split(tools),
+                    "summarize": summarize,
+                }))
+                .send()
+                .await
+                .context("Failed to connect")?;
+
+            if !resp.status().is_success() {
+                anyhow::bail!("{}", resp.text().await.unwrap_or_default());
+            }
+            let episode: Value = resp.json().await?;
+            println!("Episode #{} recorded", episode["id"]);
+            print_episode(&episode);
+        }
+
+        Commands::Episodes {
+            query,
+            agent,
+            user,
+            since,
+            until,
+            limit,
+            addr,
+        } => {
+            let mut params = vec![("limit", limit.to_string())];
+            for (key, value) in [("agent_id", agent), ("user_id", user), ("since", since), ("until", until)] {
+                if let Some(value) = value {
+                    params.push((key, value));
+                }
+            }
+
+            let resp = match query {
+                Some(query) => {
+                    let mut body: serde_json::Map<String, Value> = params
+                        .into_iter()
+                        .map(|(k, v)| (k.to_string(), Value::String(v)))
+                        .collect();
+                    body.insert("limit".into(), limit.into());
+                    body.insert("query".into(), query.into());
+                    client
+                        .post(format!("{}/api/v1/episodes/search", addr))
+                        .json(&body)
+                        .send()
+                        .await
+                }
+                None => {
+                    client
+                        .get(format!("{}/api/v1/episodes", addr))
+                        .query(&params)
+                        .send()
+                        .await
+                }
+            }
+            .context("Failed to connect")?;
+
+            if !resp.status().is_success() {
+                anyhow::bail!("{}", resp.text().await.unwrap_or_default());
+            }
+            let resp: Vec<Value> = resp.json().await?;
+
+            if resp.is_empty() {
+                println!("No episodes found.");
+            }
+            for entry in &resp {
+                // Search results wrap the episode with a score
+                let episode = if entry["episode"].is_object() { &entry["episode"] } else { entry };
+                println!(
+                    "#{} [{}] {} — {} → {}",
+                    episode["id"],
+                    episode["agent_id"],
+                    episode["session_id"],
+                    episode["started_at"],
+                    episode["ended_at"]
+                );
+                print_episode(episode);
+            }
+        }
+
         Commands::History { id, addr } => {
             let resp: Vec<Value> = client
                 .get(format!("{}/api/v1/memories/{}/history", addr, id))
@@ -473,7 +735,141 @@ async fn main() -> Result<()> {
                 );
             }
         }
+
+        Commands::Templates { addr } => {
+            let resp: Vec<Value> = client
+                .get(format!("{}/api/v1/task-templates", addr))
+                .send()
+                .await
+                .context("Failed to connect")?
+                .json()
+                .await?;
+
+            println!("Task Templates:");
+            if resp.is_empty() {
+                println!("  (none)");
+            }
+            for template in &resp {
+                print_template(template);
+            }
+        }
+
+        Commands::TemplateAdd {
+            title,
+            description,
+            cron,
+            every,
+            not_before,
+            priority,
+            capabilities,
+            deadline_secs,
+            created_by,
+            addr,
+        } => {
+            let schedule = match (cron, every) {
+                (Some(expression), _) => serde_json::json!({ "kind": "cron", "expression": expression }),
+                (None, Some(every_secs)) => serde_json::json!({ "kind": "interval", "every_secs": every_secs }),
+                (None, None) => anyhow::bail!("Provide --cron or --every"),
+            };
+            let capabilities: Vec<String> = capabilities
+                .map(|t| t.split(',').map(|s| s.trim().to_string()).collect())
+                .unwrap_or_default();
+
+            let resp = client
+                .post(format!("{}/api/v1/task-templates", addr))
+                .json(&serde_json::json!({
+                    "title": title,
+                    "description": description,
+                    "schedule": schedule,
+                    "not_before": not_before,
+                    "priority": priority,
+                    "required_capabilities": capabilities,
+                    "deadline_secs": deadline_secs,
+                    "created_by": created_by,
+                }))
+                .send()
+                .await
+                .context("Failed to connect")?;
+
+            if !resp.status().is_success() {
+                anyhow::bail!("{}", resp.text().await.unwrap_or_default());
+            }
+            let template: Value = resp.json().await?;
+            println!("Task template #{} added", template["id"]);
+            print_template(&template);
+        }
+
+        Commands::TemplatePause { id, addr } => {
+            let template = set_template_enabled(&client, &addr, id, false).await?;
+            println!("Task template #{} paused", template["id"]);
+        }
+
+        Commands::TemplateResume { id, addr } => {
+            let template = set_template_enabled(&client, &addr, id, true).await?;
+            println!("Task template #{} resumed, next run {}", template["id"], template["next_run_at"]);
+        }
+
+        Commands::TemplateRm { id, addr } => {
+            let resp = client
+                .delete(format!("{}/api/v1/task-templates/{}", addr, id))
+                .send()
+                .await
+                .context("Failed to connect")?;
+
+            if resp.status().is_success() {
+                println!("Task template #{} deleted", id);
+            } else {
+                println!("Failed: task template #{} not found", id);
+            }
+        }
     }
 
     Ok(())
 }
+
+fn print_episode(episode: &Value) {
+    println!("  Summary: {}", episode["summary"].as_str().unwrap_or_default());
+    println!("  Outcome: {}", episode["outcome"].as_str().unwrap_or_default());
+    for (label, key) in [("Decisions", "key_decisions"), ("Tools", "tools_used")] {
+        let items: Vec<&str> = episode[key]
+            .as_array()
+            .map(|a| a.iter().filter_map(|v| v.as_str()).collect())
+            .unwrap_or_default();
+        if !items.is_empty() {
+            println!("  {}: {}", label, items.join(", "));
+        }
+    }
+}
+
+async fn set_template_enabled(client: &reqwest::Client, addr: &str, id: u64, enabled: bool) -> Result<Value> {
+    let resp = client
+        .put(format!("{}/api/v1/task-templates/{}", addr, id))
+        .json(&serde_json::json!({ "enabled": enabled }))
+        .send()
+        .await
+        .context("Failed to connect")?;
+
+    if !resp.status().is_success() {
+        anyhow::bail!("{}", resp.text().await.unwrap_or_default());
+    }
+    Ok(resp.json().await?)
+}
+
+fn print_template(template: &Value) {
+    let schedule = &template["schedule"];
+    let schedule = match schedule["kind"].as_str() {
+        Some("cron") => format!("cron {}", schedule["expression"]),
+        _ => format!("every {}s", schedule["every_secs"]),
+    };
+    println!(
+        "  #{} {} — {} — {}",
+        template["id"],
+        template["title"],
+        schedule,
+        if template["enabled"].as_bool().unwrap_or(false) { "enabled" } else { "paused" }
+    );
+    println!(
+        "       next run: {}, runs: {}, last task: {}",
+        template["next_run_at"], template["run_count"], template["last_task_id"]
+    );
+}
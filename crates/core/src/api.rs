@@ -9,20 +9,28 @@ use std::sync::Arc;
 use tower_http::cors::CorsLayer;
 
 use crate::channels::ChannelHub;
-use crate::memory_engine::MemoryEngine;
+use crate::memory_engine::{MemoryEngine, WriteError};
+use crate::persistence::{self, NodeReadiness};
+use crate::scheduler;
 use crate::types::*;
 use crate::websocket;
 
 pub struct AppState {
     pub engine: Arc<MemoryEngine>,
     pub channels: Arc<ChannelHub>,
+    pub readiness: Arc<NodeReadiness>,
     pub started_at: DateTime<Utc>,
 }
 
-pub fn router(engine: Arc<MemoryEngine>, channels: Arc<ChannelHub>) -> Router {
+pub fn router(
+    engine: Arc<MemoryEngine>,
+    channels: Arc<ChannelHub>,
+    readiness: Arc<NodeReadiness>,
+) -> Router {
     let state = Arc::new(AppState {
         engine,
         channels,
+        readiness,
         started_at: Utc::now(),
     });
 
@@ -34,18 +42,29 @@ pub fn router(engine: Arc<MemoryEngine>, channels: Arc<ChannelHub>) -> Router {
         .route("/api/v1/memories/{id}", put(update_memory))
         .route("/api/v1/memories/{id}", delete(invalidate_memory))
         .route("/api/v1/memories/{id}/history", get(memory_history))
+        .route("/api/v1/memories/{id}/entities", get(memory_entities))
         .route("/api/v1/memories", get(list_memories))
         // Search
         .route("/api/v1/search", post(search))
         .route("/api/v1/search/bulk", post(bulk_search))
         // Extraction
         .route("/api/v1/extract", post(extract))
+        // Session & episode endpoints
+        .route("/api/v1/sessions", get(list_sessions))
+        .route("/api/v1/sessions/start", post(start_session))
+        .route("/api/v1/sessions/{session_id}/end", post(end_session))
+        .route("/api/v1/episodes", get(list_episodes))
+        .route("/api/v1/episodes/search", post(search_episodes))
+        .route("/api/v1/episodes/{id}", get(get_episode))
         // Knowledge Graph
         .route("/api/v1/entities", post(add_entity))
         .route("/api/v1/entities/{id}", get(get_entity))
+        .route("/api/v1/entities/{id}", put(update_entity))
         .route("/api/v1/entities/find", post(find_entity))
         .route("/api/v1/relationships", post(add_relationship))
+        .route("/api/v1/relationships/{id}", delete(invalidate_relationship))
         .route("/api/v1/entities/{id}/relationships", get(entity_relationships))
+        .route("/api/v1/entities/{id}/memories", get(entity_memories))
         .route("/api/v1/graph/traverse", post(graph_traverse))
         // Channels
         .route("/api/v1/channels", post(create_channel))
@@ -54,12 +73,24 @@ pub fn router(engine: Arc<MemoryEngine>, channels: Arc<ChannelHub>) -> Router {
         // Tasks
         .route("/api/v1/tasks", post(create_task))
         .route("/api/v1/tasks", get(list_tasks))
+        .route("/api/v1/tasks/claim-next", post(claim_next_task))
         .route("/api/v1/tasks/{id}", get(get_task))
         .route("/api/v1/tasks/{id}/claim", post(claim_task))
         .route("/api/v1/tasks/{id}/start", post(start_task))
         .route("/api/v1/tasks/{id}/complete", post(complete_task))
         .route("/api/v1/tasks/{id}/fail", post(fail_task))
+        .route("/api/v1/tasks/{id}/cancel", post(cancel_task))
+        .route("/api/v1/tasks/{id}/heartbeat", post(task_heartbeat))
+        .route("/api/v1/tasks/{id}/progress", post(task_progress))
+        .route("/api/v1/tasks/{id}/reassign", post(reassign_task))
         .route("/api/v1/tasks/{id}/events", get(task_events))
+        .route("/api/v1/tasks/{id}/graph", get(task_graph))
+        .route("/api/v1/tasks/{id}/subtasks", get(task_subtasks))
+        .route("/api/v1/task-templates", post(create_task_template))
+        .route("/api/v1/task-templates", get(list_task_templates))
+        .route("/api/v1/task-templates/{id}", get(get_task_template))
+        .route("/api/v1/task-templates/{id}", put(update_task_template))
+        .route("/api/v1/task-templates/{id}", delete(delete_task_template))
         // Agents
         .route("/api/v1/agents/register", post(register_agent))
         .route("/api/v1/agents", get(list_agents))
@@ -73,13 +104,28 @@ pub fn router(engine: Arc<MemoryEngine>, channels: Arc<ChannelHub>) -> Router {
         .route("/api/v1/system/topology", get(system_topology))
         .route("/api/v1/system/health", get(system_health))
         .route("/api/v1/system/embedding", get(system_embedding))
+        .route("/api/v1/system/embedding/reindex", post(system_embedding_reindex))
+        // Catch-up feed for bootstrapping nodes
+        .route("/api/v1/replication/snapshot", get(replication_snapshot))
+        .route("/api/v1/replication/events", get(replication_events))
         // Status
         .route("/api/v1/status", get(status))
         .route("/health", get(health))
+        .route("/health/ready", get(health_ready))
         .layer(CorsLayer::permissive())
         .with_state(state)
 }
 
+/// Answer a write the engine refused with `rejected`, and one that could not
+/// be appended to the write-ahead log or queued for replication with 503.
+fn write_error(e: WriteError, rejected: StatusCode) -> (StatusCode, String) {
+    let status = match e {
+        WriteError::Rejected(_) => rejected,
+        WriteError::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    (status, e.to_string())
+}
+
 // ============================================================================
 // Memory Endpoints
 // ============================================================================
@@ -87,8 +133,11 @@ pub fn router(engine: Arc<MemoryEngine>, channels: Arc<ChannelHub>) -> Router {
 async fn add_memory(
     State(state): State<Arc<AppState>>,
     Json(req): Json<AddMemoryRequest>,
-) -> (StatusCode, Json<Memory>) {
-    let memory = state.engine.add_memory(req);
+) -> Result<(StatusCode, Json<Memory>), (StatusCode, String)> {
+    let memory = state
+        .engine
+        .add_memory(req)
+        .map_err(|e| write_error(e, StatusCode::BAD_REQUEST))?;
 
     // Broadcast to relevant channels
     if let Some(ref user_id) = memory.user_id {
@@ -111,14 +160,17 @@ async fn add_memory(
         },
     );
 
-    (StatusCode::CREATED, Json(memory))
+    Ok((StatusCode::CREATED, Json(memory)))
 }
 
 async fn add_memories_bulk(
     State(state): State<Arc<AppState>>,
     Json(req): Json<BulkAddMemoryRequest>,
-) -> (StatusCode, Json<Vec<Memory>>) {
-    let memories = state.engine.add_memories_bulk(req.memories);
+) -> Result<(StatusCode, Json<Vec<Memory>>), (StatusCode, String)> {
+    let memories = state
+        .engine
+        .add_memories_bulk(req.memories)
+        .map_err(|e| write_error(e, StatusCode::BAD_REQUEST))?;
 
     // Broadcast to global channel
     for memory in &memories {
@@ -131,7 +183,7 @@ async fn add_memories_bulk(
         );
     }
 
-    (StatusCode::CREATED, Json(memories))
+    Ok((StatusCode::CREATED, Json(memories)))
 }
 
 async fn get_memory(
@@ -153,6 +205,7 @@ async fn update_memory(
     let memory = state
         .engine
         .update_memory(id, req, "api")
+        .map_err(|e| write_error(e, StatusCode::BAD_REQUEST).0)?
         .ok_or(StatusCode::NOT_FOUND)?;
 
     // Broadcast update
@@ -189,6 +242,7 @@ async fn invalidate_memory(
     let memory = state
         .engine
         .invalidate_memory(id, &req.reason, &req.changed_by)
+        .map_err(|e| write_error(e, StatusCode::BAD_REQUEST).0)?
         .ok_or(StatusCode::NOT_FOUND)?;
 
     // Broadcast invalidation
@@ -214,6 +268,16 @@ async fn memory_history(
     Json(state.engine.get_memory_history(id))
 }
 
+async fn memory_entities(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u64>,
+) -> Result<Json<Vec<Entity>>, StatusCode> {
+    if state.engine.get_memory(id).is_none() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    Ok(Json(state.engine.get_memory_entities(id)))
+}
+
 #[derive(serde::Deserialize)]
 struct ListMemoriesQuery {
     agent_id: Option<String>,
@@ -240,9 +304,14 @@ async fn list_memories(
 async fn search(
     State(state): State<Arc<AppState>>,
     Json(req): Json<SearchRequest>,
-) -> Json<Vec<SearchResult>> {
+) -> Result<Json<Vec<SearchResult>>, (StatusCode, String)> {
     // Use hybrid search (includes vector similarity when available)
-    Json(state.engine.search_hybrid(&req).await)
+    state
+        .engine
+        .search_hybrid(&req)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))
 }
 
 // ============================================================================
@@ -269,12 +338,15 @@ async fn bulk_search(
         handles.push(tokio::spawn(async move {
             let _permit = sem.acquire().await.unwrap();
             let query_text = query.query.clone();
-            let results = engine.search_hybrid(&query).await;
+            let (results, error) = match engine.search_hybrid(&query).await {
+                Ok(results) => (results, None),
+                Err(e) => (vec![], Some(e.to_string())),
+            };
             BulkSearchQueryResult {
                 query_index: idx,
                 query: query_text,
                 results,
-                error: None,
+                error,
             }
         }));
     }
@@ -327,6 +399,9 @@ async fn extract(
             }
             Ok((StatusCode::OK, Json(response)))
         }
+        Err(e) if matches!(e.downcast_ref(), Some(WriteError::Unavailable)) => {
+            Err((StatusCode::SERVICE_UNAVAILABLE, e.to_string()))
+        }
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
             format!("Extraction failed: {}", e),
@@ -334,6 +409,72 @@ async fn extract(
     }
 }
 
+// ============================================================================
+// Session & Episode Handlers
+// ============================================================================
+
+#[derive(serde::Deserialize)]
+struct ListSessionsQuery {
+    agent_id: Option<String>,
+}
+
+async fn list_sessions(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Query(query): axum::extract::Query<ListSessionsQuery>,
+) -> Json<Vec<Session>> {
+    Json(state.engine.list_sessions(query.agent_id.as_deref()))
+}
+
+async fn start_session(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<StartSessionRequest>,
+) -> Result<Json<Session>, (StatusCode, String)> {
+    state
+        .engine
+        .start_session(req)
+        .map(Json)
+        .map_err(|e| write_error(e, StatusCode::BAD_REQUEST))
+}
+
+async fn end_session(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+    body: Option<Json<EndSessionRequest>>,
+) -> Result<Json<Episode>, (StatusCode, String)> {
+    let req = body.map(|Json(req)| req).unwrap_or_default();
+    state
+        .engine
+        .end_session(&session_id, req)
+        .await
+        .map(Json)
+        .map_err(|e| write_error(e, StatusCode::NOT_FOUND))
+}
+
+async fn list_episodes(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Query(query): axum::extract::Query<EpisodeQuery>,
+) -> Json<Vec<Episode>> {
+    Json(state.engine.list_episodes(&query))
+}
+
+async fn get_episode(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u64>,
+) -> Result<Json<Episode>, StatusCode> {
+    state
+        .engine
+        .get_episode(id)
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn search_episodes(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<EpisodeSearchRequest>,
+) -> Json<Vec<EpisodeSearchResult>> {
+    Json(state.engine.search_episodes(&req))
+}
+
 // ============================================================================
 // Knowledge Graph
 // ============================================================================
@@ -341,8 +482,12 @@ async fn extract(
 async fn add_entity(
     State(state): State<Arc<AppState>>,
     Json(req): Json<AddEntityRequest>,
-) -> (StatusCode, Json<Entity>) {
-    (StatusCode::CREATED, Json(state.engine.add_entity(req)))
+) -> Result<(StatusCode, Json<Entity>), (StatusCode, String)> {
+    let entity = state
+        .engine
+        .add_entity(req)
+        .map_err(|e| write_error(e, StatusCode::BAD_REQUEST))?;
+    Ok((StatusCode::CREATED, Json(entity)))
 }
 
 async fn get_entity(
@@ -356,6 +501,19 @@ async fn get_entity(
         .ok_or(StatusCode::NOT_FOUND)
 }
 
+async fn update_entity(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u64>,
+    Json(req): Json<UpdateEntityRequest>,
+) -> Result<Json<Entity>, StatusCode> {
+    state
+        .engine
+        .update_entity(id, req)
+        .map_err(|e| write_error(e, StatusCode::BAD_REQUEST).0)?
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
 #[derive(serde::Deserialize)]
 struct FindEntityRequest {
     name: String,
@@ -375,11 +533,24 @@ async fn find_entity(
 async fn add_relationship(
     State(state): State<Arc<AppState>>,
     Json(req): Json<AddRelationshipRequest>,
-) -> (StatusCode, Json<Relationship>) {
-    (
-        StatusCode::CREATED,
-        Json(state.engine.add_relationship(req)),
-    )
+) -> Result<(StatusCode, Json<Relationship>), (StatusCode, String)> {
+    let relationship = state
+        .engine
+        .add_relationship(req)
+        .map_err(|e| write_error(e, StatusCode::BAD_REQUEST))?;
+    Ok((StatusCode::CREATED, Json(relationship)))
+}
+
+async fn invalidate_relationship(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u64>,
+) -> Result<Json<Relationship>, StatusCode> {
+    state
+        .engine
+        .invalidate_relationship(id)
+        .map_err(|e| write_error(e, StatusCode::BAD_REQUEST).0)?
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
 }
 
 async fn entity_relationships(
@@ -389,6 +560,16 @@ async fn entity_relationships(
     Json(state.engine.get_entity_relationships(id))
 }
 
+async fn entity_memories(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u64>,
+) -> Result<Json<Vec<Memory>>, StatusCode> {
+    if state.engine.get_entity(id).is_none() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    Ok(Json(state.engine.get_entity_memories(id)))
+}
+
 #[derive(serde::Deserialize)]
 struct TraverseRequest {
     entity_id: u64,
@@ -414,8 +595,12 @@ async fn graph_traverse(
 async fn create_channel(
     State(state): State<Arc<AppState>>,
     Json(req): Json<CreateChannelRequest>,
-) -> (StatusCode, Json<Channel>) {
-    (StatusCode::CREATED, Json(state.channels.create_channel(req)))
+) -> Result<(StatusCode, Json<Channel>), (StatusCode, String)> {
+    let channel = state
+        .channels
+        .create_channel(req)
+        .map_err(|e| (StatusCode::SERVICE_UNAVAILABLE, e.to_string()))?;
+    Ok((StatusCode::CREATED, Json(channel)))
 }
 
 async fn list_channels(State(state): State<Arc<AppState>>) -> Json<Vec<Channel>> {
@@ -455,11 +640,12 @@ async fn share_to_channel(
 async fn register_agent(
     State(state): State<Arc<AppState>>,
     Json(req): Json<RegisterAgentRequest>,
-) -> (StatusCode, Json<Agent>) {
-    (
-        StatusCode::CREATED,
-        Json(state.engine.register_agent(req)),
-    )
+) -> Result<(StatusCode, Json<Agent>), (StatusCode, String)> {
+    let agent = state
+        .engine
+        .register_agent(req)
+        .map_err(|e| write_error(e, StatusCode::BAD_REQUEST))?;
+    Ok((StatusCode::CREATED, Json(agent)))
 }
 
 async fn list_agents(State(state): State<Arc<AppState>>) -> Json<Vec<Agent>> {
@@ -469,9 +655,12 @@ async fn list_agents(State(state): State<Arc<AppState>>) -> Json<Vec<Agent>> {
 async fn agent_heartbeat(
     State(state): State<Arc<AppState>>,
     Path(agent_id): Path<String>,
-) -> StatusCode {
-    state.engine.heartbeat_agent(&agent_id);
-    StatusCode::OK
+) -> Result<StatusCode, (StatusCode, String)> {
+    state
+        .engine
+        .heartbeat_agent(&agent_id)
+        .map_err(|e| write_error(e, StatusCode::BAD_REQUEST))?;
+    Ok(StatusCode::OK)
 }
 
 // ============================================================================
@@ -481,8 +670,11 @@ async fn agent_heartbeat(
 async fn create_task(
     State(state): State<Arc<AppState>>,
     Json(req): Json<CreateTaskRequest>,
-) -> (StatusCode, Json<Task>) {
-    let task = state.engine.create_task(req);
+) -> Result<(StatusCode, Json<Task>), (StatusCode, String)> {
+    let task = state
+        .engine
+        .create_task(req)
+        .map_err(|e| write_error(e, StatusCode::BAD_REQUEST))?;
 
     // Broadcast to tasks channel
     state.channels.broadcast_to_channel_by_name(
@@ -490,7 +682,7 @@ async fn create_task(
         WsServerMessage::TaskCreated { task: task.clone() },
     );
 
-    (StatusCode::CREATED, Json(task))
+    Ok((StatusCode::CREATED, Json(task)))
 }
 
 #[derive(serde::Deserialize)]
@@ -504,6 +696,7 @@ async fn list_tasks(
     axum::extract::Query(query): axum::extract::Query<ListTasksQuery>,
 ) -> Json<Vec<Task>> {
     let status = query.status.as_deref().and_then(|s| match s {
+        "blocked" => Some(TaskStatus::Blocked),
         "pending" => Some(TaskStatus::Pending),
         "claimed" => Some(TaskStatus::Claimed),
         "in_progress" => Some(TaskStatus::InProgress),
@@ -540,7 +733,38 @@ async fn claim_task(
             );
             Ok(Json(task))
         }
-        Err(e) => Err((StatusCode::CONFLICT, e)),
+        Err(e) => Err(write_error(e, StatusCode::CONFLICT)),
+    }
+}
+
+/// Longest a claim-next request may long-poll.
+const MAX_CLAIM_WAIT_SECS: u64 = 60;
+
+/// 200 with the claimed task, or 204 when nothing was claimable in time.
+async fn claim_next_task(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<ClaimNextTaskRequest>,
+) -> axum::response::Response {
+    let wait = std::time::Duration::from_secs(req.wait_secs.min(MAX_CLAIM_WAIT_SECS));
+    let claimed = if wait.is_zero() {
+        state.engine.claim_next_task(&req.agent_id, &req.capabilities)
+    } else {
+        state
+            .engine
+            .claim_next_task_wait(&req.agent_id, &req.capabilities, wait)
+            .await
+    };
+
+    match claimed {
+        Ok(Some(task)) => {
+            state.channels.broadcast_to_channel_by_name(
+                "tasks",
+                WsServerMessage::TaskClaimed { task: task.clone() },
+            );
+            Json(task).into_response()
+        }
+        Ok(None) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => write_error(e, StatusCode::CONFLICT).into_response(),
     }
 }
 
@@ -557,7 +781,7 @@ async fn start_task(
             );
             Ok(Json(task))
         }
-        Err(e) => Err((StatusCode::CONFLICT, e)),
+        Err(e) => Err(write_error(e, StatusCode::CONFLICT)),
     }
 }
 
@@ -566,15 +790,25 @@ async fn complete_task(
     Path(id): Path<u64>,
     Json(req): Json<CompleteTaskRequest>,
 ) -> Result<Json<Task>, (StatusCode, String)> {
-    match state.engine.complete_task(id, &req.agent_id, req.result) {
-        Ok(task) => {
+    match state.engine.complete_task(id, req) {
+        Ok((task, follow_ups)) => {
+            for memory in task.result_memory_ids.iter().filter_map(|m| state.engine.get_memory(*m)) {
+                state.channels.broadcast_to_channel_by_name(
+                    "global",
+                    WsServerMessage::MemoryAdded {
+                        channel: "global".into(),
+                        memory,
+                    },
+                );
+            }
             state.channels.broadcast_to_channel_by_name(
                 "tasks",
                 WsServerMessage::TaskCompleted { task: task.clone() },
             );
+            scheduler::announce_follow_ups(&state.channels, follow_ups);
             Ok(Json(task))
         }
-        Err(e) => Err((StatusCode::CONFLICT, e)),
+        Err(e) => Err(write_error(e, StatusCode::CONFLICT)),
     }
 }
 
@@ -584,14 +818,82 @@ async fn fail_task(
     Json(req): Json<FailTaskRequest>,
 ) -> Result<Json<Task>, (StatusCode, String)> {
     match state.engine.fail_task(id, &req.agent_id, req.reason) {
-        Ok(task) => {
+        Ok((task, follow_ups)) => {
             state.channels.broadcast_to_channel_by_name(
                 "tasks",
                 WsServerMessage::TaskFailed { task: task.clone() },
             );
+            scheduler::announce_follow_ups(&state.channels, follow_ups);
             Ok(Json(task))
         }
-        Err(e) => Err((StatusCode::CONFLICT, e)),
+        Err(e) => Err(write_error(e, StatusCode::CONFLICT)),
+    }
+}
+
+async fn task_heartbeat(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u64>,
+    Json(req): Json<ClaimTaskRequest>,
+) -> Result<Json<Task>, (StatusCode, String)> {
+    state
+        .engine
+        .heartbeat_task(id, &req.agent_id)
+        .map(Json)
+        .map_err(|e| write_error(e, StatusCode::CONFLICT))
+}
+
+async fn task_progress(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u64>,
+    Json(req): Json<TaskProgressRequest>,
+) -> Result<Json<Task>, (StatusCode, String)> {
+    match state.engine.report_task_progress(id, req) {
+        Ok(task) => {
+            state.channels.broadcast_to_channel_by_name(
+                "tasks",
+                WsServerMessage::TaskUpdated { task: task.clone() },
+            );
+            Ok(Json(task))
+        }
+        Err(e) => Err(write_error(e, StatusCode::CONFLICT)),
+    }
+}
+
+async fn cancel_task(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u64>,
+    Json(req): Json<CancelTaskRequest>,
+) -> Result<Json<Task>, (StatusCode, String)> {
+    match state.engine.cancel_task(id, &req.agent_id, req.reason) {
+        Ok((task, follow_ups)) => {
+            state.channels.broadcast_to_channel_by_name(
+                "tasks",
+                WsServerMessage::TaskCancelled { task: task.clone() },
+            );
+            scheduler::announce_follow_ups(&state.channels, follow_ups);
+            Ok(Json(task))
+        }
+        Err(e) => Err(write_error(e, StatusCode::CONFLICT)),
+    }
+}
+
+async fn reassign_task(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u64>,
+    Json(req): Json<ReassignTaskRequest>,
+) -> Result<Json<Task>, (StatusCode, String)> {
+    match state.engine.reassign_task(id, &req.agent_id, &req.new_agent_id, req.reason) {
+        Ok((task, previous_agent)) => {
+            state.channels.broadcast_to_channel_by_name(
+                "tasks",
+                WsServerMessage::TaskReassigned {
+                    task: task.clone(),
+                    previous_agent,
+                },
+            );
+            Ok(Json(task))
+        }
+        Err(e) => Err(write_error(e, StatusCode::CONFLICT)),
     }
 }
 
@@ -602,6 +904,82 @@ async fn task_events(
     Json(state.engine.get_task_events(id))
 }
 
+async fn task_subtasks(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u64>,
+) -> Result<Json<Vec<Task>>, StatusCode> {
+    state.engine.get_task(id).ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(state.engine.list_subtasks(id)))
+}
+
+async fn task_graph(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u64>,
+) -> Result<Json<TaskGraph>, StatusCode> {
+    state
+        .engine
+        .get_task_graph(id)
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+// ============================================================================
+// Task Templates
+// ============================================================================
+
+async fn create_task_template(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<CreateTaskTemplateRequest>,
+) -> Result<(StatusCode, Json<TaskTemplate>), (StatusCode, String)> {
+    state
+        .engine
+        .create_task_template(req)
+        .map(|template| (StatusCode::CREATED, Json(template)))
+        .map_err(|e| write_error(e, StatusCode::BAD_REQUEST))
+}
+
+async fn list_task_templates(State(state): State<Arc<AppState>>) -> Json<Vec<TaskTemplate>> {
+    Json(state.engine.list_task_templates())
+}
+
+async fn get_task_template(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u64>,
+) -> Result<Json<TaskTemplate>, StatusCode> {
+    state
+        .engine
+        .get_task_template(id)
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn update_task_template(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u64>,
+    Json(req): Json<UpdateTaskTemplateRequest>,
+) -> Result<Json<TaskTemplate>, (StatusCode, String)> {
+    if state.engine.get_task_template(id).is_none() {
+        return Err((StatusCode::NOT_FOUND, format!("Task template {} not found", id)));
+    }
+    state
+        .engine
+        .update_task_template(id, req)
+        .map(Json)
+        .map_err(|e| write_error(e, StatusCode::BAD_REQUEST))
+}
+
+async fn delete_task_template(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u64>,
+) -> Result<Json<TaskTemplate>, StatusCode> {
+    state
+        .engine
+        .delete_task_template(id)
+        .map_err(|e| write_error(e, StatusCode::BAD_REQUEST).0)?
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
 // ============================================================================
 // WebSocket
 // ============================================================================
@@ -650,11 +1028,13 @@ async fn system_config(State(state): State<Arc<AppState>>) -> Json<SystemConfigR
 async fn system_topology(State(state): State<Arc<AppState>>) -> Json<SystemTopologyResponse> {
     let config = state.engine.config();
     Json(SystemTopologyResponse {
-        node_id: hostname(),
+        node_id: config.node_id.clone(),
+        node_number: config.node_number,
         listen_addr: config.listen_addr.clone(),
         rtdb_url: config.rtdb_url.clone(),
         replication_enabled: config.replication_enabled,
         role: if config.replication_enabled { "replica".into() } else { "standalone".into() },
+        replication: state.engine.replication_status(),
     })
 }
 
@@ -685,10 +1065,53 @@ async fn system_embedding(State(state): State<Arc<AppState>>) -> Json<SystemEmbe
     Json(state.engine.embedding_info())
 }
 
-fn hostname() -> String {
-    std::env::var("HOSTNAME")
-        .or_else(|_| std::env::var("COMPUTERNAME"))
-        .unwrap_or_else(|_| "unknown".into())
+async fn system_embedding_reindex(
+    State(state): State<Arc<AppState>>,
+) -> Result<(StatusCode, Json<EmbeddingReindexStatus>), (StatusCode, String)> {
+    state
+        .engine
+        .start_embedding_reindex("requested via admin endpoint")
+        .map(|status| (StatusCode::ACCEPTED, Json(status)))
+        .map_err(|e| (StatusCode::CONFLICT, e))
+}
+
+// ============================================================================
+// Replication Catch-up
+// ============================================================================
+
+async fn replication_snapshot(
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let mut snapshot = state.engine.create_snapshot();
+    state.channels.fill_snapshot(&mut snapshot);
+    let bytes = persistence::encode_snapshot(&snapshot, persistence::SnapshotFormat::Binary)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(([(axum::http::header::CONTENT_TYPE, "application/octet-stream")], bytes))
+}
+
+#[derive(serde::Deserialize)]
+struct ReplicationEventsQuery {
+    #[serde(default)]
+    after: u64,
+    limit: Option<usize>,
+}
+
+async fn replication_events(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Query(query): axum::extract::Query<ReplicationEventsQuery>,
+) -> Result<Json<persistence::EventPage>, (StatusCode, String)> {
+    let limit = query
+        .limit
+        .unwrap_or(persistence::BOOTSTRAP_PAGE)
+        .min(persistence::BOOTSTRAP_PAGE);
+    match state.engine.replication_events_after(query.after, limit) {
+        Ok(Some(page)) => Ok(Json(page)),
+        Ok(None) => Err((
+            StatusCode::GONE,
+            format!("Events after {} were truncated; fetch a newer snapshot", query.after),
+        )),
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e)),
+    }
 }
 
 // ============================================================================
@@ -699,6 +1122,13 @@ async fn status(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
     Json(state.engine.stats())
 }
 
-async fn health() -> &'static str {
-    "ok"
+async fn health(State(state): State<Arc<AppState>>) -> Json<HealthResponse> {
+    Json(state.readiness.report())
+}
+
+/// Readiness probe: 503 until the node has current state.
+async fn health_ready(State(state): State<Arc<AppState>>) -> (StatusCode, Json<HealthResponse>) {
+    let report = state.readiness.report();
+    let status = if report.ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (status, Json(report))
 }
@@ -1,8 +1,10 @@
+use crate::ids::IdAllocator;
+use crate::persistence::{ReplicationEvent, ReplicationSink, Snapshot};
 use crate::types::*;
 use dashmap::DashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
 use tokio::sync::broadcast;
-use tracing::info;
+use tracing::{error, info};
 
 /// Manages hivemind channels — pub/sub for real-time memory sharing between agents.
 pub struct ChannelHub {
@@ -11,23 +13,75 @@ pub struct ChannelHub {
     subscriptions: DashMap<u64, Vec<String>>, // channel_id -> [agent_ids]
     /// Broadcast senders per channel for WebSocket push.
     senders: DashMap<u64, broadcast::Sender<WsServerMessage>>,
-    next_id: AtomicU64,
+    channel_ids: IdAllocator,
     /// Active WebSocket connection counter.
     active_ws_connections: AtomicU64,
+    /// Replication events → write-ahead log + RaftTimeDB
+    replication: ReplicationSink,
 }
 
 impl ChannelHub {
     pub fn new() -> Self {
+        Self::for_node(0)
+    }
+
+    /// A hub minting channel IDs from the given node's range (see `ids`).
+    pub fn for_node(node_number: u16) -> Self {
         Self {
             channels: DashMap::new(),
             channel_by_name: DashMap::new(),
             subscriptions: DashMap::new(),
             senders: DashMap::new(),
-            next_id: AtomicU64::new(1),
+            channel_ids: IdAllocator::new(node_number),
             active_ws_connections: AtomicU64::new(0),
+            replication: ReplicationSink::default(),
+        }
+    }
+
+    /// Share the memory engine's replication sink so channel changes are logged too.
+    pub fn set_replication(&mut self, sink: ReplicationSink) {
+        self.replication = sink;
+    }
+
+    /// Restore channels and durable subscriptions from a snapshot (called at startup).
+    pub fn restore_from_snapshot(&self, snapshot: &Snapshot) {
+        for channel in &snapshot.channels {
+            self.insert_channel(channel.clone());
+        }
+        for (channel_id, agent_ids) in &snapshot.channel_subscriptions {
+            if self.channels.contains_key(channel_id) {
+                self.subscriptions.insert(*channel_id, agent_ids.clone());
+            }
+        }
+        info!(channels = self.channels.len(), "Channels restored from snapshot");
+    }
+
+    /// Add channel state to a snapshot created by the memory engine.
+    pub fn fill_snapshot(&self, snapshot: &mut Snapshot) {
+        snapshot.channels = self.list_channels();
+        snapshot.channel_subscriptions = self
+            .subscriptions
+            .iter()
+            .map(|s| (*s.key(), s.value().clone()))
+            .collect();
+    }
+
+    /// Apply a replicated channel creation (idempotent).
+    pub fn apply_channel_created(&self, channel: Channel) {
+        if !self.channels.contains_key(&channel.id) {
+            self.insert_channel(channel);
         }
     }
 
+    /// Register a channel with its broadcast sender and keep the ID counter ahead of it.
+    fn insert_channel(&self, channel: Channel) {
+        let (tx, _) = broadcast::channel(256);
+        self.senders.insert(channel.id, tx);
+        self.channel_by_name.insert(channel.name.clone(), channel.id);
+        self.channel_ids.observe(channel.id);
+        self.channels.insert(channel.id, channel);
+    }
+
     pub fn ws_connect(&self) {
         self.active_ws_connections.fetch_add(1, Ordering::Relaxed);
     }
@@ -40,33 +94,38 @@ impl ChannelHub {
         self.active_ws_connections.load(Ordering::Relaxed)
     }
 
-    pub fn create_channel(&self, req: CreateChannelRequest) -> Channel {
+    /// Create a channel, or return the existing one with that name. Fails if
+    /// the creation could not be logged, leaving no channel behind.
+    pub fn create_channel(&self, req: CreateChannelRequest) -> anyhow::Result<Channel> {
         // Return existing channel if name already taken
         if let Some(id) = self.channel_by_name.get(&req.name) {
             if let Some(ch) = self.channels.get(id.value()) {
-                return ch.clone();
+                return Ok(ch.clone());
             }
         }
 
-        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let id = self.channel_ids.next();
         let now = chrono::Utc::now();
 
         let channel = Channel {
             id,
-            name: req.name.clone(),
+            name: req.name,
             description: req.description,
             channel_type: req.channel_type,
             created_by: req.created_by,
             created_at: now,
         };
 
-        let (tx, _) = broadcast::channel(256);
-        self.senders.insert(id, tx);
-        self.channel_by_name.insert(req.name.clone(), id);
-        self.channels.insert(id, channel.clone());
+        let created = ReplicationEvent::ChannelCreated {
+            channel: channel.clone(),
+        };
+        if let Err(e) = self.replication.commit(&[created], || self.insert_channel(channel.clone())) {
+            error!(error = %e, "Failed to persist replication event");
+            return Err(e);
+        }
 
         info!(id, name = %channel.name, "Channel created");
-        channel
+        Ok(channel)
     }
 
     pub fn get_channel(&self, id: u64) -> Option<Channel> {
@@ -82,16 +141,49 @@ impl ChannelHub {
         self.channels.iter().map(|c| c.value().clone()).collect()
     }
 
+    /// Subscribe to a channel's live messages and record the subscription
+    /// durably. If it can't be logged it isn't recorded, but the live
+    /// receiver is still returned.
     pub fn subscribe(&self, channel_id: u64, agent_id: &str) -> Option<broadcast::Receiver<WsServerMessage>> {
         let sender = self.senders.get(&channel_id)?;
-        self.subscriptions
-            .entry(channel_id)
-            .or_default()
-            .push(agent_id.to_string());
+        if !self.is_subscriber(channel_id, agent_id)
+            && let Err(e) = self.replication.commit(
+                &[ReplicationEvent::ChannelSubscribed {
+                    channel_id,
+                    agent_id: agent_id.to_string(),
+                }],
+                || self.add_subscriber(channel_id, agent_id),
+            )
+        {
+            error!(error = %e, "Failed to persist replication event");
+        }
         info!(channel_id, agent_id, "Agent subscribed to channel");
         Some(sender.subscribe())
     }
 
+    /// Apply a replicated subscription (idempotent).
+    pub fn apply_channel_subscribed(&self, channel_id: u64, agent_id: &str) {
+        if self.channels.contains_key(&channel_id) {
+            self.add_subscriber(channel_id, agent_id);
+        }
+    }
+
+    fn is_subscriber(&self, channel_id: u64, agent_id: &str) -> bool {
+        self.subscriptions
+            .get(&channel_id)
+            .is_some_and(|subscribers| subscribers.iter().any(|a| a == agent_id))
+    }
+
+    /// Record a durable subscription. Returns false if the agent already had one.
+    fn add_subscriber(&self, channel_id: u64, agent_id: &str) -> bool {
+        let mut subscribers = self.subscriptions.entry(channel_id).or_default();
+        if subscribers.iter().any(|a| a == agent_id) {
+            return false;
+        }
+        subscribers.push(agent_id.to_string());
+        true
+    }
+
     pub fn subscribe_by_name(&self, channel_name: &str, agent_id: &str) -> Option<broadcast::Receiver<WsServerMessage>> {
         let id = *self.channel_by_name.get(channel_name)?;
         self.subscribe(id, agent_id)
@@ -130,7 +222,7 @@ mod tests {
             description: Some("Global channel".into()),
             channel_type: ChannelType::Public,
             created_by: "system".into(),
-        });
+        }).unwrap();
         assert_eq!(ch.name, "global");
 
         let channels = hub.list_channels();
@@ -145,13 +237,13 @@ mod tests {
             description: None,
             channel_type: ChannelType::Public,
             created_by: "agent-1".into(),
-        });
+        }).unwrap();
         let ch2 = hub.create_channel(CreateChannelRequest {
             name: "global".into(),
             description: Some("Different desc".into()),
             channel_type: ChannelType::Public,
             created_by: "agent-2".into(),
-        });
+        }).unwrap();
         assert_eq!(ch1.id, ch2.id);
         assert_eq!(hub.list_channels().len(), 1);
     }
@@ -164,7 +256,7 @@ mod tests {
             description: None,
             channel_type: ChannelType::Public,
             created_by: "system".into(),
-        });
+        }).unwrap();
 
         let mut rx = hub.subscribe(ch.id, "agent-1").unwrap();
 
@@ -193,7 +285,7 @@ mod tests {
             description: None,
             channel_type: ChannelType::Public,
             created_by: "system".into(),
-        });
+        }).unwrap();
 
         let found = hub.get_channel_by_name("project:rafttimedb").unwrap();
         assert_eq!(found.name, "project:rafttimedb");
@@ -209,7 +301,7 @@ mod tests {
             description: None,
             channel_type: ChannelType::Public,
             created_by: "system".into(),
-        });
+        }).unwrap();
 
         hub.subscribe(ch.id, "agent-1");
         hub.subscribe(ch.id, "agent-2");
@@ -218,5 +310,60 @@ mod tests {
         assert_eq!(subs.len(), 2);
         assert!(subs.contains(&"agent-1".to_string()));
         assert!(subs.contains(&"agent-2".to_string()));
+
+        // Re-subscribing (e.g. after a reconnect) doesn't duplicate the entry
+        hub.subscribe(ch.id, "agent-1");
+        assert_eq!(hub.get_subscribers(ch.id).len(), 2);
+    }
+
+    #[test]
+    fn test_channels_survive_snapshot() {
+        let hub = ChannelHub::new();
+        let ch = hub.create_channel(CreateChannelRequest {
+            name: "project:hivemind".into(),
+            description: None,
+            channel_type: ChannelType::Public,
+            created_by: "system".into(),
+        }).unwrap();
+        hub.subscribe(ch.id, "agent-1");
+
+        let mut snapshot = crate::persistence::Snapshot {
+            version: crate::persistence::Snapshot::CURRENT_VERSION,
+            created_at: chrono::Utc::now(),
+            memories: vec![],
+            entities: vec![],
+            relationships: vec![],
+            episodes: vec![],
+            sessions: vec![],
+            agents: vec![],
+            history: vec![],
+            channels: vec![],
+            channel_subscriptions: vec![],
+            tasks: vec![],
+            task_events: vec![],
+            task_templates: vec![],
+            mentions: vec![],
+            embeddings: None,
+            wal_seq: 0,
+            peer_seqs: vec![],
+        };
+        hub.fill_snapshot(&mut snapshot);
+
+        let restored = ChannelHub::new();
+        restored.restore_from_snapshot(&snapshot);
+
+        // Same ID, same name index, same durable subscribers
+        assert_eq!(restored.get_channel_by_name("project:hivemind").unwrap().id, ch.id);
+        assert_eq!(restored.get_subscribers(ch.id), vec!["agent-1".to_string()]);
+        assert!(restored.subscribe(ch.id, "agent-2").is_some());
+
+        // New channels don't reuse restored IDs
+        let next = restored.create_channel(CreateChannelRequest {
+            name: "other".into(),
+            description: None,
+            channel_type: ChannelType::Public,
+            created_by: "system".into(),
+        }).unwrap();
+        assert!(next.id > ch.id);
     }
 }
@@ -1,3 +1,34 @@
+use crate::types::RollupPolicy;
+use crate::vector_index::VectorIndexConfig;
+
+/// What the overdue sweeper does with a task once its deadline passes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverdueAction {
+    /// Record an `overdue` event and announce it; nothing else changes
+    #[default]
+    Notify,
+    /// Fail the task
+    Fail,
+    /// Raise its priority above every other open task so it is claimed next
+    Escalate,
+}
+
+impl std::str::FromStr for OverdueAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "notify" => Ok(OverdueAction::Notify),
+            "fail" => Ok(OverdueAction::Fail),
+            "escalate" => Ok(OverdueAction::Escalate),
+            other => Err(format!(
+                "Unknown overdue action '{}' (expected notify, fail or escalate)",
+                other
+            )),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct HiveMindConfig {
     pub listen_addr: String,
@@ -10,4 +41,23 @@ pub struct HiveMindConfig {
     pub data_dir: String,
     pub snapshot_interval: u64,
     pub replication_enabled: bool,
+    /// Identifies this node's writes in the replication stream
+    pub node_id: String,
+    /// Selects the range this node mints IDs from (0 = standalone, see `ids`)
+    pub node_number: u16,
+    /// BM25 term-frequency saturation for keyword search
+    pub bm25_k1: f32,
+    /// BM25 document-length normalisation (0 = none, 1 = full)
+    pub bm25_b: f32,
+    pub vector_index: VectorIndexConfig,
+    /// Link new memories to known entities whose names they contain
+    pub entity_name_matching: bool,
+    /// Seconds a claimed task stays leased without a heartbeat (0 = never expires)
+    pub task_lease_secs: u64,
+    /// Claims a task gets before an expired lease fails it (0 = unlimited)
+    pub task_max_attempts: u32,
+    /// What happens to tasks that miss their deadline
+    pub task_overdue_action: OverdueAction,
+    /// How parent tasks follow their subtasks unless they set their own policy
+    pub task_rollup_policy: RollupPolicy,
 }
@@ -1,10 +1,10 @@
 use crate::config::HiveMindConfig;
 use crate::types::*;
-use dashmap::DashMap;
-use rayon::prelude::*;
+use crate::vector_index::{self, AnnIndex, VectorIndexConfig};
+use chrono::Utc;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, RwLock};
 use tracing::{debug, info, warn};
 
 /// Vector embedding engine for semantic search.
@@ -14,13 +14,22 @@ use tracing::{debug, info, warn};
 ///   Uses all-MiniLM-L6-v2 (22M params, 384 dims) by default. CPU-only, ~22MB model.
 /// - **API**: External embedding APIs (OpenAI, Ollama, CodeGate, etc.)
 ///
-/// Uses a pool of model instances for concurrent embedding generation,
-/// and rayon parallel iterators for fast cosine similarity search.
+/// Uses a pool of model instances for concurrent embedding generation.
+/// Similarity search goes through a pluggable [`AnnIndex`] (HNSW by default,
+/// brute force for exact recall).
+///
+/// Vectors live in a [`VectorIndex`] tagged with the model that produced them.
+/// A reindex builds a replacement index in the background and swaps it in
+/// when done, so search keeps using the previous index until then.
 pub struct EmbeddingEngine {
     client: Client,
     config: EmbeddingConfig,
-    /// memory_id → embedding vector
-    vectors: DashMap<u64, Vec<f32>>,
+    /// Index that search is served from
+    index: RwLock<Arc<VectorIndex>>,
+    /// Replacement index being built by a running reindex
+    staging: RwLock<Option<Arc<VectorIndex>>>,
+    /// Progress of the current (or last) reindex
+    reindex: Mutex<EmbeddingReindexStatus>,
     /// Dimensionality (set after first embedding)
     dimensions: std::sync::atomic::AtomicU32,
     /// Pool of local ONNX embedding models for concurrent embedding.
@@ -32,6 +41,90 @@ pub struct EmbeddingEngine {
     pool_counter: std::sync::atomic::AtomicUsize,
 }
 
+/// Vectors produced by a single embedding model.
+struct VectorIndex {
+    provider: String,
+    model: String,
+    dimensions: std::sync::atomic::AtomicU32,
+    /// memory_id → embedding vector
+    vectors: Box<dyn AnnIndex>,
+    /// Memories removed since this index was created, so a reindex still
+    /// embedding them doesn't bring their vectors back
+    removed: Mutex<std::collections::HashSet<u64>>,
+}
+
+impl VectorIndex {
+    fn new(provider: &str, model: &str, index: &VectorIndexConfig) -> Self {
+        Self {
+            provider: provider.to_string(),
+            model: model.to_string(),
+            dimensions: std::sync::atomic::AtomicU32::new(0),
+            vectors: vector_index::build_index(index),
+            removed: Mutex::new(std::collections::HashSet::new()),
+        }
+    }
+
+    fn for_config(config: &EmbeddingConfig) -> Self {
+        Self::new(&config.provider, &config.model, &config.index)
+    }
+
+    fn insert(&self, memory_id: u64, vector: Vec<f32>) {
+        let mut removed = self.removed.lock().expect("removed set lock poisoned");
+        removed.remove(&memory_id);
+        self.dimensions
+            .store(vector.len() as u32, std::sync::atomic::Ordering::Relaxed);
+        self.vectors.insert(memory_id, vector);
+    }
+
+    /// Insert unless the memory already has a vector or was removed meanwhile.
+    fn insert_if_absent(&self, memory_id: u64, vector: Vec<f32>) {
+        let removed = self.removed.lock().expect("removed set lock poisoned");
+        if removed.contains(&memory_id) {
+            return;
+        }
+        let dimensions = vector.len() as u32;
+        if self.vectors.insert_if_absent(memory_id, vector) {
+            self.dimensions
+                .store(dimensions, std::sync::atomic::Ordering::Relaxed);
+        }
+    }
+
+    fn remove(&self, memory_id: u64) {
+        let mut removed = self.removed.lock().expect("removed set lock poisoned");
+        removed.insert(memory_id);
+        self.vectors.remove(memory_id);
+    }
+
+    /// Whether vectors in this index come from the configured model. An
+    /// index holding vectors of another size than the configured dimensions
+    /// doesn't match either.
+    fn matches(&self, config: &EmbeddingConfig) -> bool {
+        let dimensions = self.dimensions.load(std::sync::atomic::Ordering::Relaxed);
+        self.provider == config.provider
+            && self.model == config.model
+            && (dimensions == 0 || config.dimensions.is_none_or(|d| d == dimensions))
+    }
+
+    fn label(&self) -> String {
+        format!("{}:{}", self.provider, self.model)
+    }
+}
+
+/// Persisted vector index, tagged with the model that produced it.
+///
+/// Stored inside the snapshot so semantic search works immediately after a
+/// restart. Vectors from a different provider/model are discarded on restore.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingSnapshot {
+    pub provider: String,
+    pub model: String,
+    pub dimensions: u32,
+    pub vectors: Vec<(u64, Vec<f32>)>,
+}
+
+/// Number of memories embedded per `embed_batch` call during backfill and reindex.
+pub const BACKFILL_BATCH_SIZE: usize = 64;
+
 #[derive(Debug, Clone)]
 pub struct EmbeddingConfig {
     pub provider: String,
@@ -42,6 +135,8 @@ pub struct EmbeddingConfig {
     pub cache_dir: Option<String>,
     /// Number of model instances in the pool (default: num_cpus / 2, min 2)
     pub pool_size: usize,
+    /// Nearest-neighbour index used for similarity search
+    pub index: VectorIndexConfig,
 }
 
 impl EmbeddingConfig {
@@ -87,6 +182,7 @@ impl EmbeddingConfig {
             dimensions: None,
             cache_dir: Some(format!("{}/embeddings", config.data_dir)),
             pool_size,
+            index: config.vector_index.clone(),
         }
     }
 }
@@ -237,10 +333,25 @@ impl EmbeddingEngine {
             Vec::new()
         };
 
+        let index = Arc::new(VectorIndex::for_config(&config));
+        let reindex = EmbeddingReindexStatus {
+            state: ReindexState::Idle,
+            reason: None,
+            from_model: None,
+            to_model: format!("{}:{}", config.provider, config.model),
+            total: 0,
+            processed: 0,
+            failed: 0,
+            started_at: None,
+            finished_at: None,
+        };
+
         Self {
             client: Client::new(),
             config,
-            vectors: DashMap::new(),
+            index: RwLock::new(index),
+            staging: RwLock::new(None),
+            reindex: Mutex::new(reindex),
             dimensions: std::sync::atomic::AtomicU32::new(0),
             #[cfg(feature = "local-embeddings")]
             model_pool,
@@ -386,7 +497,7 @@ impl EmbeddingEngine {
     /// Index a memory — generate and store its embedding.
     pub async fn index_memory(&self, memory: &Memory) -> anyhow::Result<()> {
         let embedding = self.embed_text(&memory.content).await?;
-        self.vectors.insert(memory.id, embedding);
+        self.store_vector(memory.id, embedding);
         debug!(memory_id = memory.id, "Memory indexed");
         Ok(())
     }
@@ -401,16 +512,258 @@ impl EmbeddingEngine {
         let embeddings = self.embed_batch(&texts).await?;
 
         for (memory, embedding) in memories.iter().zip(embeddings) {
-            self.vectors.insert(memory.id, embedding);
+            self.store_vector(memory.id, embedding);
         }
 
         info!(count = memories.len(), "Batch indexed memories");
         Ok(())
     }
 
+    /// Index memories in batches of `batch_size`, continuing past failed batches.
+    ///
+    /// Returns the number of memories successfully indexed.
+    pub async fn index_in_batches(&self, memories: &[Memory], batch_size: usize) -> usize {
+        let mut indexed = 0;
+        for batch in memories.chunks(batch_size.max(1)) {
+            match self.index_memories(batch).await {
+                Ok(()) => indexed += batch.len(),
+                Err(e) => warn!(count = batch.len(), error = %e, "Failed to index embedding batch"),
+            }
+        }
+        indexed
+    }
+
+    /// Store a vector from the current model in every index that accepts it:
+    /// the active index (unless it belongs to another model) and the staging
+    /// index of a running reindex.
+    fn store_vector(&self, memory_id: u64, vector: Vec<f32>) {
+        let active = self.active_index();
+        if let Some(staging) = self.staging_index() {
+            staging.insert(memory_id, vector.clone());
+        }
+        if active.matches(&self.config) {
+            active.insert(memory_id, vector);
+        }
+    }
+
+    fn active_index(&self) -> Arc<VectorIndex> {
+        self.index.read().expect("vector index lock poisoned").clone()
+    }
+
+    fn staging_index(&self) -> Option<Arc<VectorIndex>> {
+        self.staging.read().expect("vector index lock poisoned").clone()
+    }
+
+    /// Export the vector index for persistence.
+    pub fn snapshot_vectors(&self) -> EmbeddingSnapshot {
+        let index = self.active_index();
+        EmbeddingSnapshot {
+            provider: index.provider.clone(),
+            model: index.model.clone(),
+            dimensions: index.dimensions.load(std::sync::atomic::Ordering::Relaxed),
+            vectors: index.vectors.vectors(),
+        }
+    }
+
+    /// Restore a persisted vector index, replacing the active one.
+    ///
+    /// Returns the number of vectors restored. Vectors whose length doesn't
+    /// match the recorded dimensions are skipped. If the snapshot was produced
+    /// by a different provider or model it is kept as-is and
+    /// [`needs_reindex`](Self::needs_reindex) reports true.
+    pub fn restore_vectors(&self, snapshot: EmbeddingSnapshot) -> usize {
+        // The index (e.g. the HNSW graph) is rebuilt from scratch, before serving
+        let start = std::time::Instant::now();
+        let index = VectorIndex::new(&snapshot.provider, &snapshot.model, &self.config.index);
+        for (memory_id, vector) in snapshot.vectors {
+            if vector.len() == snapshot.dimensions as usize {
+                index.insert(memory_id, vector);
+            }
+        }
+        let restored = index.vectors.len();
+        info!(
+            vectors = restored,
+            index = ?self.config.index.kind,
+            elapsed_ms = start.elapsed().as_millis() as u64,
+            "Vector index rebuilt"
+        );
+
+        if !index.matches(&self.config) {
+            info!(
+                stored = %index.label(),
+                stored_dimensions = snapshot.dimensions,
+                current = %format!("{}:{}", self.config.provider, self.config.model),
+                current_dimensions = ?self.config.dimensions,
+                "Persisted vectors were produced by a different embedding model"
+            );
+        } else if restored > 0 {
+            self.dimensions
+                .store(snapshot.dimensions, std::sync::atomic::Ordering::Relaxed);
+        }
+
+        *self.index.write().expect("vector index lock poisoned") = Arc::new(index);
+        restored
+    }
+
+    /// Whether the active index was produced by a different model than the
+    /// configured one and must be rebuilt.
+    pub fn needs_reindex(&self) -> bool {
+        !self.active_index().matches(&self.config)
+    }
+
+    /// Whether the active index holds vectors of another size than the
+    /// configured model produces now, e.g. because the model behind the same
+    /// name changed. Embeds a short probe text to find out.
+    pub async fn dimensions_changed(&self) -> anyhow::Result<bool> {
+        let index = self.active_index();
+        let stored = index.dimensions.load(std::sync::atomic::Ordering::Relaxed);
+        if stored == 0 || !index.matches(&self.config) {
+            return Ok(false);
+        }
+        let probe = self.embed_text("dimension probe").await?;
+        Ok(probe.len() as u32 != stored)
+    }
+
+    /// Start re-embedding `memories` into a fresh index in the background.
+    ///
+    /// Search keeps using the current index until every batch has been
+    /// processed, then the new index is swapped in. Fails if a reindex is
+    /// already running or no embedding backend is available.
+    pub fn start_reindex(
+        self: &Arc<Self>,
+        memories: Vec<Memory>,
+        reason: &str,
+    ) -> Result<EmbeddingReindexStatus, String> {
+        if !self.is_available() {
+            return Err("Embeddings are not available".into());
+        }
+
+        let status = {
+            let mut status = self.reindex.lock().expect("reindex status lock poisoned");
+            if status.state == ReindexState::Running {
+                return Err("A reindex is already running".into());
+            }
+            *status = EmbeddingReindexStatus {
+                state: ReindexState::Running,
+                reason: Some(reason.to_string()),
+                from_model: Some(self.active_index().label()),
+                to_model: format!("{}:{}", self.config.provider, self.config.model),
+                total: memories.len(),
+                processed: 0,
+                failed: 0,
+                started_at: Some(Utc::now()),
+                finished_at: None,
+            };
+            status.clone()
+        };
+
+        *self.staging.write().expect("vector index lock poisoned") =
+            Some(Arc::new(VectorIndex::for_config(&self.config)));
+
+        info!(
+            total = memories.len(),
+            from = status.from_model.as_deref().unwrap_or_default(),
+            to = %status.to_model,
+            reason,
+            "Starting embedding reindex"
+        );
+
+        let engine = self.clone();
+        tokio::spawn(async move {
+            engine.run_reindex(memories, BACKFILL_BATCH_SIZE).await;
+        });
+        Ok(status)
+    }
+
+    /// Embed `memories` batch by batch into the staging index, then swap it in.
+    ///
+    /// Batches that fail are retried once, one memory at a time, after the
+    /// first pass; memories that still fail are reported and left to the
+    /// next backfill.
+    async fn run_reindex(&self, memories: Vec<Memory>, batch_size: usize) {
+        let Some(staging) = self.staging_index() else {
+            return;
+        };
+
+        let mut retry = Vec::new();
+        for batch in memories.chunks(batch_size.max(1)) {
+            let texts: Vec<String> = batch.iter().map(|m| m.content.clone()).collect();
+            match self.embed_batch(&texts).await {
+                Ok(embeddings) => {
+                    for (memory, embedding) in batch.iter().zip(embeddings) {
+                        // Writes that landed during the reindex are newer, keep them
+                        staging.insert_if_absent(memory.id, embedding);
+                    }
+                    let mut status = self.reindex.lock().expect("reindex status lock poisoned");
+                    status.processed += batch.len();
+                }
+                Err(e) => {
+                    warn!(count = batch.len(), error = %e, "Failed to re-embed batch, will retry");
+                    retry.extend_from_slice(batch);
+                }
+            }
+        }
+
+        let mut failed = Vec::new();
+        for memory in retry {
+            match self.embed_text(&memory.content).await {
+                Ok(embedding) => {
+                    staging.insert_if_absent(memory.id, embedding);
+                    let mut status = self.reindex.lock().expect("reindex status lock poisoned");
+                    status.processed += 1;
+                }
+                Err(_) => failed.push(memory.id),
+            }
+        }
+        if !failed.is_empty() {
+            warn!(count = failed.len(), ids = ?failed, "Memories could not be re-embedded");
+        }
+
+        let mut status = self.reindex.lock().expect("reindex status lock poisoned");
+        status.failed = failed.len();
+        let staging = self
+            .staging
+            .write()
+            .expect("vector index lock poisoned")
+            .take();
+        if status.total > 0 && status.processed == 0 {
+            warn!("Every reindex batch failed, keeping the previous index");
+            status.state = ReindexState::Failed;
+        } else if let Some(staging) = staging {
+            self.dimensions.store(
+                staging.dimensions.load(std::sync::atomic::Ordering::Relaxed),
+                std::sync::atomic::Ordering::Relaxed,
+            );
+            *self.index.write().expect("vector index lock poisoned") = staging;
+            status.state = ReindexState::Completed;
+            info!(
+                processed = status.processed,
+                failed = status.failed,
+                "Embedding reindex complete, new index swapped in"
+            );
+        }
+        status.finished_at = Some(Utc::now());
+    }
+
+    /// Current (or last) reindex progress.
+    pub fn reindex_status(&self) -> EmbeddingReindexStatus {
+        self.reindex
+            .lock()
+            .expect("reindex status lock poisoned")
+            .clone()
+    }
+
+    /// `provider:model` of the index search is currently served from.
+    pub fn index_model(&self) -> String {
+        self.active_index().label()
+    }
+
     /// Remove a memory's embedding from the index.
     pub fn remove_memory(&self, memory_id: u64) {
-        self.vectors.remove(&memory_id);
+        self.active_index().vectors.remove(memory_id);
+        if let Some(staging) = self.staging_index() {
+            staging.remove(memory_id);
+        }
     }
 
     /// Semantic search — find memories most similar to the query.
@@ -425,39 +778,25 @@ impl EmbeddingEngine {
         Ok(self.search_by_vector(&query_embedding, limit))
     }
 
-    /// Search by pre-computed vector using rayon parallel iteration.
+    /// Search by pre-computed vector through the configured ANN index.
     pub fn search_by_vector(
         &self,
         query_vec: &[f32],
         limit: usize,
     ) -> Vec<(u64, f32)> {
-        // Collect keys+vectors into a vec for rayon (DashMap iter isn't Send)
-        let entries: Vec<(u64, Vec<f32>)> = self
-            .vectors
-            .iter()
-            .map(|entry| (*entry.key(), entry.value().clone()))
-            .collect();
-
-        // Parallel cosine similarity computation
-        let mut scores: Vec<(u64, f32)> = entries
-            .par_iter()
-            .map(|(id, vec)| (*id, cosine_similarity(query_vec, vec)))
-            .collect();
-
-        // Partial sort: only need top `limit` results
-        if scores.len() > limit {
-            scores.select_nth_unstable_by(limit, |a, b| {
-                b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal)
-            });
-            scores.truncate(limit);
-        }
-        scores.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
-        scores
+        // Vectors from another model live in a different space — comparing
+        // them with this query would be meaningless, so wait for the reindex.
+        let index = self.active_index();
+        if !index.matches(&self.config) {
+            return vec![];
+        }
+
+        index.vectors.search(query_vec, limit)
     }
 
     /// Get number of indexed vectors.
     pub fn indexed_count(&self) -> usize {
-        self.vectors.len()
+        self.active_index().vectors.len()
     }
 
     /// Get the embedding dimensions (0 if no embeddings generated yet).
@@ -467,7 +806,7 @@ impl EmbeddingEngine {
 
     /// Check if a memory has been indexed.
     pub fn is_indexed(&self, memory_id: u64) -> bool {
-        self.vectors.contains_key(&memory_id)
+        self.active_index().vectors.contains(memory_id)
     }
 
     /// Get the provider name for status reporting.
@@ -550,6 +889,7 @@ pub fn hybrid_score(keyword_score: f32, vector_score: f32, vector_weight: f32) -
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::OverdueAction;
 
     #[test]
     fn test_cosine_similarity_identical() {
@@ -637,12 +977,13 @@ mod tests {
             dimensions: Some(3),
             cache_dir: None,
             pool_size: 0,
+            index: VectorIndexConfig::default(),
         });
 
         // Manually insert some vectors
-        engine.vectors.insert(1, vec![1.0, 0.0, 0.0]);
-        engine.vectors.insert(2, vec![0.0, 1.0, 0.0]);
-        engine.vectors.insert(3, vec![0.9, 0.1, 0.0]);
+        engine.store_vector(1, vec![1.0, 0.0, 0.0]);
+        engine.store_vector(2, vec![0.0, 1.0, 0.0]);
+        engine.store_vector(3, vec![0.9, 0.1, 0.0]);
 
         let query = vec![1.0, 0.0, 0.0];
         let results = engine.search_by_vector(&query, 3);
@@ -660,6 +1001,186 @@ mod tests {
         assert!(results[2].1.abs() < 1e-6);
     }
 
+    #[test]
+    fn test_restore_vectors_checks_model() {
+        let config = EmbeddingConfig {
+            provider: "test".into(),
+            model: "model-a".into(),
+            api_key: None,
+            base_url: "http://localhost:1234".into(),
+            dimensions: None,
+            cache_dir: None,
+            pool_size: 0,
+            index: VectorIndexConfig::default(),
+        };
+        let engine = EmbeddingEngine::new(config.clone());
+        engine.store_vector(1, vec![1.0, 0.0, 0.0]);
+        engine.store_vector(2, vec![0.0, 1.0, 0.0]);
+        engine.dimensions.store(3, std::sync::atomic::Ordering::Relaxed);
+
+        let mut snapshot = engine.snapshot_vectors();
+        assert_eq!(snapshot.dimensions, 3);
+        snapshot.vectors.push((3, vec![1.0, 0.0])); // wrong length, skipped
+
+        let restored = EmbeddingEngine::new(config.clone());
+        assert_eq!(restored.restore_vectors(snapshot.clone()), 2);
+        assert_eq!(restored.dimensions(), 3);
+        assert!(restored.is_indexed(1) && restored.is_indexed(2));
+        assert!(!restored.is_indexed(3));
+
+        let other_model = EmbeddingEngine::new(EmbeddingConfig {
+            model: "model-b".into(),
+            ..config
+        });
+        assert_eq!(other_model.restore_vectors(snapshot), 2);
+        assert!(other_model.needs_reindex());
+        assert_eq!(other_model.index_model(), "test:model-a");
+        // Vectors from another model are never compared with new queries
+        assert!(other_model.search_by_vector(&[1.0, 0.0, 0.0], 2).is_empty());
+    }
+
+    /// Serve deterministic OpenAI-style embeddings: [len, 1.0] per input.
+    /// Batches containing "flaky" fail; it embeds fine on its own.
+    async fn spawn_mock_embedding_api() -> String {
+        use axum::{Json, Router, http::StatusCode, routing::post};
+
+        async fn embed(Json(req): Json<serde_json::Value>) -> Result<Json<serde_json::Value>, StatusCode> {
+            let input = req["input"].as_array().unwrap();
+            if input.len() > 1 && input.iter().any(|t| t == "flaky") {
+                return Err(StatusCode::SERVICE_UNAVAILABLE);
+            }
+            let data: Vec<serde_json::Value> = input
+                .iter()
+                .map(|t| serde_json::json!({ "embedding": [t.as_str().unwrap().len() as f32, 1.0] }))
+                .collect();
+            Ok(Json(serde_json::json!({ "data": data })))
+        }
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, Router::new().route("/embeddings", post(embed)))
+                .await
+                .unwrap();
+        });
+        format!("http://{}", addr)
+    }
+
+    fn memory(id: u64, content: &str) -> Memory {
+        let now = Utc::now();
+        Memory {
+            id,
+            content: content.into(),
+            memory_type: MemoryType::Fact,
+            agent_id: None,
+            user_id: None,
+            session_id: None,
+            confidence: 1.0,
+            tags: vec![],
+            created_at: now,
+            updated_at: now,
+            valid_from: now,
+            valid_until: None,
+            source: "test".into(),
+            metadata: serde_json::Value::Null,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reindex_swaps_in_new_model() {
+        let base_url = spawn_mock_embedding_api().await;
+        let engine = Arc::new(EmbeddingEngine::new(EmbeddingConfig {
+            provider: "test".into(),
+            model: "model-b".into(),
+            api_key: None,
+            base_url,
+            dimensions: None,
+            cache_dir: None,
+            pool_size: 0,
+            index: VectorIndexConfig::default(),
+        }));
+        engine.restore_vectors(EmbeddingSnapshot {
+            provider: "test".into(),
+            model: "model-a".into(),
+            dimensions: 3,
+            vectors: vec![(1, vec![1.0, 0.0, 0.0]), (2, vec![0.0, 1.0, 0.0])],
+        });
+        assert!(engine.needs_reindex());
+
+        let status = engine
+            .start_reindex(vec![memory(1, "a"), memory(2, "bbbb"), memory(3, "flaky")], "test")
+            .unwrap();
+        assert_eq!(status.state, ReindexState::Running);
+        assert_eq!(status.from_model.as_deref(), Some("test:model-a"));
+        assert_eq!(status.total, 3);
+
+        for _ in 0..100 {
+            if engine.reindex_status().state != ReindexState::Running {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+
+        let status = engine.reindex_status();
+        assert_eq!(status.state, ReindexState::Completed);
+        // The failed batch was retried memory by memory
+        assert_eq!((status.processed, status.failed), (3, 0));
+        assert!(engine.is_indexed(3));
+        assert!(!engine.needs_reindex());
+        assert_eq!(engine.index_model(), "test:model-b");
+        assert_eq!(engine.snapshot_vectors().dimensions, 2);
+        assert_eq!(engine.dimensions(), 2);
+        assert_eq!(engine.search_by_vector(&[4.0, 1.0], 1)[0].0, 2);
+    }
+
+    #[test]
+    fn test_removed_vectors_stay_removed() {
+        let index = VectorIndex::new("test", "model-a", &VectorIndexConfig::default());
+        index.insert(1, vec![1.0, 0.0]);
+        index.remove(1);
+        // A reindex that embedded the memory before it was removed
+        index.insert_if_absent(1, vec![1.0, 0.0]);
+        assert!(!index.vectors.contains(1));
+        // A new write brings it back
+        index.insert(1, vec![0.0, 1.0]);
+        assert!(index.vectors.contains(1));
+    }
+
+    #[tokio::test]
+    async fn test_restore_vectors_checks_dimensions() {
+        let config = EmbeddingConfig {
+            provider: "test".into(),
+            model: "model-a".into(),
+            api_key: None,
+            base_url: spawn_mock_embedding_api().await,
+            dimensions: None,
+            cache_dir: None,
+            pool_size: 0,
+            index: VectorIndexConfig::default(),
+        };
+        let snapshot = |dimensions: u32| EmbeddingSnapshot {
+            provider: "test".into(),
+            model: "model-a".into(),
+            dimensions,
+            vectors: vec![(1, vec![1.0; dimensions as usize])],
+        };
+
+        // The mock model produces 2-dimensional vectors
+        let engine = EmbeddingEngine::new(config.clone());
+        engine.restore_vectors(snapshot(2));
+        assert!(!engine.needs_reindex());
+        assert!(!engine.dimensions_changed().await.unwrap());
+        engine.restore_vectors(snapshot(3));
+        assert!(!engine.needs_reindex());
+        assert!(engine.dimensions_changed().await.unwrap());
+
+        // A tag that disagrees with the configured dimensions needs a reindex
+        let configured = EmbeddingEngine::new(EmbeddingConfig { dimensions: Some(2), ..config });
+        configured.restore_vectors(snapshot(3));
+        assert!(configured.needs_reindex());
+        assert!(configured.search_by_vector(&[1.0, 1.0, 1.0], 1).is_empty());
+    }
+
     #[test]
     fn test_embedding_config_parse() {
         let config = HiveMindConfig {
@@ -673,6 +1194,16 @@ mod tests {
             data_dir: "./data".into(),
             snapshot_interval: 60,
             replication_enabled: false,
+            node_id: "test-node".into(),
+            node_number: 0,
+            bm25_k1: 1.2,
+            bm25_b: 0.75,
+            vector_index: VectorIndexConfig::default(),
+            entity_name_matching: true,
+            task_lease_secs: 300,
+            task_max_attempts: 3,
+            task_overdue_action: OverdueAction::Notify,
+            task_rollup_policy: RollupPolicy::FailFast,
         };
         let ec = EmbeddingConfig::from_hivemind_config(&config);
         assert_eq!(ec.provider, "openai");
@@ -695,6 +1226,16 @@ mod tests {
             data_dir: "./data".into(),
             snapshot_interval: 60,
             replication_enabled: false,
+            node_id: "test-node".into(),
+            node_number: 0,
+            bm25_k1: 1.2,
+            bm25_b: 0.75,
+            vector_index: VectorIndexConfig::default(),
+            entity_name_matching: true,
+            task_lease_secs: 300,
+            task_max_attempts: 3,
+            task_overdue_action: OverdueAction::Notify,
+            task_rollup_policy: RollupPolicy::FailFast,
         };
         let ec = EmbeddingConfig::from_hivemind_config(&config);
         assert_eq!(ec.provider, "ollama");
@@ -716,6 +1257,16 @@ mod tests {
             data_dir: "/data".into(),
             snapshot_interval: 60,
             replication_enabled: false,
+            node_id: "test-node".into(),
+            node_number: 0,
+            bm25_k1: 1.2,
+            bm25_b: 0.75,
+            vector_index: VectorIndexConfig::default(),
+            entity_name_matching: true,
+            task_lease_secs: 300,
+            task_max_attempts: 3,
+            task_overdue_action: OverdueAction::Notify,
+            task_rollup_policy: RollupPolicy::FailFast,
         };
         let ec = EmbeddingConfig::from_hivemind_config(&config);
         assert_eq!(ec.provider, "local");
@@ -739,6 +1290,16 @@ mod tests {
             data_dir: "./data".into(),
             snapshot_interval: 60,
             replication_enabled: false,
+            node_id: "test-node".into(),
+            node_number: 0,
+            bm25_k1: 1.2,
+            bm25_b: 0.75,
+            vector_index: VectorIndexConfig::default(),
+            entity_name_matching: true,
+            task_lease_secs: 300,
+            task_max_attempts: 3,
+            task_overdue_action: OverdueAction::Notify,
+            task_rollup_policy: RollupPolicy::FailFast,
         };
         let ec = EmbeddingConfig::from_hivemind_config(&config);
         assert_eq!(ec.provider, "local");
@@ -755,10 +1316,11 @@ mod tests {
             dimensions: None,
             cache_dir: None,
             pool_size: 0,
+            index: VectorIndexConfig::default(),
         });
 
         assert_eq!(engine.indexed_count(), 0);
-        engine.vectors.insert(1, vec![1.0, 0.0]);
+        engine.store_vector(1, vec![1.0, 0.0]);
         assert_eq!(engine.indexed_count(), 1);
         assert!(engine.is_indexed(1));
         assert!(!engine.is_indexed(2));
@@ -77,6 +77,9 @@ pub struct ExtractedFact {
     pub operation: ExtractionOperation,
     /// If updating, the ID of the memory to update.
     pub updates_memory_id: Option<u64>,
+    /// Names of the extracted entities this fact mentions.
+    #[serde(default)]
+    pub entities: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -94,6 +97,18 @@ pub struct ExtractedEntity {
     pub description: Option<String>,
 }
 
+/// LLM-written summary of a finished session.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EpisodeSummary {
+    pub summary: String,
+    #[serde(default)]
+    pub key_decisions: Vec<String>,
+    #[serde(default)]
+    pub tools_used: Vec<String>,
+    #[serde(default)]
+    pub outcome: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExtractedRelationship {
     pub source_entity: String,
@@ -171,6 +186,7 @@ For each fact, determine:
 - `memory_type`: "fact" (concrete info), "episodic" (event/experience), "procedural" (how-to), "semantic" (abstract concept)
 - `confidence`: 0.0-1.0 how confident you are this is accurate
 - `tags`: relevant categories
+- `entities`: names of the extracted entities the fact mentions
 
 Respond with ONLY valid JSON in this exact format:
 {
@@ -181,7 +197,8 @@ Respond with ONLY valid JSON in this exact format:
       "confidence": 0.95,
       "tags": ["preferences", "languages"],
       "operation": "add",
-      "updates_memory_id": null
+      "updates_memory_id": null,
+      "entities": ["EntityName"]
     }
   ],
   "entities": [
@@ -201,6 +218,22 @@ Respond with ONLY valid JSON in this exact format:
   ]
 }"#;
 
+const EPISODE_SYSTEM_PROMPT: &str = r#"You are a session summarizer for HiveMindDB. You receive the memories an AI agent recorded during one work session, in order, and describe what actually happened.
+
+Determine:
+- `summary`: 1-3 sentences describing what the session accomplished
+- `key_decisions`: decisions that were made, one short statement each
+- `tools_used`: tools, commands, or services the agent used
+- `outcome`: "success", "partial", "failed", or a short free-form result
+
+Respond with ONLY valid JSON in this exact format:
+{
+  "summary": "what happened in the session",
+  "key_decisions": ["Chose X over Y because Z"],
+  "tools_used": ["cargo", "git"],
+  "outcome": "success"
+}"#;
+
 impl ExtractionPipeline {
     pub fn new(config: ExtractionConfig) -> Self {
         Self {
@@ -248,7 +281,7 @@ impl ExtractionPipeline {
             ));
         }
 
-        let response_text = self.call_llm(&user_prompt).await?;
+        let response_text = self.call_llm(EXTRACTION_SYSTEM_PROMPT, &user_prompt).await?;
         debug!(response = %response_text, "LLM extraction response");
 
         // Parse JSON response — handle markdown code blocks
@@ -268,15 +301,41 @@ impl ExtractionPipeline {
         Ok(result)
     }
 
-    async fn call_llm(&self, user_prompt: &str) -> anyhow::Result<String> {
+    /// Summarize a finished session from the memories recorded during it.
+    pub async fn summarize_episode(&self, memories: &[Memory]) -> anyhow::Result<EpisodeSummary> {
+        let timeline = memories
+            .iter()
+            .take(100) // Limit context size
+            .map(|m| {
+                let tags = if m.tags.is_empty() {
+                    String::new()
+                } else {
+                    format!(" [{}]", m.tags.join(", "))
+                };
+                format!("- ({}) {}{}", m.created_at.format("%H:%M:%S"), m.content, tags)
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let user_prompt = format!("Summarize this session from its recorded memories:\n\n{}", timeline);
+
+        let response_text = self.call_llm(EPISODE_SYSTEM_PROMPT, &user_prompt).await?;
+        debug!(response = %response_text, "LLM episode summary response");
+
+        let json_str = extract_json_from_response(&response_text);
+        serde_json::from_str(json_str).map_err(|e| {
+            anyhow::anyhow!("Failed to parse episode summary: {} — raw: {}", e, json_str)
+        })
+    }
+
+    async fn call_llm(&self, system_prompt: &str, user_prompt: &str) -> anyhow::Result<String> {
         if self.config.provider == "anthropic" {
-            self.call_anthropic(user_prompt).await
+            self.call_anthropic(system_prompt, user_prompt).await
         } else {
-            self.call_openai_compatible(user_prompt).await
+            self.call_openai_compatible(system_prompt, user_prompt).await
         }
     }
 
-    async fn call_openai_compatible(&self, user_prompt: &str) -> anyhow::Result<String> {
+    async fn call_openai_compatible(&self, system_prompt: &str, user_prompt: &str) -> anyhow::Result<String> {
         let url = format!("{}/chat/completions", self.config.base_url);
 
         let req = ChatRequest {
@@ -284,7 +343,7 @@ impl ExtractionPipeline {
             messages: vec![
                 ChatMessage {
                     role: "system".into(),
-                    content: EXTRACTION_SYSTEM_PROMPT.into(),
+                    content: system_prompt.into(),
                 },
                 ChatMessage {
                     role: "user".into(),
@@ -318,7 +377,7 @@ impl ExtractionPipeline {
             .ok_or_else(|| anyhow::anyhow!("Empty LLM response"))
     }
 
-    async fn call_anthropic(&self, user_prompt: &str) -> anyhow::Result<String> {
+    async fn call_anthropic(&self, system_prompt: &str, user_prompt: &str) -> anyhow::Result<String> {
         let url = format!("{}/v1/messages", self.config.base_url);
 
         let req = AnthropicRequest {
@@ -327,7 +386,7 @@ impl ExtractionPipeline {
             messages: vec![
                 ChatMessage {
                     role: "user".into(),
-                    content: format!("{}\n\n{}", EXTRACTION_SYSTEM_PROMPT, user_prompt),
+                    content: format!("{}\n\n{}", system_prompt, user_prompt),
                 },
             ],
         };
@@ -378,6 +437,8 @@ fn extract_json_from_response(text: &str) -> &str {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::OverdueAction;
+    use crate::vector_index::VectorIndexConfig;
 
     #[test]
     fn test_extract_json_from_response_plain() {
@@ -410,6 +471,16 @@ mod tests {
             data_dir: "".into(),
             snapshot_interval: 60,
             replication_enabled: false,
+            node_id: "test-node".into(),
+            node_number: 0,
+            bm25_k1: 1.2,
+            bm25_b: 0.75,
+            vector_index: VectorIndexConfig::default(),
+            entity_name_matching: true,
+            task_lease_secs: 300,
+            task_max_attempts: 3,
+            task_overdue_action: OverdueAction::Notify,
+            task_rollup_policy: RollupPolicy::FailFast,
         };
         let ec = ExtractionConfig::from_hivemind_config(&config);
         assert_eq!(ec.base_url, "https://api.openai.com/v1");
@@ -429,6 +500,16 @@ mod tests {
             data_dir: "".into(),
             snapshot_interval: 60,
             replication_enabled: false,
+            node_id: "test-node".into(),
+            node_number: 0,
+            bm25_k1: 1.2,
+            bm25_b: 0.75,
+            vector_index: VectorIndexConfig::default(),
+            entity_name_matching: true,
+            task_lease_secs: 300,
+            task_max_attempts: 3,
+            task_overdue_action: OverdueAction::Notify,
+            task_rollup_policy: RollupPolicy::FailFast,
         };
         let ec = ExtractionConfig::from_hivemind_config(&config);
         assert_eq!(ec.base_url, "http://localhost:9212/v1");
@@ -448,6 +529,16 @@ mod tests {
             data_dir: "".into(),
             snapshot_interval: 60,
             replication_enabled: false,
+            node_id: "test-node".into(),
+            node_number: 0,
+            bm25_k1: 1.2,
+            bm25_b: 0.75,
+            vector_index: VectorIndexConfig::default(),
+            entity_name_matching: true,
+            task_lease_secs: 300,
+            task_max_attempts: 3,
+            task_overdue_action: OverdueAction::Notify,
+            task_rollup_policy: RollupPolicy::FailFast,
         };
         let ec = ExtractionConfig::from_hivemind_config(&config);
         assert_eq!(ec.base_url, "http://my-proxy:8080/v1");
@@ -488,6 +579,7 @@ mod tests {
         assert_eq!(result.facts.len(), 1);
         assert_eq!(result.facts[0].content, "User prefers Rust over Python");
         assert_eq!(result.facts[0].operation, ExtractionOperation::Add);
+        assert!(result.facts[0].entities.is_empty());
         assert_eq!(result.entities.len(), 1);
         assert_eq!(result.relationships.len(), 1);
     }
@@ -0,0 +1,99 @@
+//! Cluster-safe ID allocation.
+//!
+//! Every node mints IDs from its own range, so nodes accepting writes at the
+//! same time never hand out the same ID. An ID is
+//! `node_number << COUNTER_BITS | counter`: 10 node bits over a 43-bit counter
+//! keep every ID below 2^53, so JSON clients that parse numbers as doubles
+//! (the MCP server) still see them exactly. Node 0 is the standalone range,
+//! where IDs are plain 1, 2, 3, …
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+pub const NODE_BITS: u32 = 10;
+pub const COUNTER_BITS: u32 = 43;
+
+/// Highest node number that fits in an ID.
+pub const MAX_NODE_NUMBER: u16 = (1 << NODE_BITS) - 1;
+
+/// Hands out IDs from one node's range.
+pub struct IdAllocator {
+    node: u64,
+    next: AtomicU64,
+}
+
+impl IdAllocator {
+    pub fn new(node_number: u16) -> Self {
+        assert!(node_number <= MAX_NODE_NUMBER, "node number {} out of range", node_number);
+        let node = node_number as u64;
+        Self {
+            node,
+            next: AtomicU64::new((node << COUNTER_BITS) | 1),
+        }
+    }
+
+    pub fn next(&self) -> u64 {
+        self.next.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Keep the counter past an existing ID (from a snapshot, the WAL or a
+    /// replicated event). IDs in other nodes' ranges leave it alone.
+    pub fn observe(&self, id: u64) {
+        if node_of(id) as u64 == self.node {
+            self.next.fetch_max(id + 1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// The node whose range an ID belongs to.
+pub fn node_of(id: u64) -> u16 {
+    (id >> COUNTER_BITS) as u16
+}
+
+/// Node number for a node that wasn't given one: a hash of its node ID in
+/// `1..=MAX_NODE_NUMBER`. Distinct node IDs can collide, so clusters should
+/// set `--node-number` explicitly; a collision is refused once the nodes'
+/// events meet (see `replication::inbound_loop`).
+pub fn derive_node_number(node_id: &str) -> u16 {
+    // FNV-1a: stable across builds and platforms, unlike the std hasher
+    let hash = node_id
+        .bytes()
+        .fold(0xcbf29ce484222325u64, |h, b| (h ^ b as u64).wrapping_mul(0x100000001b3));
+    (hash % MAX_NODE_NUMBER as u64) as u16 + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_standalone_ids_start_at_one() {
+        let ids = IdAllocator::new(0);
+        assert_eq!(ids.next(), 1);
+        assert_eq!(ids.next(), 2);
+        ids.observe(41);
+        assert_eq!(ids.next(), 42);
+    }
+
+    #[test]
+    fn test_nodes_allocate_disjoint_ranges() {
+        let a = IdAllocator::new(1);
+        let b = IdAllocator::new(2);
+        let (x, y) = (a.next(), b.next());
+        assert_ne!(x, y);
+        assert_eq!((node_of(x), node_of(y)), (1, 2));
+
+        // Replicated IDs from another node never move this node's counter
+        a.observe(y + 1000);
+        assert_eq!(a.next(), x + 1);
+
+        let top = IdAllocator::new(MAX_NODE_NUMBER);
+        assert!(top.next() < 1 << 53);
+    }
+
+    #[test]
+    fn test_derive_node_number() {
+        let n = derive_node_number("0b6f1c1e-6c43-4c4e-9b8e-3f2a1d5c7e90");
+        assert!((1..=MAX_NODE_NUMBER).contains(&n));
+        assert_eq!(n, derive_node_number("0b6f1c1e-6c43-4c4e-9b8e-3f2a1d5c7e90"));
+    }
+}
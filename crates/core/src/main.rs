@@ -4,16 +4,21 @@ use anyhow::Result;
 use clap::Parser;
 use std::sync::Arc;
 use tokio::sync::watch;
-use tracing::info;
+use tracing::{error, info, warn};
 
 mod api;
 mod channels;
 mod config;
 mod embeddings;
 mod extraction;
+mod ids;
 mod memory_engine;
 mod persistence;
+mod query;
+mod replication;
+mod scheduler;
 mod types;
+mod vector_index;
 mod websocket;
 
 #[derive(Parser)]
@@ -55,9 +60,74 @@ struct Cli {
     #[arg(long, default_value = "60", env = "HIVEMIND_SNAPSHOT_INTERVAL")]
     snapshot_interval: u64,
 
+    /// Snapshot encoding: binary (compressed + checksummed) or json (human-readable export)
+    #[arg(long, default_value = "binary", env = "HIVEMIND_SNAPSHOT_FORMAT")]
+    snapshot_format: persistence::SnapshotFormat,
+
+    /// BM25 term-frequency saturation (k1) for keyword search
+    #[arg(long, default_value = "1.2", env = "HIVEMIND_BM25_K1")]
+    bm25_k1: f32,
+
+    /// BM25 document-length normalisation (b, 0..=1) for keyword search
+    #[arg(long, default_value = "0.75", env = "HIVEMIND_BM25_B")]
+    bm25_b: f32,
+
+    /// Vector index for semantic search: hnsw (approximate) or brute-force (exact)
+    #[arg(long, default_value = "hnsw", env = "HIVEMIND_VECTOR_INDEX")]
+    vector_index: vector_index::VectorIndexKind,
+
+    /// HNSW links per node (higher = better recall, more memory)
+    #[arg(long, default_value = "16", env = "HIVEMIND_HNSW_M", value_parser = clap::value_parser!(u32).range(2..))]
+    hnsw_m: u32,
+
+    /// HNSW candidate list size while inserting
+    #[arg(long, default_value = "200", env = "HIVEMIND_HNSW_EF_CONSTRUCTION", value_parser = clap::value_parser!(u32).range(1..))]
+    hnsw_ef_construction: u32,
+
+    /// HNSW candidate list size while searching (higher = better recall, slower queries)
+    #[arg(long, default_value = "64", env = "HIVEMIND_HNSW_EF_SEARCH", value_parser = clap::value_parser!(u32).range(1..))]
+    hnsw_ef_search: u32,
+
+    /// Below this many vectors, HNSW search scans every vector exactly
+    #[arg(long, default_value = "1000", env = "HIVEMIND_VECTOR_EXACT_THRESHOLD")]
+    vector_exact_threshold: usize,
+
+    /// Link new memories to existing entities whose names appear in their content
+    #[arg(long, default_value = "true", action = clap::ArgAction::Set, env = "HIVEMIND_ENTITY_NAME_MATCHING")]
+    entity_name_matching: bool,
+
+    /// Seconds a claimed task stays leased without a heartbeat or progress (0 = never expires)
+    #[arg(long, default_value = "300", env = "HIVEMIND_TASK_LEASE_SECS")]
+    task_lease_secs: u64,
+
+    /// Claims a task gets before an expired lease marks it failed (0 = unlimited)
+    #[arg(long, default_value = "3", env = "HIVEMIND_TASK_MAX_ATTEMPTS")]
+    task_max_attempts: u32,
+
+    /// What to do with tasks past their deadline: notify, fail or escalate
+    #[arg(long, default_value = "notify", env = "HIVEMIND_TASK_OVERDUE_ACTION")]
+    task_overdue_action: config::OverdueAction,
+
+    /// How parent tasks follow their subtasks: fail-fast, best-effort or manual
+    #[arg(long, default_value = "fail-fast", env = "HIVEMIND_TASK_ROLLUP_POLICY")]
+    task_rollup_policy: types::RollupPolicy,
+
     /// Enable RaftTimeDB replication
     #[arg(long, env = "HIVEMIND_ENABLE_REPLICATION")]
     enable_replication: bool,
+
+    /// Unique ID of this node in the cluster (generated and stored in the data dir if unset)
+    #[arg(long, env = "HIVEMIND_NODE_ID")]
+    node_id: Option<String>,
+
+    /// Catch up from this peer before serving (its base URL, e.g. http://node-1:8100)
+    #[arg(long, env = "HIVEMIND_BOOTSTRAP_FROM")]
+    bootstrap_from: Option<String>,
+
+    /// Unique number (1-1023) of this node in the cluster; new IDs are minted from its range.
+    /// Derived from the node ID if unset with replication enabled, 0 when standalone
+    #[arg(long, env = "HIVEMIND_NODE_NUMBER", value_parser = clap::value_parser!(u16).range(0..=ids::MAX_NODE_NUMBER as i64))]
+    node_number: Option<u16>,
 }
 
 #[tokio::main]
@@ -79,6 +149,21 @@ async fn main() -> Result<()> {
         "Starting HiveMindDB"
     );
 
+    let node_id = persistence::load_or_create_node_id(&cli.data_dir, cli.node_id.clone())?;
+    let node_number = match cli.node_number {
+        Some(0) if cli.enable_replication => anyhow::bail!(
+            "--node-number 0 is the standalone ID range; a replicating node needs a number from 1 to {}",
+            ids::MAX_NODE_NUMBER
+        ),
+        Some(n) => n,
+        None if cli.enable_replication => {
+            let n = ids::derive_node_number(&node_id);
+            warn!(node_number = n, "No --node-number set, derived one from the node ID; set it explicitly to guarantee unique ID ranges");
+            n
+        }
+        None => 0,
+    };
+
     let config = config::HiveMindConfig {
         listen_addr: cli.listen_addr.clone(),
         rtdb_url: cli.rtdb_url.clone(),
@@ -90,6 +175,22 @@ async fn main() -> Result<()> {
         data_dir: cli.data_dir.clone(),
         snapshot_interval: cli.snapshot_interval,
         replication_enabled: cli.enable_replication,
+        node_id: node_id.clone(),
+        node_number,
+        bm25_k1: cli.bm25_k1,
+        bm25_b: cli.bm25_b,
+        vector_index: vector_index::VectorIndexConfig {
+            kind: cli.vector_index,
+            m: cli.hnsw_m as usize,
+            ef_construction: cli.hnsw_ef_construction as usize,
+            ef_search: cli.hnsw_ef_search as usize,
+            exact_threshold: cli.vector_exact_threshold,
+        },
+        entity_name_matching: cli.entity_name_matching,
+        task_lease_secs: cli.task_lease_secs,
+        task_max_attempts: cli.task_max_attempts,
+        task_overdue_action: cli.task_overdue_action,
+        task_rollup_policy: cli.task_rollup_policy,
     };
 
     // Shutdown signal
@@ -97,44 +198,170 @@ async fn main() -> Result<()> {
 
     // Initialize engine
     let mut engine = memory_engine::MemoryEngine::new(config.clone());
+    let mut channel_hub = channels::ChannelHub::for_node(node_number);
 
-    // Restore from snapshot if available
-    let snapshot_mgr = persistence::SnapshotManager::new(&cli.data_dir);
-    if let Some(snapshot) = snapshot_mgr.load().await? {
-        engine.restore_from_snapshot(snapshot);
-    }
-
-    // Set up replication if enabled
+    // Set up replication if enabled. The client starts before catching up so
+    // events committed meanwhile are buffered and applied afterwards.
+    let mut inbound_rx = None;
+    let mut outbox = None;
+    let mut replication_connected = None;
     if cli.enable_replication {
-        let (repl_tx, repl_rx) = tokio::sync::mpsc::unbounded_channel();
-        engine.set_replication_tx(repl_tx);
+        let repl_outbox = Arc::new(persistence::ReplicationOutbox::open(&cli.data_dir)?);
+        let (inbound_tx, rx) = tokio::sync::mpsc::unbounded_channel();
 
         let repl_client =
-            persistence::ReplicationClient::new(&cli.rtdb_url, shutdown_rx.clone());
+            persistence::ReplicationClient::new(&cli.rtdb_url, &node_id, node_number, shutdown_rx.clone());
+        replication_connected = Some(repl_client.connection_flag());
+        let client_outbox = repl_outbox.clone();
         tokio::spawn(async move {
-            repl_client.run(repl_rx).await;
+            repl_client.run(client_outbox, inbound_tx).await;
         });
-        info!("Replication client started");
+        inbound_rx = Some(rx);
+        outbox = Some(repl_outbox);
+        info!(node_id = %node_id, "Replication client started");
+    }
+
+    // Catch up from a peer before serving
+    let mut catch_up = None;
+    let mut bootstrap_outcome = types::BootstrapOutcome::Skipped;
+    if let Some(ref from) = cli.bootstrap_from {
+        let source = persistence::BootstrapSource::parse(from)?;
+        info!(source = ?source, "Bootstrapping from cluster state");
+        match persistence::bootstrap(&source).await {
+            Ok(state) => {
+                catch_up = Some(state);
+                bootstrap_outcome = types::BootstrapOutcome::CaughtUp;
+            }
+            Err(e) => {
+                error!(error = %e, "Bootstrap failed, starting from local state; /health reports not ready");
+                bootstrap_outcome = types::BootstrapOutcome::Failed;
+            }
+        }
     }
 
+    let wal = Arc::new(persistence::WriteAheadLog::open(&cli.data_dir)?);
+    let snapshot_mgr = persistence::SnapshotManager::new(&cli.data_dir);
+    let caught_up = catch_up.is_some();
+    if let Some(state) = catch_up {
+        // The fetched state supersedes the local snapshot and WAL
+        channel_hub.restore_from_snapshot(&state.snapshot);
+        engine.restore_from_snapshot(state.snapshot);
+        info!(events = state.events.len(), "Replaying events after the bootstrap snapshot");
+        for event in state.events {
+            apply_event(&engine, &channel_hub, event);
+        }
+        // Local writes RaftTimeDB hasn't committed yet aren't in the source's state
+        if let Some(ref outbox) = outbox {
+            for (_, event) in outbox.pending(outbox.acked_seq(), usize::MAX)? {
+                apply_event(&engine, &channel_hub, event);
+            }
+        }
+    } else {
+        // Restore from snapshot if available
+        let mut snapshot_wal_seq = 0;
+        if let Some(snapshot) = snapshot_mgr.load().await? {
+            snapshot_wal_seq = snapshot.wal_seq;
+            channel_hub.restore_from_snapshot(&snapshot);
+            engine.restore_from_snapshot(snapshot);
+        }
+
+        // Replay writes acknowledged after the snapshot was taken
+        let replayed = wal.replay(snapshot_wal_seq)?;
+        if !replayed.is_empty() {
+            info!(events = replayed.len(), "Replaying write-ahead log");
+            for event in replayed {
+                apply_event(&engine, &channel_hub, event);
+            }
+        }
+    }
+    engine.set_wal(wal.clone());
+    if let Some(outbox) = outbox {
+        engine.set_replication_outbox(outbox);
+    }
+    channel_hub.set_replication(engine.replication_sink());
+
+    // Persist the caught-up state so a restart doesn't fall back to the old one
+    if caught_up {
+        let mut snapshot = engine.create_snapshot();
+        channel_hub.fill_snapshot(&mut snapshot);
+        snapshot_mgr.with_format(cli.snapshot_format).save(&snapshot).await?;
+        wal.truncate_through(snapshot.wal_seq)?;
+    }
+
+    // Embed anything the persisted vector index doesn't cover
+    engine.spawn_embedding_backfill();
+
     let engine = Arc::new(engine);
-    let channel_hub = Arc::new(channels::ChannelHub::new());
+    let channel_hub = Arc::new(channel_hub);
+
+    // Apply writes committed by other nodes
+    if let Some(rx) = inbound_rx {
+        tokio::spawn(replication::inbound_loop(
+            engine.clone(),
+            channel_hub.clone(),
+            rx,
+            shutdown_rx.clone(),
+        ));
+        tokio::spawn(replication::node_heartbeat_loop(
+            engine.clone(),
+            replication::NODE_HEARTBEAT_SECS,
+            shutdown_rx.clone(),
+        ));
+    }
 
     // Start periodic snapshot task
     if cli.snapshot_interval > 0 {
         let engine_clone = engine.clone();
-        let snapshot_fn = move || engine_clone.create_snapshot();
+        let hub_clone = channel_hub.clone();
+        let snapshot_fn = move || {
+            let mut snapshot = engine_clone.create_snapshot();
+            hub_clone.fill_snapshot(&mut snapshot);
+            snapshot
+        };
         tokio::spawn(persistence::snapshot_loop(
-            persistence::SnapshotManager::new(&cli.data_dir),
+            persistence::SnapshotManager::new(&cli.data_dir).with_format(cli.snapshot_format),
             snapshot_fn,
+            Some(wal.clone()),
             cli.snapshot_interval,
             shutdown_rx.clone(),
         ));
         info!(interval = cli.snapshot_interval, "Snapshot task started");
     }
 
+    // Requeue tasks whose agent stopped renewing the lease
+    if cli.task_lease_secs > 0 {
+        tokio::spawn(scheduler::task_reaper_loop(
+            engine.clone(),
+            channel_hub.clone(),
+            scheduler::reaper_interval_secs(cli.task_lease_secs),
+            shutdown_rx.clone(),
+        ));
+        info!(lease_secs = cli.task_lease_secs, "Task lease reaper started");
+    }
+
+    // Flag (and optionally fail or escalate) tasks that miss their deadline
+    tokio::spawn(scheduler::overdue_sweeper_loop(
+        engine.clone(),
+        channel_hub.clone(),
+        scheduler::OVERDUE_SWEEP_SECS,
+        shutdown_rx.clone(),
+    ));
+
+    // Create tasks from recurring task templates
+    tokio::spawn(scheduler::template_scheduler_loop(
+        engine.clone(),
+        channel_hub.clone(),
+        scheduler::TEMPLATE_TICK_SECS,
+        shutdown_rx.clone(),
+    ));
+
     // Build and start the API server
-    let app = api::router(engine.clone(), channel_hub.clone());
+    let readiness = Arc::new(persistence::NodeReadiness::new(
+        bootstrap_outcome,
+        replication_connected,
+        engine.replication_sink(),
+    ));
+    let app = api::router(engine.clone(), channel_hub.clone(), readiness);
 
     let listener = tokio::net::TcpListener::bind(&cli.listen_addr).await?;
     info!(addr = %cli.listen_addr, "HiveMindDB API listening");
@@ -153,3 +380,20 @@ async fn main() -> Result<()> {
     info!("HiveMindDB stopped");
     Ok(())
 }
+
+/// Apply a logged or fetched event at startup; channel state lives in the hub.
+fn apply_event(
+    engine: &memory_engine::MemoryEngine,
+    channel_hub: &channels::ChannelHub,
+    event: persistence::ReplicationEvent,
+) {
+    match event {
+        persistence::ReplicationEvent::ChannelCreated { channel } => {
+            channel_hub.apply_channel_created(channel)
+        }
+        persistence::ReplicationEvent::ChannelSubscribed { channel_id, agent_id } => {
+            channel_hub.apply_channel_subscribed(channel_id, &agent_id)
+        }
+        other => engine.apply_replication_event(other),
+    }
+}
@@ -1,16 +1,58 @@
-use crate::config::HiveMindConfig;
+use crate::config::{HiveMindConfig, OverdueAction};
 use crate::embeddings::{self, EmbeddingEngine};
-use crate::extraction::{ExtractionOperation, ExtractionPipeline};
-use crate::persistence::{ReplicationEvent, Snapshot};
+use crate::extraction::{EpisodeSummary, ExtractionOperation, ExtractionPipeline};
+use crate::ids::IdAllocator;
+use crate::persistence::{
+    EventPage, ReplicationEvent, ReplicationOutbox, ReplicationSink, Snapshot, WriteAheadLog,
+};
+use crate::query::{Query, QueryError};
 use crate::types::*;
 use chrono::Utc;
 use dashmap::DashMap;
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tracing::{info, warn};
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+/// Why the engine refused a write.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WriteError {
+    /// The request is invalid or conflicts with the current state
+    Rejected(String),
+    /// The write could not be logged to the write-ahead log or replication
+    /// outbox, so nothing was changed
+    Unavailable,
+}
+
+impl std::fmt::Display for WriteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WriteError::Rejected(message) => f.write_str(message),
+            WriteError::Unavailable => {
+                f.write_str("Write could not be persisted to the write-ahead log or replication outbox")
+            }
+        }
+    }
+}
+
+impl std::error::Error for WriteError {}
+
+impl From<String> for WriteError {
+    fn from(message: String) -> Self {
+        WriteError::Rejected(message)
+    }
+}
+
+/// How stale an online agent's `last_seen` may get before a heartbeat
+/// refreshes (and replicates) it. Heartbeats in between change nothing.
+const AGENT_SEEN_RESOLUTION_SECS: i64 = 30;
+
+/// How long a node counts as alive after its last heartbeat.
+pub const NODE_LIVENESS_SECS: u64 = 30;
 
 /// Tokenize text into lowercase words for the inverted index.
-fn tokenize(text: &str) -> Vec<String> {
+pub(crate) fn tokenize(text: &str) -> Vec<String> {
     text.to_lowercase()
         .split(|c: char| !c.is_alphanumeric())
         .filter(|w| w.len() >= 2)
@@ -18,6 +60,129 @@ fn tokenize(text: &str) -> Vec<String> {
         .collect()
 }
 
+/// Whether `name` appears in tokenized text as consecutive whole words.
+fn mentions_name(words: &[String], name: &str) -> bool {
+    let name = tokenize(name);
+    !name.is_empty() && words.windows(name.len()).any(|w| w == name.as_slice())
+}
+
+/// Whether an episode passes the agent/user/time-range filter.
+fn episode_matches(episode: &Episode, query: &EpisodeQuery) -> bool {
+    query.agent_id.as_deref().is_none_or(|a| episode.agent_id == a)
+        && query.user_id.as_deref().is_none_or(|u| episode.user_id.as_deref() == Some(u))
+        && query.since.is_none_or(|t| episode.ended_at >= t)
+        && query.until.is_none_or(|t| episode.started_at <= t)
+}
+
+/// Capabilities in `required` that `available` does not provide.
+pub(crate) fn missing_capabilities(required: &[String], available: &[String]) -> Vec<String> {
+    required
+        .iter()
+        .filter(|c| !available.contains(c))
+        .cloned()
+        .collect()
+}
+
+/// The search's query: structured if asked for, plain words otherwise.
+fn parse_query(req: &SearchRequest) -> Result<Query, QueryError> {
+    if req.structured {
+        Query::parse(&req.query)
+    } else {
+        Ok(Query::words(&req.query))
+    }
+}
+
+/// Whether a task reached a final status.
+fn is_finished(status: &TaskStatus) -> bool {
+    matches!(status, TaskStatus::Completed | TaskStatus::Failed | TaskStatus::Cancelled)
+}
+
+/// Whether a task is still open after its deadline.
+fn is_overdue(task: &Task, now: chrono::DateTime<Utc>) -> bool {
+    !matches!(task.status, TaskStatus::Completed | TaskStatus::Failed | TaskStatus::Cancelled)
+        && task.deadline.is_some_and(|d| d <= now)
+}
+
+/// A template's per-task deadline offset must be positive and bounded.
+fn validate_deadline_secs(deadline_secs: Option<u64>) -> Result<(), String> {
+    match deadline_secs {
+        Some(0) => Err("deadline_secs must be at least 1".into()),
+        Some(secs) if secs > MAX_SCHEDULE_SECS => {
+            Err(format!("deadline_secs must be at most {}", MAX_SCHEDULE_SECS))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Most urgent first: highest priority, then earliest deadline (tasks
+/// without one last), then oldest.
+fn cmp_urgency(a: &Task, b: &Task) -> std::cmp::Ordering {
+    b.priority
+        .cmp(&a.priority)
+        .then_with(|| match (a.deadline, b.deadline) {
+            (Some(x), Some(y)) => x.cmp(&y),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        })
+        .then_with(|| a.created_at.cmp(&b.created_at))
+        .then_with(|| a.id.cmp(&b.id))
+}
+
+/// Build an episode summary from a session's memories without an LLM.
+///
+/// The summary lists the first few memories; memories tagged `decision` become
+/// key decisions, `tool:<name>` tags name the tools used, and the last memory
+/// tagged `outcome` gives the outcome.
+fn summarize_memories(memories: &[Memory]) -> EpisodeSummary {
+    const PREVIEW: usize = 3;
+    const PREVIEW_CHARS: usize = 120;
+
+    let has_tag = |m: &Memory, tag: &str| m.tags.iter().any(|t| t.eq_ignore_ascii_case(tag));
+
+    let summary = if memories.is_empty() {
+        "No memories were recorded during this session.".to_string()
+    } else {
+        let preview: Vec<String> = memories
+            .iter()
+            .take(PREVIEW)
+            .map(|m| m.content.chars().take(PREVIEW_CHARS).collect())
+            .collect();
+        let mut summary = format!("{} memories recorded: {}", memories.len(), preview.join("; "));
+        if memories.len() > PREVIEW {
+            summary.push_str(&format!(" (and {} more)", memories.len() - PREVIEW));
+        }
+        summary
+    };
+
+    let mut tools_used: Vec<String> = Vec::new();
+    for tool in memories
+        .iter()
+        .flat_map(|m| m.tags.iter())
+        .filter_map(|t| t.strip_prefix("tool:"))
+    {
+        if !tools_used.iter().any(|t| t == tool) {
+            tools_used.push(tool.to_string());
+        }
+    }
+
+    EpisodeSummary {
+        summary,
+        key_decisions: memories
+            .iter()
+            .filter(|m| has_tag(m, "decision"))
+            .map(|m| m.content.clone())
+            .collect(),
+        tools_used,
+        outcome: memories
+            .iter()
+            .rev()
+            .find(|m| has_tag(m, "outcome"))
+            .map(|m| m.content.clone())
+            .unwrap_or_default(),
+    }
+}
+
 /// Core memory engine — manages memories, entities, relationships, and search.
 ///
 /// Integrates:
@@ -25,7 +190,7 @@ fn tokenize(text: &str) -> Vec<String> {
 /// - Inverted index for O(1) keyword search
 /// - LLM extraction pipeline for automatic knowledge extraction
 /// - Vector embeddings for semantic search (with model pool + rayon)
-/// - Snapshot persistence for restart recovery
+/// - Snapshot persistence + write-ahead log for restart recovery
 /// - Replication events for multi-node sync via RaftTimeDB
 pub struct MemoryEngine {
     config: HiveMindConfig,
@@ -34,26 +199,44 @@ pub struct MemoryEngine {
     entities: DashMap<u64, Entity>,
     relationships: DashMap<u64, Relationship>,
     episodes: DashMap<u64, Episode>,
+    sessions: DashMap<String, Session>,
     agents: DashMap<String, Agent>,
     history: DashMap<u64, Vec<MemoryHistory>>,
     // Task stores
     tasks: DashMap<u64, Task>,
     task_events: DashMap<u64, Vec<TaskEvent>>,
-    // Inverted index: word → set of memory IDs containing that word
-    inverted_index: DashMap<String, Vec<u64>>,
-    next_memory_id: AtomicU64,
-    next_entity_id: AtomicU64,
-    next_relationship_id: AtomicU64,
-    next_episode_id: AtomicU64,
-    next_history_id: AtomicU64,
-    next_task_id: AtomicU64,
-    next_task_event_id: AtomicU64,
+    task_templates: DashMap<u64, TaskTemplate>,
+    // Wakes long-polling claim-next callers when a task becomes pending
+    task_available: tokio::sync::Notify,
+    // Mention links: memory ID → entities it mentions, and entity ID → memory IDs
+    mentions: DashMap<u64, Vec<EntityMention>>,
+    entity_mentions: DashMap<u64, Vec<u64>>,
+    // Entity name index: first word of the name → entity IDs, for name matching
+    entity_names: DashMap<String, Vec<u64>>,
+    // Inverted index: word → (memory ID → term frequency)
+    inverted_index: DashMap<String, HashMap<u64, u32>>,
+    // BM25 document lengths: memory ID → indexed token count, plus their sum
+    doc_lengths: DashMap<u64, u32>,
+    total_doc_length: AtomicU64,
+    memory_ids: IdAllocator,
+    entity_ids: IdAllocator,
+    relationship_ids: IdAllocator,
+    episode_ids: IdAllocator,
+    history_ids: IdAllocator,
+    task_ids: IdAllocator,
+    task_event_ids: IdAllocator,
+    template_ids: IdAllocator,
     // Extraction pipeline (LLM-powered)
     extraction: ExtractionPipeline,
     // Embedding engine (vector search)
     embeddings: Arc<EmbeddingEngine>,
-    // Replication event sender (optional)
-    replication_tx: Option<tokio::sync::mpsc::UnboundedSender<ReplicationEvent>>,
+    // Replication events → write-ahead log + RaftTimeDB (both optional)
+    replication: ReplicationSink,
+    // Origin node ID → highest outbox sequence number of its events applied here
+    peer_seqs: DashMap<String, u64>,
+    // Node number → when its last heartbeat arrived, to pick the maintainer
+    live_nodes: DashMap<u16, Instant>,
+    started: Instant,
 }
 
 impl MemoryEngine {
@@ -61,6 +244,7 @@ impl MemoryEngine {
         info!("Initializing memory engine");
         let extraction = ExtractionPipeline::from_hivemind_config(&config);
         let embeddings = Arc::new(EmbeddingEngine::from_hivemind_config(&config));
+        let node_number = config.node_number;
 
         Self {
             config,
@@ -68,30 +252,66 @@ impl MemoryEngine {
             entities: DashMap::new(),
             relationships: DashMap::new(),
             episodes: DashMap::new(),
+            sessions: DashMap::new(),
             agents: DashMap::new(),
             history: DashMap::new(),
             tasks: DashMap::new(),
             task_events: DashMap::new(),
+            task_templates: DashMap::new(),
+            task_available: tokio::sync::Notify::new(),
+            mentions: DashMap::new(),
+            entity_mentions: DashMap::new(),
+            entity_names: DashMap::new(),
             inverted_index: DashMap::new(),
-            next_memory_id: AtomicU64::new(1),
-            next_entity_id: AtomicU64::new(1),
-            next_relationship_id: AtomicU64::new(1),
-            next_episode_id: AtomicU64::new(1),
-            next_history_id: AtomicU64::new(1),
-            next_task_id: AtomicU64::new(1),
-            next_task_event_id: AtomicU64::new(1),
+            doc_lengths: DashMap::new(),
+            total_doc_length: AtomicU64::new(0),
+            memory_ids: IdAllocator::new(node_number),
+            entity_ids: IdAllocator::new(node_number),
+            relationship_ids: IdAllocator::new(node_number),
+            episode_ids: IdAllocator::new(node_number),
+            history_ids: IdAllocator::new(node_number),
+            task_ids: IdAllocator::new(node_number),
+            task_event_ids: IdAllocator::new(node_number),
+            template_ids: IdAllocator::new(node_number),
             extraction,
             embeddings,
-            replication_tx: None,
+            replication: ReplicationSink::default(),
+            peer_seqs: DashMap::new(),
+            live_nodes: DashMap::new(),
+            started: Instant::now(),
         }
     }
 
-    /// Set the replication event sender for RaftTimeDB sync.
-    pub fn set_replication_tx(
-        &mut self,
-        tx: tokio::sync::mpsc::UnboundedSender<ReplicationEvent>,
-    ) {
-        self.replication_tx = Some(tx);
+    /// Set the outbox local writes are queued in for RaftTimeDB sync.
+    pub fn set_replication_outbox(&mut self, outbox: Arc<ReplicationOutbox>) {
+        self.replication.set_outbox(outbox);
+    }
+
+    /// Replication backlog and lag, if replication is enabled.
+    pub fn replication_status(&self) -> Option<ReplicationStatus> {
+        self.replication.status()
+    }
+
+    /// Write-ahead log entries after `after_seq`, served to bootstrapping
+    /// nodes. `None` if some were truncated and a newer snapshot is needed.
+    pub fn replication_events_after(
+        &self,
+        after_seq: u64,
+        limit: usize,
+    ) -> Result<Option<EventPage>, String> {
+        self.replication
+            .events_after(after_seq, limit)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Set the write-ahead log that every mutation is appended to.
+    pub fn set_wal(&mut self, wal: Arc<WriteAheadLog>) {
+        self.replication.set_wal(wal);
+    }
+
+    /// Get a handle to the replication sink (shared with the ChannelHub).
+    pub fn replication_sink(&self) -> ReplicationSink {
+        self.replication.clone()
     }
 
     /// Get a reference to the config.
@@ -106,75 +326,82 @@ impl MemoryEngine {
 
     /// Restore state from a snapshot (called at startup).
     pub fn restore_from_snapshot(&mut self, snapshot: Snapshot) {
-        let mut max_memory_id = 0u64;
-        let mut max_entity_id = 0u64;
-        let mut max_rel_id = 0u64;
-        let mut max_episode_id = 0u64;
-        let mut max_history_id = 0u64;
-        let mut max_task_id = 0u64;
-        let mut max_task_event_id = 0u64;
-
         for memory in snapshot.memories {
-            max_memory_id = max_memory_id.max(memory.id);
+            self.memory_ids.observe(memory.id);
             self.memories.insert(memory.id, memory);
         }
         for entity in snapshot.entities {
-            max_entity_id = max_entity_id.max(entity.id);
-            self.entities.insert(entity.id, entity);
+            self.entity_ids.observe(entity.id);
+            self.put_entity(entity);
         }
         for rel in snapshot.relationships {
-            max_rel_id = max_rel_id.max(rel.id);
+            self.relationship_ids.observe(rel.id);
             self.relationships.insert(rel.id, rel);
         }
         for episode in snapshot.episodes {
-            max_episode_id = max_episode_id.max(episode.id);
+            self.episode_ids.observe(episode.id);
             self.episodes.insert(episode.id, episode);
         }
+        for session in snapshot.sessions {
+            self.sessions.insert(session.session_id.clone(), session);
+        }
         for agent in snapshot.agents {
             self.agents.insert(agent.agent_id.clone(), agent);
         }
         for (memory_id, hist_entries) in snapshot.history {
             for h in &hist_entries {
-                max_history_id = max_history_id.max(h.id);
+                self.history_ids.observe(h.id);
             }
             self.history.insert(memory_id, hist_entries);
         }
         for task in snapshot.tasks {
-            max_task_id = max_task_id.max(task.id);
+            self.task_ids.observe(task.id);
             self.tasks.insert(task.id, task);
         }
         for (task_id, events) in snapshot.task_events {
             for e in &events {
-                max_task_event_id = max_task_event_id.max(e.id);
+                self.task_event_ids.observe(e.id);
             }
             self.task_events.insert(task_id, events);
         }
-
-        // Set counters past the max existing IDs
-        self.next_memory_id.store(max_memory_id + 1, Ordering::Relaxed);
-        self.next_entity_id.store(max_entity_id + 1, Ordering::Relaxed);
-        self.next_relationship_id.store(max_rel_id + 1, Ordering::Relaxed);
-        self.next_episode_id.store(max_episode_id + 1, Ordering::Relaxed);
-        self.next_history_id.store(max_history_id + 1, Ordering::Relaxed);
-        self.next_task_id.store(max_task_id + 1, Ordering::Relaxed);
-        self.next_task_event_id.store(max_task_event_id + 1, Ordering::Relaxed);
+        for template in snapshot.task_templates {
+            self.template_ids.observe(template.id);
+            self.task_templates.insert(template.id, template);
+        }
+        for mention in snapshot.mentions {
+            self.insert_mention(mention);
+        }
+        self.peer_seqs.extend(snapshot.peer_seqs);
 
         // Rebuild inverted index from restored memories
         self.rebuild_inverted_index();
 
+        // Restore persisted vectors (skipped if the embedding model changed)
+        let restored_vectors = snapshot
+            .embeddings
+            .map(|e| self.embeddings.restore_vectors(e))
+            .unwrap_or(0);
+
         info!(
             memories = self.memories.len(),
             entities = self.entities.len(),
             relationships = self.relationships.len(),
             agents = self.agents.len(),
             tasks = self.tasks.len(),
+            task_templates = self.task_templates.len(),
             index_words = self.inverted_index.len(),
+            vectors = restored_vectors,
             "State restored from snapshot"
         );
     }
 
     /// Create a snapshot of current state.
     pub fn create_snapshot(&self) -> Snapshot {
+        // Read the WAL position first: every event up to it has already been
+        // applied to the stores, so the snapshot below covers it. Batches
+        // logged but still being applied are past it and get replayed.
+        let wal_seq = self.replication.wal_seq();
+
         Snapshot {
             version: Snapshot::CURRENT_VERSION,
             created_at: Utc::now(),
@@ -182,17 +409,289 @@ impl MemoryEngine {
             entities: self.entities.iter().map(|e| e.value().clone()).collect(),
             relationships: self.relationships.iter().map(|r| r.value().clone()).collect(),
             episodes: self.episodes.iter().map(|e| e.value().clone()).collect(),
+            sessions: self.sessions.iter().map(|s| s.value().clone()).collect(),
             agents: self.agents.iter().map(|a| a.value().clone()).collect(),
             history: self.history.iter().map(|h| (*h.key(), h.value().clone())).collect(),
-            channels: vec![], // Channels are managed by ChannelHub
+            // Channel state is filled in by ChannelHub::fill_snapshot
+            channels: vec![],
+            channel_subscriptions: vec![],
             tasks: self.tasks.iter().map(|t| t.value().clone()).collect(),
             task_events: self.task_events.iter().map(|e| (*e.key(), e.value().clone())).collect(),
+            task_templates: self.task_templates.iter().map(|t| t.value().clone()).collect(),
+            mentions: self.mentions.iter().flat_map(|m| m.value().clone()).collect(),
+            embeddings: (self.embeddings.indexed_count() > 0)
+                .then(|| self.embeddings.snapshot_vectors()),
+            wal_seq,
+            peer_seqs: self.peer_seqs.iter().map(|p| (p.key().clone(), *p.value())).collect(),
+        }
+    }
+
+    /// Embed valid memories that have no vector yet, in the background.
+    ///
+    /// Called after startup restore: covers memories written since the last
+    /// snapshot. If the persisted index came from another model, or holds
+    /// vectors of another size than the model now produces, a full reindex
+    /// is started instead.
+    pub fn spawn_embedding_backfill(&self) {
+        if !self.embeddings.is_available() {
+            return;
+        }
+
+        let valid: Vec<Memory> = self
+            .memories
+            .iter()
+            .filter(|m| m.value().valid_until.is_none())
+            .map(|m| m.value().clone())
+            .collect();
+        let emb = self.embeddings.clone();
+        tokio::spawn(async move {
+            let reindex_reason = if emb.needs_reindex() {
+                Some("embedding model changed")
+            } else {
+                match emb.dimensions_changed().await {
+                    Ok(changed) => changed.then_some("embedding dimensions changed"),
+                    Err(e) => {
+                        warn!(error = %e, "Could not check embedding dimensions");
+                        None
+                    }
+                }
+            };
+            if let Some(reason) = reindex_reason {
+                if let Err(e) = emb.start_reindex(valid, reason) {
+                    warn!(error = %e, "Could not start embedding reindex");
+                }
+                return;
+            }
+
+            let missing: Vec<Memory> = valid.into_iter().filter(|m| !emb.is_indexed(m.id)).collect();
+            if missing.is_empty() {
+                return;
+            }
+            info!(count = missing.len(), "Starting embedding backfill");
+            let indexed = emb
+                .index_in_batches(&missing, embeddings::BACKFILL_BATCH_SIZE)
+                .await;
+            info!(indexed, total = missing.len(), "Embedding backfill finished");
+        });
+    }
+
+    /// Re-embed every valid memory into a new index, swapped in when done.
+    pub fn start_embedding_reindex(&self, reason: &str) -> Result<EmbeddingReindexStatus, String> {
+        let memories: Vec<Memory> = self
+            .memories
+            .iter()
+            .filter(|m| m.value().valid_until.is_none())
+            .map(|m| m.value().clone())
+            .collect();
+        self.embeddings.start_reindex(memories, reason)
+    }
+
+    /// Log a write's events, then run `apply` to make the write visible.
+    /// If the events can't be logged nothing is applied.
+    fn commit<T>(&self, events: &[ReplicationEvent], apply: impl FnOnce() -> T) -> Result<T, WriteError> {
+        self.replication.commit(events, apply).map_err(|e| {
+            error!(error = %e, "Failed to persist replication events");
+            WriteError::Unavailable
+        })
+    }
+
+    /// Log events, then apply them the way a follower would.
+    fn commit_events(&self, events: Vec<ReplicationEvent>) -> Result<(), WriteError> {
+        self.commit(&events.clone(), || {
+            for event in events {
+                self.apply_replication_event(event);
+            }
+        })
+    }
+
+    /// Log a task's new state (`change`) after the other `events` of the
+    /// write, then store it and apply them. `entry` is the task's slot, held
+    /// since the transition was checked so no other write to it interleaves.
+    fn commit_task(
+        &self,
+        entry: dashmap::mapref::one::RefMut<'_, u64, Task>,
+        task: &Task,
+        change: fn(Task) -> ReplicationEvent,
+        events: Vec<ReplicationEvent>,
+    ) -> Result<(), WriteError> {
+        let mut logged = events.clone();
+        logged.push(change(task.clone()));
+        self.commit(&logged, || {
+            let mut entry = entry;
+            *entry = task.clone();
+            drop(entry);
+            for event in events {
+                self.apply_replication_event(event);
+            }
+            if task.status == TaskStatus::Pending {
+                self.task_available.notify_waiters();
+            }
+        })
+    }
+
+    /// Apply an event committed by another node. It is logged to the local WAL
+    /// so it survives a restart, but not replicated again. Memory writes are
+    /// re-embedded here since vectors aren't part of the event. `receipt` is
+    /// the event's [`ReplicationEvent::PeerEventApplied`] (see
+    /// [`Self::commit_inbound`]).
+    pub fn apply_inbound_event(&self, event: ReplicationEvent, receipt: Option<ReplicationEvent>) {
+        let reembed = match &event {
+            ReplicationEvent::MemoryAdded { memory } | ReplicationEvent::MemoryUpdated { memory }
+                if memory.valid_until.is_none() =>
+            {
+                Some(memory.clone())
+            }
+            _ => None,
+        };
+        let logged = event.clone();
+        self.commit_inbound(logged, receipt, || self.apply_replication_event(event));
+
+        if let Some(mem) = reembed
+            && self.embeddings.is_available()
+        {
+            let emb = self.embeddings.clone();
+            tokio::spawn(async move {
+                if let Err(e) = emb.index_memory(&mem).await {
+                    warn!(memory_id = mem.id, error = %e, "Failed to index replicated memory embedding");
+                }
+            });
         }
     }
 
-    fn emit_replication(&self, event: ReplicationEvent) {
-        if let Some(ref tx) = self.replication_tx {
-            let _ = tx.send(event);
+    /// Log an event from another node together with its receipt, then run
+    /// `apply` and record the receipt, so the origin's resends are skipped
+    /// (see [`Self::peer_seq`]) also after a restart.
+    pub fn commit_inbound<T>(
+        &self,
+        event: ReplicationEvent,
+        receipt: Option<ReplicationEvent>,
+        apply: impl FnOnce() -> T,
+    ) -> T {
+        let mut logged = vec![event];
+        logged.extend(receipt.clone());
+        self.replication.commit_inbound(&logged, || {
+            let result = apply();
+            if let Some(receipt) = receipt {
+                self.apply_replication_event(receipt);
+            }
+            result
+        })
+    }
+
+    /// Highest outbox sequence number of `origin`'s events applied here.
+    pub fn peer_seq(&self, origin: &str) -> u64 {
+        self.peer_seqs.get(origin).map(|seq| *seq).unwrap_or(0)
+    }
+
+    /// Apply a previously recorded replication event (write-ahead log replay).
+    ///
+    /// Application is idempotent: records are upserted by ID, so replaying an
+    /// event that is already reflected in the snapshot is harmless.
+    pub fn apply_replication_event(&self, event: ReplicationEvent) {
+        match event {
+            ReplicationEvent::MemoryAdded { memory } | ReplicationEvent::MemoryUpdated { memory } => {
+                self.memory_ids.observe(memory.id);
+                if let Some(old) = self.memories.get(&memory.id).map(|m| m.clone()) {
+                    if old.valid_until.is_none() {
+                        self.unindex_memory_words(old.id, &old.content, &old.tags);
+                    }
+                    // The vector (e.g. restored from a snapshot) was embedded from
+                    // the old content; drop it so the backfill re-embeds the memory
+                    if old.content != memory.content || memory.valid_until.is_some() {
+                        self.embeddings.remove_memory(memory.id);
+                    }
+                }
+                if memory.valid_until.is_none() {
+                    self.index_memory_words(memory.id, &memory.content, &memory.tags);
+                }
+                self.memories.insert(memory.id, memory);
+            }
+            ReplicationEvent::MemoryInvalidated {
+                memory_id,
+                invalidated_at,
+                ..
+            } => {
+                if let Some(mut entry) = self.memories.get_mut(&memory_id)
+                    && entry.valid_until.is_none()
+                {
+                    let at = invalidated_at.unwrap_or_else(Utc::now);
+                    entry.valid_until = Some(at);
+                    entry.updated_at = at;
+                    self.unindex_memory_words(memory_id, &entry.content, &entry.tags);
+                    self.embeddings.remove_memory(memory_id);
+                }
+            }
+            ReplicationEvent::MemoryHistoryRecorded { history } => {
+                self.history_ids.observe(history.id);
+                let mut entries = self.history.entry(history.memory_id).or_default();
+                if !entries.iter().any(|h| h.id == history.id) {
+                    entries.push(history);
+                }
+            }
+            ReplicationEvent::EntityAdded { entity } | ReplicationEvent::EntityUpdated { entity } => {
+                self.entity_ids.observe(entity.id);
+                self.put_entity(entity);
+            }
+            ReplicationEvent::RelationshipAdded { relationship }
+            | ReplicationEvent::RelationshipInvalidated { relationship } => {
+                self.relationship_ids.observe(relationship.id);
+                self.relationships.insert(relationship.id, relationship);
+            }
+            ReplicationEvent::MentionAdded { mention } => {
+                self.insert_mention(mention);
+            }
+            ReplicationEvent::SessionStarted { session } => {
+                self.sessions.insert(session.session_id.clone(), session);
+            }
+            ReplicationEvent::EpisodeRecorded { episode } => {
+                self.episode_ids.observe(episode.id);
+                self.sessions.remove(&episode.session_id);
+                self.episodes.insert(episode.id, episode);
+            }
+            ReplicationEvent::AgentRegistered { agent } | ReplicationEvent::AgentUpdated { agent } => {
+                self.agents.insert(agent.agent_id.clone(), agent);
+            }
+            ReplicationEvent::TaskCreated { task }
+            | ReplicationEvent::TaskClaimed { task }
+            | ReplicationEvent::TaskStarted { task }
+            | ReplicationEvent::TaskCompleted { task }
+            | ReplicationEvent::TaskFailed { task }
+            | ReplicationEvent::TaskCancelled { task }
+            | ReplicationEvent::TaskReassigned { task }
+            | ReplicationEvent::TaskUnblocked { task }
+            | ReplicationEvent::TaskUpdated { task } => {
+                self.task_ids.observe(task.id);
+                let pending = task.status == TaskStatus::Pending;
+                self.tasks.insert(task.id, task);
+                if pending {
+                    self.task_available.notify_waiters();
+                }
+            }
+            ReplicationEvent::TaskTemplateCreated { template }
+            | ReplicationEvent::TaskTemplateUpdated { template } => {
+                self.template_ids.observe(template.id);
+                self.task_templates.insert(template.id, template);
+            }
+            ReplicationEvent::TaskTemplateDeleted { template_id } => {
+                self.task_templates.remove(&template_id);
+            }
+            ReplicationEvent::TaskEventRecorded { event } => {
+                self.task_event_ids.observe(event.id);
+                let mut events = self.task_events.entry(event.task_id).or_default();
+                if !events.iter().any(|e| e.id == event.id) {
+                    events.push(event);
+                }
+            }
+            ReplicationEvent::BenchmarkDataRemoved { agent_id } => {
+                self.remove_benchmark_data(&agent_id);
+            }
+            // Channels live in the ChannelHub (see ChannelHub::apply_channel_created)
+            ReplicationEvent::ChannelCreated { .. } | ReplicationEvent::ChannelSubscribed { .. } => {}
+            ReplicationEvent::NodeHeartbeat { node_number } => self.record_node_heartbeat(node_number),
+            ReplicationEvent::PeerEventApplied { origin, seq } => {
+                let mut last = self.peer_seqs.entry(origin).or_insert(0);
+                *last = (*last).max(seq);
+            }
         }
     }
 
@@ -200,33 +699,51 @@ impl MemoryEngine {
     // Inverted Index Maintenance
     // ========================================================================
 
-    /// Add a memory's words to the inverted index.
-    fn index_memory_words(&self, id: u64, content: &str, tags: &[String]) {
+    /// Count term frequencies over a memory's content and tags.
+    fn term_frequencies(content: &str, tags: &[String]) -> (HashMap<String, u32>, u32) {
         let mut words = tokenize(content);
         for tag in tags {
             words.extend(tokenize(tag));
         }
+        let length = words.len() as u32;
+        let mut frequencies: HashMap<String, u32> = HashMap::new();
         for word in words {
-            self.inverted_index.entry(word).or_default().push(id);
+            *frequencies.entry(word).or_insert(0) += 1;
         }
+        (frequencies, length)
+    }
+
+    /// Add a memory's words to the inverted index and record its length.
+    fn index_memory_words(&self, id: u64, content: &str, tags: &[String]) {
+        let (frequencies, length) = Self::term_frequencies(content, tags);
+        for (word, tf) in frequencies {
+            self.inverted_index.entry(word).or_default().insert(id, tf);
+        }
+        if let Some(old) = self.doc_lengths.insert(id, length) {
+            self.total_doc_length.fetch_sub(old as u64, Ordering::Relaxed);
+        }
+        self.total_doc_length.fetch_add(length as u64, Ordering::Relaxed);
     }
 
     /// Remove a memory's words from the inverted index.
     fn unindex_memory_words(&self, id: u64, content: &str, tags: &[String]) {
-        let mut words = tokenize(content);
-        for tag in tags {
-            words.extend(tokenize(tag));
-        }
-        for word in words {
-            if let Some(mut ids) = self.inverted_index.get_mut(&word) {
-                ids.retain(|&mid| mid != id);
+        let (frequencies, _) = Self::term_frequencies(content, tags);
+        for word in frequencies.keys() {
+            if let Some(mut postings) = self.inverted_index.get_mut(word) {
+                postings.remove(&id);
             }
+            self.inverted_index.remove_if(word, |_, postings| postings.is_empty());
+        }
+        if let Some((_, length)) = self.doc_lengths.remove(&id) {
+            self.total_doc_length.fetch_sub(length as u64, Ordering::Relaxed);
         }
     }
 
     /// Rebuild the entire inverted index from scratch (used after snapshot restore).
     fn rebuild_inverted_index(&self) {
         self.inverted_index.clear();
+        self.doc_lengths.clear();
+        self.total_doc_length.store(0, Ordering::Relaxed);
         for entry in self.memories.iter() {
             let m = entry.value();
             if m.valid_until.is_none() {
@@ -239,8 +756,32 @@ impl MemoryEngine {
     // Memory CRUD
     // ========================================================================
 
-    pub fn add_memory(&self, req: AddMemoryRequest) -> Memory {
-        let id = self.next_memory_id.fetch_add(1, Ordering::Relaxed);
+    pub fn add_memory(&self, req: AddMemoryRequest) -> Result<Memory, WriteError> {
+        let (memory, events) = self.new_memory(req, "Initial creation");
+        let id = memory.id;
+        // Stores the memory and indexes its words for keyword search
+        self.commit_events(events)?;
+
+        // Async: index embedding (fire-and-forget)
+        if self.embeddings.is_available() {
+            let emb = self.embeddings.clone();
+            let mem = memory.clone();
+            tokio::spawn(async move {
+                if let Err(e) = emb.index_memory(&mem).await {
+                    warn!(memory_id = mem.id, error = %e, "Failed to index memory embedding");
+                }
+            });
+        }
+
+        self.link_entity_names(&memory);
+
+        info!(id, "Memory added");
+        Ok(memory)
+    }
+
+    /// Build a new memory and the events that add it with its history entry.
+    fn new_memory(&self, req: AddMemoryRequest, reason: &str) -> (Memory, Vec<ReplicationEvent>) {
+        let id = self.memory_ids.next();
         let now = Utc::now();
 
         let memory = Memory {
@@ -261,40 +802,24 @@ impl MemoryEngine {
         };
 
         // Record history
-        let hist_id = self.next_history_id.fetch_add(1, Ordering::Relaxed);
+        let hist_id = self.history_ids.next();
         let hist = MemoryHistory {
             id: hist_id,
             memory_id: id,
             operation: Operation::Add,
             old_content: None,
             new_content: req.content,
-            reason: "Initial creation".into(),
+            reason: reason.into(),
             changed_by: memory.source.clone(),
             timestamp: now,
         };
-        self.history.entry(id).or_default().push(hist);
-        self.memories.insert(id, memory.clone());
-
-        // Index in inverted index for fast keyword search
-        self.index_memory_words(id, &memory.content, &memory.tags);
-
-        // Async: index embedding (fire-and-forget)
-        if self.embeddings.is_available() {
-            let emb = self.embeddings.clone();
-            let mem = memory.clone();
-            tokio::spawn(async move {
-                if let Err(e) = emb.index_memory(&mem).await {
-                    warn!(memory_id = mem.id, error = %e, "Failed to index memory embedding");
-                }
-            });
-        }
-
-        self.emit_replication(ReplicationEvent::MemoryAdded {
-            memory: memory.clone(),
-        });
-
-        info!(id, "Memory added");
-        memory
+        let events = vec![
+            ReplicationEvent::MemoryHistoryRecorded { history: hist },
+            ReplicationEvent::MemoryAdded {
+                memory: memory.clone(),
+            },
+        ];
+        (memory, events)
     }
 
     pub fn get_memory(&self, id: u64) -> Option<Memory> {
@@ -306,44 +831,50 @@ impl MemoryEngine {
         id: u64,
         req: UpdateMemoryRequest,
         changed_by: &str,
-    ) -> Option<Memory> {
-        let mut entry = self.memories.get_mut(&id)?;
-        let old_content = entry.content.clone();
-        let old_tags = entry.tags.clone();
+    ) -> Result<Option<Memory>, WriteError> {
+        let Some(mut entry) = self.memories.get_mut(&id) else {
+            return Ok(None);
+        };
+        let mut memory = entry.clone();
 
         if let Some(content) = &req.content {
-            entry.content = content.clone();
+            memory.content = content.clone();
         }
         if let Some(tags) = req.tags {
-            entry.tags = tags;
+            memory.tags = tags;
         }
         if let Some(confidence) = req.confidence {
-            entry.confidence = confidence;
+            memory.confidence = confidence;
         }
         if let Some(metadata) = req.metadata {
-            entry.metadata = metadata;
+            memory.metadata = metadata;
         }
-        entry.updated_at = Utc::now();
+        memory.updated_at = Utc::now();
 
         // Record history
-        let hist_id = self.next_history_id.fetch_add(1, Ordering::Relaxed);
+        let hist_id = self.history_ids.next();
         let hist = MemoryHistory {
             id: hist_id,
             memory_id: id,
             operation: Operation::Update,
-            old_content: Some(old_content.clone()),
-            new_content: entry.content.clone(),
+            old_content: Some(entry.content.clone()),
+            new_content: memory.content.clone(),
             reason: "Manual update".into(),
             changed_by: changed_by.into(),
             timestamp: Utc::now(),
         };
-        self.history.entry(id).or_default().push(hist);
-
-        let memory = entry.clone();
-
-        // Update inverted index
-        self.unindex_memory_words(id, &old_content, &old_tags);
-        self.index_memory_words(id, &memory.content, &memory.tags);
+        let history = ReplicationEvent::MemoryHistoryRecorded { history: hist };
+        let updated = ReplicationEvent::MemoryUpdated {
+            memory: memory.clone(),
+        };
+        self.commit(&[history.clone(), updated], || {
+            // Update inverted index
+            self.unindex_memory_words(id, &entry.content, &entry.tags);
+            self.index_memory_words(id, &memory.content, &memory.tags);
+            *entry = memory.clone();
+            drop(entry);
+            self.apply_replication_event(history);
+        })?;
 
         // Re-index embedding if content changed
         if req.content.is_some() && self.embeddings.is_available() {
@@ -356,45 +887,56 @@ impl MemoryEngine {
             });
         }
 
-        self.emit_replication(ReplicationEvent::MemoryUpdated {
-            memory: memory.clone(),
-        });
+        if req.content.is_some() {
+            self.link_entity_names(&memory);
+        }
 
         info!(id, "Memory updated");
-        Some(memory)
+        Ok(Some(memory))
     }
 
-    pub fn invalidate_memory(&self, id: u64, reason: &str, changed_by: &str) -> Option<Memory> {
-        let mut entry = self.memories.get_mut(&id)?;
-        entry.valid_until = Some(Utc::now());
-        entry.updated_at = Utc::now();
+    pub fn invalidate_memory(
+        &self,
+        id: u64,
+        reason: &str,
+        changed_by: &str,
+    ) -> Result<Option<Memory>, WriteError> {
+        let Some(mut entry) = self.memories.get_mut(&id) else {
+            return Ok(None);
+        };
+        let now = Utc::now();
+        let mut memory = entry.clone();
+        memory.valid_until = Some(now);
+        memory.updated_at = now;
 
-        let hist_id = self.next_history_id.fetch_add(1, Ordering::Relaxed);
+        let hist_id = self.history_ids.next();
         let hist = MemoryHistory {
             id: hist_id,
             memory_id: id,
             operation: Operation::Invalidate,
-            old_content: Some(entry.content.clone()),
-            new_content: entry.content.clone(),
+            old_content: Some(memory.content.clone()),
+            new_content: memory.content.clone(),
             reason: reason.into(),
             changed_by: changed_by.into(),
-            timestamp: Utc::now(),
+            timestamp: now,
         };
-        self.history.entry(id).or_default().push(hist);
-
-        // Remove from embedding index and inverted index
-        self.embeddings.remove_memory(id);
-        self.unindex_memory_words(id, &entry.content, &entry.tags);
-
-        let memory = entry.clone();
-
-        self.emit_replication(ReplicationEvent::MemoryInvalidated {
+        let history = ReplicationEvent::MemoryHistoryRecorded { history: hist };
+        let invalidated = ReplicationEvent::MemoryInvalidated {
             memory_id: id,
             reason: reason.into(),
-        });
+            invalidated_at: Some(now),
+        };
+        self.commit(&[history.clone(), invalidated], || {
+            // Remove from embedding index and inverted index
+            self.embeddings.remove_memory(id);
+            self.unindex_memory_words(id, &entry.content, &entry.tags);
+            *entry = memory.clone();
+            drop(entry);
+            self.apply_replication_event(history);
+        })?;
 
         info!(id, reason, "Memory invalidated");
-        Some(memory)
+        Ok(Some(memory))
     }
 
     pub fn get_memory_history(&self, memory_id: u64) -> Vec<MemoryHistory> {
@@ -412,26 +954,46 @@ impl MemoryEngine {
     ///
     /// If embeddings are available, uses 70% vector + 30% keyword scoring.
     /// Falls back to pure keyword search if embeddings are not configured.
-    pub fn search(&self, req: &SearchRequest) -> Vec<SearchResult> {
-        self.search_keyword(req)
+    ///
+    /// The query is parsed with the [`query`](crate::query) language.
+    pub fn search(&self, req: &SearchRequest) -> Result<Vec<SearchResult>, QueryError> {
+        let mut results = self.search_keyword(req)?;
+        if req.include_graph {
+            self.attach_graph_context(&mut results);
+        }
+        Ok(results)
     }
 
     /// Async search that includes vector similarity when embeddings are available.
-    pub async fn search_hybrid(&self, req: &SearchRequest) -> Vec<SearchResult> {
-        // Get keyword results
-        let keyword_results = self.search_keyword(req);
+    pub async fn search_hybrid(&self, req: &SearchRequest) -> Result<Vec<SearchResult>, QueryError> {
+        let mut results = self.rank_hybrid(req).await?;
+        if req.include_graph {
+            self.attach_graph_context(&mut results);
+        }
+        Ok(results)
+    }
+
+    async fn rank_hybrid(&self, req: &SearchRequest) -> Result<Vec<SearchResult>, QueryError> {
+        let query = parse_query(req)?;
 
-        // If embeddings aren't available, return keyword results
-        if !self.embeddings.is_available() || self.embeddings.indexed_count() == 0 {
-            return keyword_results;
+        // Get keyword results
+        let keyword_results = self.search_parsed(&query, req);
+
+        // If embeddings aren't available (or there is nothing to embed), return keyword results
+        let semantic_text = query.semantic_text();
+        if !self.embeddings.is_available()
+            || self.embeddings.indexed_count() == 0
+            || semantic_text.is_empty()
+        {
+            return Ok(keyword_results);
         }
 
         // Get vector similarity scores
-        let vector_scores = match self.embeddings.search(&req.query, req.limit * 2).await {
+        let vector_scores = match self.embeddings.search(&semantic_text, req.limit * 2).await {
             Ok(scores) => scores,
             Err(e) => {
                 warn!(error = %e, "Vector search failed, using keyword only");
-                return keyword_results;
+                return Ok(keyword_results);
             }
         };
 
@@ -468,6 +1030,9 @@ impl MemoryEngine {
                             continue;
                         }
                     }
+                    if !query.matches_semantic(&memory) {
+                        continue;
+                    }
                     if *vec_score > 0.3 {
                         // Minimum threshold for vector-only results
                         results.push(SearchResult {
@@ -483,33 +1048,89 @@ impl MemoryEngine {
 
         results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
         results.truncate(req.limit);
-        results
+        Ok(results)
     }
 
-    /// Keyword search using the inverted index for O(1) word lookups.
-    fn search_keyword(&self, req: &SearchRequest) -> Vec<SearchResult> {
-        let query_words = tokenize(&req.query);
-        if query_words.is_empty() {
+    /// Fill in each result's knowledge-graph context: the entities its
+    /// memory mentions and their live relationships.
+    fn attach_graph_context(&self, results: &mut [SearchResult]) {
+        for result in results {
+            let entities = self.get_memory_entities(result.memory.id);
+            let entity_ids: std::collections::HashSet<u64> =
+                entities.iter().map(|e| e.id).collect();
+
+            let mut relationships: Vec<Relationship> = self
+                .relationships
+                .iter()
+                .filter(|r| {
+                    let rel = r.value();
+                    rel.valid_until.is_none()
+                        && (entity_ids.contains(&rel.source_entity_id)
+                            || entity_ids.contains(&rel.target_entity_id))
+                })
+                .map(|r| r.value().clone())
+                .collect();
+            relationships.sort_by_key(|r| r.id);
+
+            result.related_entities = entities;
+            result.related_relationships = relationships;
+        }
+    }
+
+    /// Keyword search using the inverted index, ranked by BM25.
+    fn search_keyword(&self, req: &SearchRequest) -> Result<Vec<SearchResult>, QueryError> {
+        let query = parse_query(req)?;
+        Ok(self.search_parsed(&query, req))
+    }
+
+    /// Evaluate a parsed query: BM25 over its words, then boolean matching.
+    ///
+    /// Scores are divided by the best candidate's score so they land in
+    /// 0..=1 and can be blended with vector similarity. Queries made only of
+    /// filters score every match 1.0.
+    fn search_parsed(&self, query: &Query, req: &SearchRequest) -> Vec<SearchResult> {
+        if query.root.is_none() {
             return vec![];
         }
+        let query_words = query.scoring_terms();
 
-        // Use the inverted index to find candidate memory IDs
-        let mut candidate_counts: std::collections::HashMap<u64, usize> =
-            std::collections::HashMap::new();
+        let doc_count = self.doc_lengths.len() as f32;
+        let avg_length = if doc_count > 0.0 {
+            (self.total_doc_length.load(Ordering::Relaxed) as f32 / doc_count).max(1.0)
+        } else {
+            1.0
+        };
+        let (k1, b) = (self.config.bm25_k1, self.config.bm25_b);
 
+        // Accumulate BM25 over the postings of each query term
+        let mut candidate_scores: HashMap<u64, f32> = HashMap::new();
         for word in &query_words {
-            if let Some(ids) = self.inverted_index.get(word) {
-                for &id in ids.value() {
-                    *candidate_counts.entry(id).or_insert(0) += 1;
-                }
+            let Some(postings) = self.inverted_index.get(word) else {
+                continue;
+            };
+            let df = postings.len() as f32;
+            let idf = (1.0 + (doc_count - df + 0.5) / (df + 0.5)).ln();
+            for (&id, &tf) in postings.value() {
+                let length = self.doc_lengths.get(&id).map(|l| *l as f32).unwrap_or(avg_length);
+                let tf = tf as f32;
+                let norm = k1 * (1.0 - b + b * length / avg_length);
+                *candidate_scores.entry(id).or_insert(0.0) += idf * tf * (k1 + 1.0) / (tf + norm);
+            }
+        }
+
+        // Filters, NOT and word-less OR branches can match memories that
+        // share no words with the query, so every memory is a candidate.
+        if query.needs_full_scan() {
+            let base = if query_words.is_empty() { 1.0 } else { 0.0 };
+            for entry in self.memories.iter() {
+                candidate_scores.entry(*entry.key()).or_insert(base);
             }
         }
 
         // Score and filter candidates
-        let total_words = query_words.len();
-        let mut results: Vec<SearchResult> = candidate_counts
+        let mut results: Vec<SearchResult> = candidate_scores
             .into_iter()
-            .filter_map(|(id, match_count)| {
+            .filter_map(|(id, score)| {
                 let m = self.memories.get(&id)?;
                 let m = m.value();
 
@@ -533,8 +1154,9 @@ impl MemoryEngine {
                 if !req.tags.is_empty() && !req.tags.iter().any(|t| m.tags.contains(t)) {
                     return None;
                 }
-
-                let score = match_count as f32 / total_words as f32;
+                if !query.matches(m) {
+                    return None;
+                }
 
                 Some(SearchResult {
                     memory: m.clone(),
@@ -545,8 +1167,18 @@ impl MemoryEngine {
             })
             .collect();
 
-        results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        results.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(b.memory.updated_at.cmp(&a.memory.updated_at))
+        });
         results.truncate(req.limit);
+        if let Some(best) = results.first().map(|r| r.score).filter(|s| *s > 0.0) {
+            for r in &mut results {
+                r.score /= best;
+            }
+        }
         results
     }
 
@@ -554,70 +1186,39 @@ impl MemoryEngine {
     ///
     /// More efficient than calling add_memory in a loop because it:
     /// - Batch-embeds all memories in one ONNX call
-    /// - Reduces per-call overhead
-    pub fn add_memories_bulk(&self, requests: Vec<AddMemoryRequest>) -> Vec<Memory> {
+    /// - Logs them in one write, so either all of them are added or none is
+    pub fn add_memories_bulk(&self, requests: Vec<AddMemoryRequest>) -> Result<Vec<Memory>, WriteError> {
         let mut memories = Vec::with_capacity(requests.len());
+        let mut events = Vec::with_capacity(requests.len() * 2);
 
         for req in requests {
-            let id = self.next_memory_id.fetch_add(1, Ordering::Relaxed);
-            let now = Utc::now();
-
-            let memory = Memory {
-                id,
-                content: req.content.clone(),
-                memory_type: req.memory_type,
-                agent_id: req.agent_id.clone(),
-                user_id: req.user_id.clone(),
-                session_id: req.session_id,
-                confidence: 1.0,
-                tags: req.tags,
-                created_at: now,
-                updated_at: now,
-                valid_from: now,
-                valid_until: None,
-                source: req.agent_id.unwrap_or_else(|| "unknown".into()),
-                metadata: req.metadata,
-            };
-
-            // Record history
-            let hist_id = self.next_history_id.fetch_add(1, Ordering::Relaxed);
-            let hist = MemoryHistory {
-                id: hist_id,
-                memory_id: id,
-                operation: Operation::Add,
-                old_content: None,
-                new_content: req.content,
-                reason: "Initial creation (bulk)".into(),
-                changed_by: memory.source.clone(),
-                timestamp: now,
-            };
-            self.history.entry(id).or_default().push(hist);
-            self.memories.insert(id, memory.clone());
-
-            // Index in inverted index
-            self.index_memory_words(id, &memory.content, &memory.tags);
+            let (memory, added) = self.new_memory(req, "Initial creation (bulk)");
+            events.extend(added);
+            memories.push(memory);
+        }
+        self.commit_events(events)?;
+        self.index_new_memories(&memories);
 
-            self.emit_replication(ReplicationEvent::MemoryAdded {
-                memory: memory.clone(),
-            });
+        info!(count = memories.len(), "Bulk memories added");
+        Ok(memories)
+    }
 
-            memories.push(memory);
+    /// Link and batch-embed memories just added (fire-and-forget embedding).
+    fn index_new_memories(&self, memories: &[Memory]) {
+        for memory in memories {
+            self.link_entity_names(memory);
         }
 
-        // Batch-embed all memories at once (fire-and-forget)
         if self.embeddings.is_available() && !memories.is_empty() {
             let emb = self.embeddings.clone();
-            let mems = memories.clone();
+            let mems = memories.to_vec();
             tokio::spawn(async move {
                 if let Err(e) = emb.index_memories(&mems).await {
                     warn!(count = mems.len(), error = %e, "Failed to batch-index memory embeddings");
                 }
             });
         }
-
-        info!(count = memories.len(), "Bulk memories added");
-        memories
-    }
+    }
 
     /// Get all memories, optionally filtered by agent/user.
     pub fn list_memories(
@@ -660,6 +1261,8 @@ impl MemoryEngine {
     /// - Creates entities in the knowledge graph
     /// - Creates relationships between entities
     /// - Handles conflict resolution (add/update/noop)
+    ///
+    /// A write that can't be logged fails the extraction with a [`WriteError`].
     pub async fn extract_and_store(
         &self,
         req: &ExtractRequest,
@@ -687,6 +1290,7 @@ impl MemoryEngine {
             relationships_added: vec![],
             skipped: 0,
         };
+        let mut stored_facts = Vec::new();
 
         // Process extracted facts
         for fact in &result.facts {
@@ -703,7 +1307,8 @@ impl MemoryEngine {
                             "confidence": fact.confidence,
                             "extracted": true,
                         }),
-                    });
+                    })?;
+                    stored_facts.push((memory.clone(), fact));
                     response.memories_added.push(memory);
                 }
                 ExtractionOperation::Update => {
@@ -717,7 +1322,8 @@ impl MemoryEngine {
                                 metadata: None,
                             },
                             req.agent_id.as_deref().unwrap_or("extraction"),
-                        ) {
+                        )? {
+                            stored_facts.push((updated.clone(), fact));
                             response.memories_updated.push(updated);
                         }
                     } else {
@@ -733,7 +1339,8 @@ impl MemoryEngine {
                                 "confidence": fact.confidence,
                                 "extracted": true,
                             }),
-                        });
+                        })?;
+                        stored_facts.push((memory.clone(), fact));
                         response.memories_added.push(memory);
                     }
                 }
@@ -755,7 +1362,7 @@ impl MemoryEngine {
                 description: entity.description.clone(),
                 agent_id: req.agent_id.clone(),
                 metadata: serde_json::json!({"extracted": true}),
-            });
+            })?;
             response.entities_added.push(e);
         }
 
@@ -773,11 +1380,32 @@ impl MemoryEngine {
                     weight: 1.0,
                     created_by: req.agent_id.clone().unwrap_or_else(|| "extraction".into()),
                     metadata: serde_json::json!({"extracted": true}),
-                });
+                })?;
                 response.relationships_added.push(r);
             }
         }
 
+        // Link stored facts to the entities they mention. Facts without an
+        // explicit list fall back to the batch entities named in their content.
+        for (memory, fact) in &stored_facts {
+            let names: Vec<&str> = if fact.entities.is_empty() {
+                let words = tokenize(&memory.content);
+                result
+                    .entities
+                    .iter()
+                    .map(|e| e.name.as_str())
+                    .filter(|name| mentions_name(&words, name))
+                    .collect()
+            } else {
+                fact.entities.iter().map(|n| n.as_str()).collect()
+            };
+            for name in names {
+                if let Some(entity) = self.find_entity_by_name(name) {
+                    self.link_memory_entity(memory.id, entity.id, MentionSource::Extraction)?;
+                }
+            }
+        }
+
         info!(
             added = response.memories_added.len(),
             updated = response.memories_updated.len(),
@@ -790,12 +1418,211 @@ impl MemoryEngine {
         Ok(response)
     }
 
+    // ========================================================================
+    // Sessions & Episodes
+    // ========================================================================
+
+    /// Open a session. Starting a session that is already open returns it unchanged.
+    pub fn start_session(&self, req: StartSessionRequest) -> Result<Session, WriteError> {
+        let session_id = req
+            .session_id
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        if let Some(existing) = self.sessions.get(&session_id) {
+            return Ok(existing.clone());
+        }
+
+        let session = Session {
+            session_id: session_id.clone(),
+            agent_id: req.agent_id,
+            user_id: req.user_id,
+            started_at: Utc::now(),
+            metadata: req.metadata,
+        };
+        self.commit_events(vec![ReplicationEvent::SessionStarted {
+            session: session.clone(),
+        }])?;
+
+        info!(session_id = %session_id, agent_id = %session.agent_id, "Session started");
+        Ok(session)
+    }
+
+    /// Sessions that have been started but not ended.
+    pub fn list_sessions(&self, agent_id: Option<&str>) -> Vec<Session> {
+        let mut sessions: Vec<Session> = self
+            .sessions
+            .iter()
+            .filter(|s| agent_id.is_none_or(|a| s.value().agent_id == a))
+            .map(|s| s.value().clone())
+            .collect();
+        sessions.sort_by_key(|s| s.started_at);
+        sessions
+    }
+
+    /// End a session and record it as an episode.
+    ///
+    /// Fields not given in the request are derived from the memories recorded
+    /// under the session ID — by the LLM pipeline when `summarize` is set and
+    /// it is available, otherwise heuristically (see `summarize_memories`).
+    /// Sessions never started through the API can still be ended if the
+    /// request names the agent.
+    pub async fn end_session(&self, session_id: &str, req: EndSessionRequest) -> Result<Episode, WriteError> {
+        let mut memories: Vec<Memory> = self
+            .memories
+            .iter()
+            .filter(|m| {
+                let m = m.value();
+                m.valid_until.is_none() && m.session_id.as_deref() == Some(session_id)
+            })
+            .map(|m| m.value().clone())
+            .collect();
+        memories.sort_by_key(|m| (m.created_at, m.id));
+
+        let started = self.sessions.remove(session_id).map(|(_, session)| session);
+        let session = match started.clone() {
+            Some(session) => session,
+            None => Session {
+                session_id: session_id.to_string(),
+                agent_id: req
+                    .agent_id
+                    .clone()
+                    .ok_or_else(|| format!("Session {} not found", session_id))?,
+                user_id: req.user_id.clone(),
+                started_at: memories.first().map(|m| m.created_at).unwrap_or_else(Utc::now),
+                metadata: serde_json::Value::Null,
+            },
+        };
+
+        let mut summarized_by = "heuristic";
+        let derived = if req.summarize && self.extraction.is_available() && !memories.is_empty() {
+            match self.extraction.summarize_episode(&memories).await {
+                Ok(summary) => {
+                    summarized_by = "llm";
+                    summary
+                }
+                Err(e) => {
+                    warn!(session_id, error = %e, "LLM episode summary failed, using heuristic");
+                    summarize_memories(&memories)
+                }
+            }
+        } else {
+            summarize_memories(&memories)
+        };
+
+        let mut metadata = serde_json::json!({
+            "memory_count": memories.len(),
+            "summarized_by": summarized_by,
+        });
+        for extra in [session.metadata, req.metadata] {
+            if let (serde_json::Value::Object(extra), Some(metadata)) = (extra, metadata.as_object_mut()) {
+                metadata.extend(extra);
+            }
+        }
+
+        let episode = Episode {
+            id: self.episode_ids.next(),
+            agent_id: session.agent_id,
+            user_id: session.user_id.or(req.user_id),
+            session_id: session.session_id,
+            summary: req.summary.unwrap_or(derived.summary),
+            key_decisions: if req.key_decisions.is_empty() {
+                derived.key_decisions
+            } else {
+                req.key_decisions
+            },
+            tools_used: if req.tools_used.is_empty() {
+                derived.tools_used
+            } else {
+                req.tools_used
+            },
+            outcome: req
+                .outcome
+                .or(Some(derived.outcome).filter(|o| !o.is_empty()))
+                .unwrap_or_else(|| "completed".into()),
+            started_at: session.started_at,
+            ended_at: Utc::now(),
+            metadata,
+        };
+        if let Err(e) = self.commit_events(vec![ReplicationEvent::EpisodeRecorded {
+            episode: episode.clone(),
+        }]) {
+            // Still open: it can be ended again
+            if let Some(session) = started {
+                self.sessions.insert(session.session_id.clone(), session);
+            }
+            return Err(e);
+        }
+
+        info!(id = episode.id, session_id, memories = memories.len(), "Episode recorded");
+        Ok(episode)
+    }
+
+    pub fn get_episode(&self, id: u64) -> Option<Episode> {
+        self.episodes.get(&id).map(|e| e.clone())
+    }
+
+    /// Episodes matching the filter, most recently ended first.
+    pub fn list_episodes(&self, query: &EpisodeQuery) -> Vec<Episode> {
+        let mut episodes: Vec<Episode> = self
+            .episodes
+            .iter()
+            .filter(|e| episode_matches(e.value(), query))
+            .map(|e| e.value().clone())
+            .collect();
+        episodes.sort_by(|a, b| b.ended_at.cmp(&a.ended_at).then(b.id.cmp(&a.id)));
+        if let Some(limit) = query.limit {
+            episodes.truncate(limit);
+        }
+        episodes
+    }
+
+    /// Search episode summaries, decisions, tools and outcomes.
+    ///
+    /// Scores are the fraction of query words an episode contains; ties go
+    /// to the most recently ended episode.
+    pub fn search_episodes(&self, req: &EpisodeSearchRequest) -> Vec<EpisodeSearchResult> {
+        let query_words: std::collections::HashSet<String> = tokenize(&req.query).into_iter().collect();
+
+        let mut results: Vec<EpisodeSearchResult> = self
+            .episodes
+            .iter()
+            .filter(|e| episode_matches(e.value(), &req.filter))
+            .filter_map(|e| {
+                let episode = e.value();
+                if query_words.is_empty() {
+                    return Some(EpisodeSearchResult { episode: episode.clone(), score: 1.0 });
+                }
+                let text = format!(
+                    "{} {} {} {}",
+                    episode.summary,
+                    episode.key_decisions.join(" "),
+                    episode.tools_used.join(" "),
+                    episode.outcome
+                );
+                let words: std::collections::HashSet<String> = tokenize(&text).into_iter().collect();
+                let matched = query_words.iter().filter(|w| words.contains(*w)).count();
+                (matched > 0).then(|| EpisodeSearchResult {
+                    episode: episode.clone(),
+                    score: matched as f32 / query_words.len() as f32,
+                })
+            })
+            .collect();
+
+        results.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(b.episode.ended_at.cmp(&a.episode.ended_at))
+        });
+        results.truncate(req.filter.limit.unwrap_or(10));
+        results
+    }
+
     // ========================================================================
     // Knowledge Graph
     // ========================================================================
 
-    pub fn add_entity(&self, req: AddEntityRequest) -> Entity {
-        let id = self.next_entity_id.fetch_add(1, Ordering::Relaxed);
+    pub fn add_entity(&self, req: AddEntityRequest) -> Result<Entity, WriteError> {
+        let id = self.entity_ids.next();
         let now = Utc::now();
 
         let entity = Entity {
@@ -809,20 +1636,85 @@ impl MemoryEngine {
             metadata: req.metadata,
         };
 
-        self.entities.insert(id, entity.clone());
-
-        self.emit_replication(ReplicationEvent::EntityAdded {
+        self.commit_events(vec![ReplicationEvent::EntityAdded {
             entity: entity.clone(),
-        });
+        }])?;
+        self.link_name_mentions(&entity);
 
         info!(id, name = %entity.name, "Entity added");
-        entity
+        Ok(entity)
+    }
+
+    pub fn update_entity(&self, id: u64, req: UpdateEntityRequest) -> Result<Option<Entity>, WriteError> {
+        let Some(mut entry) = self.entities.get_mut(&id) else {
+            return Ok(None);
+        };
+        let old_name = entry.name.clone();
+        let mut entity = entry.clone();
+        if let Some(name) = req.name {
+            entity.name = name;
+        }
+        if let Some(entity_type) = req.entity_type {
+            entity.entity_type = entity_type;
+        }
+        if let Some(description) = req.description {
+            entity.description = Some(description);
+        }
+        if let Some(metadata) = req.metadata {
+            entity.metadata = metadata;
+        }
+        entity.updated_at = Utc::now();
+
+        let event = ReplicationEvent::EntityUpdated {
+            entity: entity.clone(),
+        };
+        self.commit(&[event], || {
+            *entry = entity.clone();
+            drop(entry);
+            if entity.name != old_name {
+                self.unindex_entity_name(id, &old_name);
+                self.index_entity_name(id, &entity.name);
+            }
+        })?;
+        if entity.name != old_name {
+            self.link_name_mentions(&entity);
+        }
+
+        info!(id, name = %entity.name, "Entity updated");
+        Ok(Some(entity))
     }
 
     pub fn get_entity(&self, id: u64) -> Option<Entity> {
         self.entities.get(&id).map(|e| e.clone())
     }
 
+    /// Insert or replace an entity, keeping the name index in step.
+    fn put_entity(&self, entity: Entity) {
+        let (id, name) = (entity.id, entity.name.clone());
+        if let Some(old) = self.entities.insert(id, entity) {
+            self.unindex_entity_name(id, &old.name);
+        }
+        self.index_entity_name(id, &name);
+    }
+
+    fn index_entity_name(&self, id: u64, name: &str) {
+        if let Some(first) = tokenize(name).into_iter().next() {
+            let mut ids = self.entity_names.entry(first).or_default();
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+
+    fn unindex_entity_name(&self, id: u64, name: &str) {
+        if let Some(first) = tokenize(name).into_iter().next() {
+            if let Some(mut ids) = self.entity_names.get_mut(&first) {
+                ids.retain(|e| *e != id);
+            }
+            self.entity_names.remove_if(&first, |_, ids| ids.is_empty());
+        }
+    }
+
     pub fn find_entity_by_name(&self, name: &str) -> Option<Entity> {
         let name_lower = name.to_lowercase();
         self.entities
@@ -831,8 +1723,156 @@ impl MemoryEngine {
             .map(|e| e.value().clone())
     }
 
-    pub fn add_relationship(&self, req: AddRelationshipRequest) -> Relationship {
-        let id = self.next_relationship_id.fetch_add(1, Ordering::Relaxed);
+    // ========================================================================
+    // Entity Mentions
+    // ========================================================================
+
+    /// Record that a memory mentions an entity.
+    ///
+    /// Returns `None` if either side does not exist or the link is already
+    /// present (whatever its source).
+    pub fn link_memory_entity(
+        &self,
+        memory_id: u64,
+        entity_id: u64,
+        source: MentionSource,
+    ) -> Result<Option<EntityMention>, WriteError> {
+        if !self.memories.contains_key(&memory_id) || !self.entities.contains_key(&entity_id) {
+            return Ok(None);
+        }
+        let linked = self
+            .mentions
+            .get(&memory_id)
+            .is_some_and(|links| links.iter().any(|m| m.entity_id == entity_id));
+        if linked {
+            return Ok(None);
+        }
+        let mention = EntityMention {
+            memory_id,
+            entity_id,
+            source,
+            created_at: Utc::now(),
+        };
+
+        let event = ReplicationEvent::MentionAdded {
+            mention: mention.clone(),
+        };
+        let inserted = self.commit(&[event], || self.insert_mention(mention.clone()))?;
+        Ok(inserted.then_some(mention))
+    }
+
+    /// Insert a mention into both indexes. Returns false if already linked.
+    fn insert_mention(&self, mention: EntityMention) -> bool {
+        let (memory_id, entity_id) = (mention.memory_id, mention.entity_id);
+        {
+            let mut links = self.mentions.entry(memory_id).or_default();
+            if links.iter().any(|m| m.entity_id == entity_id) {
+                return false;
+            }
+            links.push(mention);
+        }
+        self.entity_mentions.entry(entity_id).or_default().push(memory_id);
+        true
+    }
+
+    /// Link a new or edited memory to every known entity whose name appears
+    /// in its content as whole words (case-insensitive), if name matching is
+    /// enabled. Only entities whose name starts with one of its words are
+    /// checked.
+    fn link_entity_names(&self, memory: &Memory) {
+        if !self.config.entity_name_matching {
+            return;
+        }
+        let words = tokenize(&memory.content);
+        let mut candidates: Vec<u64> = words
+            .iter()
+            .collect::<std::collections::HashSet<_>>()
+            .into_iter()
+            .filter_map(|w| self.entity_names.get(w).map(|ids| ids.clone()))
+            .flatten()
+            .collect();
+        candidates.sort_unstable();
+        let matched: Vec<u64> = candidates
+            .into_iter()
+            .filter(|id| self.entities.get(id).is_some_and(|e| mentions_name(&words, &e.name)))
+            .collect();
+        for entity_id in matched {
+            // A failure is logged; the remaining links would fail the same way
+            if self.link_memory_entity(memory.id, entity_id, MentionSource::NameMatch).is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Link a new or renamed entity to the valid memories that already mention
+    /// its name, if name matching is enabled. Candidates come from the
+    /// inverted index entry for the name's first word.
+    fn link_name_mentions(&self, entity: &Entity) {
+        if !self.config.entity_name_matching {
+            return;
+        }
+        let Some(first) = tokenize(&entity.name).into_iter().next() else {
+            return;
+        };
+        let mut candidates: Vec<u64> = self
+            .inverted_index
+            .get(&first)
+            .map(|postings| postings.keys().copied().collect())
+            .unwrap_or_default();
+        candidates.sort_unstable();
+        let matched: Vec<u64> = candidates
+            .into_iter()
+            .filter(|id| {
+                self.memories.get(id).is_some_and(|m| {
+                    m.valid_until.is_none() && mentions_name(&tokenize(&m.content), &entity.name)
+                })
+            })
+            .collect();
+        for memory_id in matched {
+            // A failure is logged; the remaining links would fail the same way
+            if self.link_memory_entity(memory_id, entity.id, MentionSource::NameMatch).is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Mention links recorded for a memory.
+    pub fn get_memory_mentions(&self, memory_id: u64) -> Vec<EntityMention> {
+        self.mentions
+            .get(&memory_id)
+            .map(|m| m.clone())
+            .unwrap_or_default()
+    }
+
+    /// Entities a memory mentions, ordered by ID.
+    pub fn get_memory_entities(&self, memory_id: u64) -> Vec<Entity> {
+        let mut entities: Vec<Entity> = self
+            .get_memory_mentions(memory_id)
+            .iter()
+            .filter_map(|m| self.get_entity(m.entity_id))
+            .collect();
+        entities.sort_by_key(|e| e.id);
+        entities
+    }
+
+    /// Valid memories that mention an entity, newest first.
+    pub fn get_entity_memories(&self, entity_id: u64) -> Vec<Memory> {
+        let ids = self
+            .entity_mentions
+            .get(&entity_id)
+            .map(|ids| ids.clone())
+            .unwrap_or_default();
+        let mut memories: Vec<Memory> = ids
+            .into_iter()
+            .filter_map(|id| self.get_memory(id))
+            .filter(|m| m.valid_until.is_none())
+            .collect();
+        memories.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(b.id.cmp(&a.id)));
+        memories
+    }
+
+    pub fn add_relationship(&self, req: AddRelationshipRequest) -> Result<Relationship, WriteError> {
+        let id = self.relationship_ids.next();
         let now = Utc::now();
 
         let rel = Relationship {
@@ -848,14 +1888,33 @@ impl MemoryEngine {
             metadata: req.metadata,
         };
 
-        self.relationships.insert(id, rel.clone());
-
-        self.emit_replication(ReplicationEvent::RelationshipAdded {
+        self.commit_events(vec![ReplicationEvent::RelationshipAdded {
             relationship: rel.clone(),
-        });
+        }])?;
 
         info!(id, src = req.source_entity_id, dst = req.target_entity_id, "Relationship added");
-        rel
+        Ok(rel)
+    }
+
+    /// Mark a relationship as no longer valid. Like memories, it is kept for
+    /// history but drops out of graph queries.
+    pub fn invalidate_relationship(&self, id: u64) -> Result<Option<Relationship>, WriteError> {
+        let Some(mut entry) = self.relationships.get_mut(&id) else {
+            return Ok(None);
+        };
+        if entry.valid_until.is_some() {
+            return Ok(Some(entry.clone()));
+        }
+        let mut rel = entry.clone();
+        rel.valid_until = Some(Utc::now());
+
+        let event = ReplicationEvent::RelationshipInvalidated {
+            relationship: rel.clone(),
+        };
+        self.commit(&[event], || *entry = rel.clone())?;
+
+        info!(id, "Relationship invalidated");
+        Ok(Some(rel))
     }
 
     pub fn get_entity_relationships(&self, entity_id: u64) -> Vec<(Relationship, Entity)> {
@@ -925,7 +1984,7 @@ impl MemoryEngine {
     // Agents
     // ========================================================================
 
-    pub fn register_agent(&self, req: RegisterAgentRequest) -> Agent {
+    pub fn register_agent(&self, req: RegisterAgentRequest) -> Result<Agent, WriteError> {
         let now = Utc::now();
         let agent = Agent {
             agent_id: req.agent_id.clone(),
@@ -938,14 +1997,12 @@ impl MemoryEngine {
             metadata: req.metadata,
         };
 
-        self.agents.insert(req.agent_id.clone(), agent.clone());
-
-        self.emit_replication(ReplicationEvent::AgentRegistered {
+        self.commit_events(vec![ReplicationEvent::AgentRegistered {
             agent: agent.clone(),
-        });
+        }])?;
 
         info!(agent_id = %req.agent_id, "Agent registered");
-        agent
+        Ok(agent)
     }
 
     pub fn get_agent(&self, agent_id: &str) -> Option<Agent> {
@@ -956,197 +2013,1245 @@ impl MemoryEngine {
         self.agents.iter().map(|a| a.value().clone()).collect()
     }
 
-    pub fn heartbeat_agent(&self, agent_id: &str) {
-        if let Some(mut agent) = self.agents.get_mut(agent_id) {
-            agent.last_seen = Utc::now();
+    /// Mark an agent as alive. `last_seen` is only refreshed when it is more
+    /// than [`AGENT_SEEN_RESOLUTION_SECS`] old or the agent wasn't online, so
+    /// frequent heartbeats don't each hit the WAL and the cluster. Also renews
+    /// the leases of its claimed and in-progress tasks once less than half of
+    /// the lease remains.
+    pub fn heartbeat_agent(&self, agent_id: &str) -> Result<(), WriteError> {
+        let now = Utc::now();
+        if let Some(mut entry) = self.agents.get_mut(agent_id)
+            && (entry.status != AgentStatus::Online
+                || now - entry.last_seen >= chrono::Duration::seconds(AGENT_SEEN_RESOLUTION_SECS))
+        {
+            let mut agent = entry.clone();
+            agent.last_seen = now;
             agent.status = AgentStatus::Online;
+            let event = ReplicationEvent::AgentUpdated { agent: agent.clone() };
+            self.commit(&[event], || *entry = agent)?;
+        }
+
+        let Some(deadline) = self.lease_deadline() else {
+            return Ok(());
+        };
+        let half_lease = chrono::Duration::seconds(self.config.task_lease_secs as i64 / 2);
+        let due: Vec<u64> = self
+            .tasks
+            .iter()
+            .filter(|t| {
+                matches!(t.status, TaskStatus::Claimed | TaskStatus::InProgress)
+                    && t.assigned_agent.as_deref() == Some(agent_id)
+                    && t.lease_expires_at.is_none_or(|at| at - Utc::now() < half_lease)
+            })
+            .map(|t| t.id)
+            .collect();
+        for task_id in due {
+            let Some(mut entry) = self.tasks.get_mut(&task_id) else {
+                continue;
+            };
+            let mut task = entry.clone();
+            task.lease_expires_at = Some(deadline);
+            let event = ReplicationEvent::TaskUpdated { task: task.clone() };
+            self.commit(&[event], || *entry = task)?;
         }
+        Ok(())
     }
 
     // ========================================================================
-    // Stats
+    // Task Templates
     // ========================================================================
 
-    // ========================================================================
-    // Task CRUD
-    // ========================================================================
+    /// First run of a schedule: at (or the first cron match from) `not_before`
+    /// if that is still ahead, otherwise the next run from now.
+    fn first_template_run(
+        schedule: &TaskSchedule,
+        not_before: Option<chrono::DateTime<Utc>>,
+        now: chrono::DateTime<Utc>,
+    ) -> Result<chrono::DateTime<Utc>, String> {
+        match not_before {
+            Some(start) if start > now => schedule.next_run(start, true),
+            _ => schedule.next_run(now, false),
+        }
+    }
 
-    pub fn create_task(&self, req: CreateTaskRequest) -> Task {
-        let id = self.next_task_id.fetch_add(1, Ordering::Relaxed);
+    pub fn create_task_template(&self, req: CreateTaskTemplateRequest) -> Result<TaskTemplate, WriteError> {
+        req.schedule.validate()?;
+        validate_deadline_secs(req.deadline_secs)?;
         let now = Utc::now();
+        let next_run_at = if req.enabled {
+            Some(Self::first_template_run(&req.schedule, req.not_before, now)?)
+        } else {
+            None
+        };
+        let id = self.template_ids.next();
 
-        let task = Task {
+        let template = TaskTemplate {
             id,
             title: req.title,
             description: req.description,
-            status: TaskStatus::Pending,
             priority: req.priority,
             required_capabilities: req.required_capabilities,
-            assigned_agent: None,
-            created_by: req.created_by.clone(),
-            dependencies: req.dependencies,
-            result: None,
+            created_by: req.created_by,
+            metadata: req.metadata,
+            schedule: req.schedule,
+            not_before: req.not_before,
+            deadline_secs: req.deadline_secs,
+            enabled: req.enabled,
+            next_run_at,
+            last_run_at: None,
+            last_task_id: None,
+            run_count: 0,
             created_at: now,
             updated_at: now,
-            deadline: req.deadline,
-            metadata: req.metadata,
-        };
-
-        self.tasks.insert(id, task.clone());
-
-        // Record event
-        let event_id = self.next_task_event_id.fetch_add(1, Ordering::Relaxed);
-        let event = TaskEvent {
-            id: event_id,
-            task_id: id,
-            event_type: TaskEventType::Created,
-            agent_id: Some(req.created_by),
-            details: None,
-            timestamp: now,
         };
-        self.task_events.entry(id).or_default().push(event);
+        self.commit_events(vec![ReplicationEvent::TaskTemplateCreated { template: template.clone() }])?;
+        info!(template_id = id, next_run_at = ?template.next_run_at, "Task template created");
+        Ok(template)
+    }
 
-        self.emit_replication(ReplicationEvent::TaskCreated { task: task.clone() });
-        info!(task_id = id, "Task created");
-        task
+    pub fn get_task_template(&self, id: u64) -> Option<TaskTemplate> {
+        self.task_templates.get(&id).map(|t| t.value().clone())
     }
 
-    pub fn claim_task(&self, task_id: u64, agent_id: &str) -> Result<Task, String> {
-        let mut entry = self.tasks.get_mut(&task_id)
-            .ok_or_else(|| format!("Task {} not found", task_id))?;
+    pub fn list_task_templates(&self) -> Vec<TaskTemplate> {
+        let mut templates: Vec<TaskTemplate> =
+            self.task_templates.iter().map(|t| t.value().clone()).collect();
+        templates.sort_by_key(|t| t.id);
+        templates
+    }
 
-        let task = entry.value_mut();
-        if task.status != TaskStatus::Pending {
-            return Err(format!("Task {} is not pending (status: {:?})", task_id, task.status));
+    /// Change a template. Changing its schedule, `not_before` or `enabled`
+    /// recomputes the next run.
+    pub fn update_task_template(&self, id: u64, req: UpdateTaskTemplateRequest) -> Result<TaskTemplate, WriteError> {
+        if let Some(schedule) = &req.schedule {
+            schedule.validate()?;
         }
+        validate_deadline_secs(req.deadline_secs)?;
+        let reschedule = req.schedule.is_some() || req.not_before.is_some() || req.enabled.is_some();
+
+        let mut entry = self.task_templates.get_mut(&id)
+            .ok_or_else(|| format!("Task template {} not found", id))?;
+        let mut template = entry.value().clone();
+        if let Some(title) = req.title {
+            template.title = title;
+        }
+        if let Some(description) = req.description {
+            template.description = description;
+        }
+        if let Some(priority) = req.priority {
+            template.priority = priority;
+        }
+        if let Some(capabilities) = req.required_capabilities {
+            template.required_capabilities = capabilities;
+        }
+        if let Some(schedule) = req.schedule {
+            template.schedule = schedule;
+        }
+        if let Some(not_before) = req.not_before {
+            template.not_before = Some(not_before);
+        }
+        if let Some(deadline_secs) = req.deadline_secs {
+            template.deadline_secs = Some(deadline_secs);
+        }
+        if let Some(enabled) = req.enabled {
+            template.enabled = enabled;
+        }
+        let now = Utc::now();
+        if reschedule {
+            template.next_run_at = if template.enabled {
+                Some(Self::first_template_run(&template.schedule, template.not_before, now)?)
+            } else {
+                None
+            };
+        }
+        template.updated_at = now;
+        let event = ReplicationEvent::TaskTemplateUpdated { template: template.clone() };
+        self.commit(&[event], || *entry = template.clone())?;
+        info!(template_id = id, next_run_at = ?template.next_run_at, "Task template updated");
+        Ok(template)
+    }
 
-        task.status = TaskStatus::Claimed;
-        task.assigned_agent = Some(agent_id.to_string());
-        task.updated_at = Utc::now();
-
-        let task_clone = task.clone();
-        drop(entry);
-
-        let event_id = self.next_task_event_id.fetch_add(1, Ordering::Relaxed);
-        let event = TaskEvent {
-            id: event_id,
-            task_id,
-            event_type: TaskEventType::Claimed,
-            agent_id: Some(agent_id.to_string()),
-            details: None,
-            timestamp: Utc::now(),
+    /// Delete a template. Tasks it already created are left alone.
+    pub fn delete_task_template(&self, id: u64) -> Result<Option<TaskTemplate>, WriteError> {
+        let Some(template) = self.get_task_template(id) else {
+            return Ok(None);
         };
-        self.task_events.entry(task_id).or_default().push(event);
+        self.commit_events(vec![ReplicationEvent::TaskTemplateDeleted { template_id: id }])?;
+        info!(template_id = id, "Task template deleted");
+        Ok(Some(template))
+    }
 
-        self.emit_replication(ReplicationEvent::TaskClaimed { task: task_clone.clone() });
-        info!(task_id, agent_id, "Task claimed");
-        Ok(task_clone)
+    /// Tell the other nodes this one is alive (see [`Self::is_maintainer`]).
+    pub fn send_node_heartbeat(&self) -> anyhow::Result<()> {
+        self.replication.announce(&ReplicationEvent::NodeHeartbeat {
+            node_number: self.config.node_number,
+        })
     }
 
-    pub fn start_task(&self, task_id: u64, agent_id: &str) -> Result<Task, String> {
-        let mut entry = self.tasks.get_mut(&task_id)
-            .ok_or_else(|| format!("Task {} not found", task_id))?;
+    /// Note that the node `node_number` announced itself just now.
+    pub fn record_node_heartbeat(&self, node_number: u16) {
+        if node_number != self.config.node_number {
+            self.live_nodes.insert(node_number, Instant::now());
+        }
+    }
 
-        let task = entry.value_mut();
-        if task.status != TaskStatus::Claimed {
-            return Err(format!("Task {} is not claimed (status: {:?})", task_id, task.status));
+    /// Whether this node runs the background sweeps (lease reaping, overdue
+    /// checks, template runs). In a cluster only the lowest-numbered node
+    /// that sent a heartbeat within [`NODE_LIVENESS_SECS`] does, so a
+    /// template fires once per run rather than once per node, and the sweeps
+    /// move to another node when that one goes away. A node that just
+    /// started waits that long before taking over, so it hears the others
+    /// first.
+    fn is_maintainer(&self) -> bool {
+        if !self.config.replication_enabled {
+            return true;
         }
-        if task.assigned_agent.as_deref() != Some(agent_id) {
-            return Err(format!("Task {} is assigned to {:?}, not {}", task_id, task.assigned_agent, agent_id));
+        let liveness = Duration::from_secs(NODE_LIVENESS_SECS);
+        if self.started.elapsed() < liveness {
+            return false;
         }
+        let own = self.config.node_number;
+        !self
+            .live_nodes
+            .iter()
+            .any(|node| *node.key() < own && node.value().elapsed() < liveness)
+    }
 
-        task.status = TaskStatus::InProgress;
-        task.updated_at = Utc::now();
+    /// Create a task from every enabled template whose next run is due and
+    /// schedule the run after it. Returns the created tasks.
+    pub fn run_due_templates(&self) -> Vec<Task> {
+        if !self.is_maintainer() {
+            return Vec::new();
+        }
+        let now = Utc::now();
+        let due: Vec<u64> = self
+            .task_templates
+            .iter()
+            .filter(|t| t.enabled && t.next_run_at.is_some_and(|at| at <= now))
+            .map(|t| t.id)
+            .collect();
 
-        let task_clone = task.clone();
-        drop(entry);
+        let mut created = Vec::new();
+        for template_id in due {
+            let Some(mut template) = self.get_task_template(template_id) else {
+                continue;
+            };
+            let Some(scheduled) = template.next_run_at.filter(|at| template.enabled && *at <= now) else {
+                continue;
+            };
+            // Advance even if the task can't be created, so it doesn't retry every tick
+            template.next_run_at = match template.schedule.advance(scheduled, now) {
+                Ok(next) => Some(next),
+                Err(e) => {
+                    warn!(template_id, error = %e, "Task template has no further runs");
+                    None
+                }
+            };
+            template.updated_at = now;
+            let req = CreateTaskRequest {
+                title: template.title.clone(),
+                description: template.description.clone(),
+                priority: template.priority,
+                required_capabilities: template.required_capabilities.clone(),
+                created_by: template.created_by.clone(),
+                dependencies: Vec::new(),
+                deadline: template
+                    .deadline_secs
+                    .and_then(|secs| i64::try_from(secs).ok())
+                    .and_then(chrono::TimeDelta::try_seconds)
+                    .and_then(|delta| now.checked_add_signed(delta))
+                    .map(|deadline| deadline.to_rfc3339()),
+                metadata: template.metadata.clone(),
+                parent_id: None,
+                rollup: None,
+                template_id: Some(template_id),
+            };
 
-        let event_id = self.next_task_event_id.fetch_add(1, Ordering::Relaxed);
-        let event = TaskEvent {
-            id: event_id,
+            // The task and the template's advance are logged together, so a
+            // run is never recorded without its task or repeated after it
+            let mut events = Vec::new();
+            let task = match self.new_task(req) {
+                Ok((task, task_events)) => {
+                    template.last_run_at = Some(now);
+                    template.last_task_id = Some(task.id);
+                    template.run_count += 1;
+                    events = task_events;
+                    Some(task)
+                }
+                Err(e) => {
+                    warn!(template_id, error = %e, "Failed to create task from template");
+                    None
+                }
+            };
+            events.push(ReplicationEvent::TaskTemplateUpdated { template });
+            if self.commit_events(events).is_err() {
+                continue;
+            }
+            if let Some(task) = task {
+                info!(template_id, task_id = task.id, "Scheduled task created");
+                created.push(task);
+            }
+        }
+        created
+    }
+
+    // ========================================================================
+    // Stats
+    // ========================================================================
+
+    // ========================================================================
+    // Task CRUD
+    // ========================================================================
+
+    /// Create a task. Dependencies must exist and keep the task graph acyclic;
+    /// a task with unfinished dependencies starts out `Blocked`.
+    pub fn create_task(&self, req: CreateTaskRequest) -> Result<Task, WriteError> {
+        let (task, events) = self.new_task(req)?;
+        self.commit_events(events)?;
+        info!(task_id = task.id, parent_id = ?task.parent_id, "Task created");
+        Ok(task)
+    }
+
+    /// Validate a task request and build the task with the events that create it.
+    fn new_task(&self, req: CreateTaskRequest) -> Result<(Task, Vec<ReplicationEvent>), String> {
+        let now = Utc::now();
+        let deadline = req.deadline.as_deref().map(parse_deadline).transpose()?;
+        if let Some(deadline) = deadline
+            && deadline <= now
+        {
+            return Err(format!("Deadline {} is in the past", deadline.to_rfc3339()));
+        }
+        if let Some(parent_id) = req.parent_id {
+            let parent = self.tasks.get(&parent_id)
+                .ok_or_else(|| format!("Parent task {} not found", parent_id))?;
+            if is_finished(&parent.status) {
+                return Err(format!("Parent task {} is already finished (status: {:?})", parent_id, parent.status));
+            }
+        }
+        let mut dependencies = req.dependencies;
+        let mut seen = std::collections::HashSet::new();
+        dependencies.retain(|d| seen.insert(*d));
+        self.check_dependencies(&dependencies)?;
+
+        // Only a cycle is found after the ID is taken: it needs the ID
+        let id = self.task_ids.next();
+        self.check_dependency_cycle(id, &dependencies)?;
+        let status = if self.blocking_dependencies(&dependencies).is_empty() {
+            TaskStatus::Pending
+        } else {
+            TaskStatus::Blocked
+        };
+
+        let task = Task {
+            id,
+            title: req.title,
+            description: req.description,
+            status,
+            priority: req.priority,
+            required_capabilities: req.required_capabilities,
+            assigned_agent: None,
+            created_by: req.created_by.clone(),
+            dependencies,
+            result: None,
+            artifacts: Vec::new(),
+            result_memory_ids: Vec::new(),
+            progress: None,
+            created_at: now,
+            updated_at: now,
+            deadline,
+            overdue_at: None,
+            metadata: req.metadata,
+            lease_expires_at: None,
+            attempts: 0,
+            template_id: req.template_id,
+            parent_id: req.parent_id,
+            rollup: req.rollup,
+        };
+
+        // Record event
+        let event_id = self.task_event_ids.next();
+        let event = TaskEvent {
+            id: event_id,
+            task_id: id,
+            event_type: TaskEventType::Created,
+            agent_id: Some(req.created_by),
+            details: None,
+            data: None,
+            timestamp: now,
+        };
+        let events = vec![
+            ReplicationEvent::TaskEventRecorded { event },
+            ReplicationEvent::TaskCreated { task: task.clone() },
+        ];
+        Ok((task, events))
+    }
+
+    /// Check that every dependency exists and can still complete.
+    fn check_dependencies(&self, dependencies: &[u64]) -> Result<(), String> {
+        for dep_id in dependencies {
+            let dep = self.tasks.get(dep_id)
+                .ok_or_else(|| format!("Dependency {} not found", dep_id))?;
+            if matches!(dep.status, TaskStatus::Failed | TaskStatus::Cancelled) {
+                return Err(format!("Dependency {} will never complete (status: {:?})", dep_id, dep.status));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that no dependency (transitively) depends on `task_id`.
+    fn check_dependency_cycle(&self, task_id: u64, dependencies: &[u64]) -> Result<(), String> {
+        if dependencies.contains(&task_id) {
+            return Err(format!("Task {} cannot depend on itself", task_id));
+        }
+        let mut stack = dependencies.to_vec();
+        let mut visited = std::collections::HashSet::new();
+        while let Some(id) = stack.pop() {
+            if id == task_id {
+                return Err(format!("Dependencies of task {} would form a cycle", task_id));
+            }
+            if visited.insert(id)
+                && let Some(task) = self.tasks.get(&id)
+            {
+                stack.extend(task.dependencies.iter().copied());
+            }
+        }
+        Ok(())
+    }
+
+    /// Dependencies that have not completed (missing ones count as unfinished).
+    fn blocking_dependencies(&self, dependencies: &[u64]) -> Vec<u64> {
+        dependencies
+            .iter()
+            .copied()
+            .filter(|d| {
+                self.tasks
+                    .get(d)
+                    .is_none_or(|t| t.status != TaskStatus::Completed)
+            })
+            .collect()
+    }
+
+    /// Blocked task IDs by the dependencies they wait on.
+    fn blocked_by_dependency(&self) -> HashMap<u64, Vec<u64>> {
+        let mut blocked: HashMap<u64, Vec<u64>> = HashMap::new();
+        for task in self.tasks.iter().filter(|t| t.status == TaskStatus::Blocked) {
+            for &dependency in &task.dependencies {
+                blocked.entry(dependency).or_default().push(task.id);
+            }
+        }
+        blocked
+    }
+
+    /// Move the blocked tasks in `waiting` (those that waited on
+    /// `completed_id`) to `Pending` once all of their dependencies are done.
+    /// Returns the tasks that were unblocked.
+    fn unblock_dependents(&self, completed_id: u64, waiting: &[u64]) -> Vec<Task> {
+        let mut unblocked = Vec::new();
+        for &task_id in waiting {
+            let Some(dependencies) = self
+                .tasks
+                .get(&task_id)
+                .filter(|t| t.status == TaskStatus::Blocked)
+                .map(|t| t.dependencies.clone())
+            else {
+                continue;
+            };
+            if !self.blocking_dependencies(&dependencies).is_empty() {
+                continue;
+            }
+            let Some(entry) = self.tasks.get_mut(&task_id) else {
+                continue;
+            };
+            if entry.status != TaskStatus::Blocked {
+                continue;
+            }
+            let mut task = entry.clone();
+            task.status = TaskStatus::Pending;
+            task.updated_at = Utc::now();
+
+            let event_id = self.task_event_ids.next();
+            let event = TaskEvent {
+                id: event_id,
+                task_id,
+                event_type: TaskEventType::Unblocked,
+                agent_id: None,
+                details: Some(format!("Dependency {} completed", completed_id)),
+                data: None,
+                timestamp: Utc::now(),
+            };
+            let recorded = vec![ReplicationEvent::TaskEventRecorded { event }];
+            if self.commit_task(entry, &task, |task| ReplicationEvent::TaskUnblocked { task }, recorded).is_err() {
+                continue;
+            }
+            info!(task_id, dependency = completed_id, "Task unblocked");
+            unblocked.push(task);
+        }
+        unblocked
+    }
+
+    /// Claim a pending task. Registered agents must have every capability the
+    /// task requires; unregistered agents are not checked.
+    pub fn claim_task(&self, task_id: u64, agent_id: &str) -> Result<Task, WriteError> {
+        let (dependencies, required) = self.tasks.get(&task_id)
+            .map(|t| (t.dependencies.clone(), t.required_capabilities.clone()))
+            .unwrap_or_default();
+        if let Some(capabilities) = self.get_agent(agent_id).map(|a| a.capabilities) {
+            let missing = missing_capabilities(&required, &capabilities);
+            if !missing.is_empty() {
+                return Err(format!(
+                    "Agent {} lacks capabilities required by task {}: {}",
+                    agent_id, task_id, missing.join(", ")
+                ).into());
+            }
+        }
+        let blockers = self.blocking_dependencies(&dependencies);
+        if !blockers.is_empty() {
+            let ids: Vec<String> = blockers.iter().map(|id| id.to_string()).collect();
+            return Err(format!("Task {} is blocked by unfinished dependencies: {}", task_id, ids.join(", ")).into());
+        }
+
+        let entry = self.tasks.get_mut(&task_id)
+            .ok_or_else(|| format!("Task {} not found", task_id))?;
+
+        let mut task = entry.clone();
+        if task.status != TaskStatus::Pending {
+            return Err(format!("Task {} is not pending (status: {:?})", task_id, task.status).into());
+        }
+
+        task.status = TaskStatus::Claimed;
+        task.assigned_agent = Some(agent_id.to_string());
+        task.attempts += 1;
+        task.lease_expires_at = self.lease_deadline();
+        task.updated_at = Utc::now();
+
+        let event_id = self.task_event_ids.next();
+        let event = TaskEvent {
+            id: event_id,
             task_id,
-            event_type: TaskEventType::Started,
+            event_type: TaskEventType::Claimed,
             agent_id: Some(agent_id.to_string()),
             details: None,
+            data: None,
             timestamp: Utc::now(),
         };
-        self.task_events.entry(task_id).or_default().push(event);
+        let recorded = vec![ReplicationEvent::TaskEventRecorded { event }];
+        self.commit_task(entry, &task, |task| ReplicationEvent::TaskClaimed { task }, recorded)?;
+        info!(task_id, agent_id, "Task claimed");
+        Ok(task)
+    }
+
+    /// Claim the most urgent pending task the agent can do (see [`cmp_urgency`]).
+    /// A registered agent's capabilities take precedence over
+    /// `fallback_capabilities`. Returns `None` when nothing is claimable.
+    pub fn claim_next_task(&self, agent_id: &str, fallback_capabilities: &[String]) -> Result<Option<Task>, WriteError> {
+        let capabilities = self
+            .get_agent(agent_id)
+            .map(|a| a.capabilities)
+            .unwrap_or_else(|| fallback_capabilities.to_vec());
+
+        let mut candidates: Vec<Task> = self
+            .tasks
+            .iter()
+            .filter(|t| {
+                t.status == TaskStatus::Pending
+                    && missing_capabilities(&t.required_capabilities, &capabilities).is_empty()
+            })
+            .map(|t| t.value().clone())
+            .collect();
+        candidates.sort_by(cmp_urgency);
+
+        // Another agent may win the race for a candidate; fall through to the next
+        for candidate in candidates {
+            match self.claim_task(candidate.id, agent_id) {
+                Ok(task) => return Ok(Some(task)),
+                Err(WriteError::Rejected(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(None)
+    }
+
+    /// Like [`claim_next_task`](Self::claim_next_task), but waits up to `wait`
+    /// for a suitable task to become pending.
+    pub async fn claim_next_task_wait(
+        &self,
+        agent_id: &str,
+        fallback_capabilities: &[String],
+        wait: std::time::Duration,
+    ) -> Result<Option<Task>, WriteError> {
+        let deadline = tokio::time::Instant::now() + wait;
+        loop {
+            // Register interest before looking so a task created in between isn't missed
+            let notified = self.task_available.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            if let Some(task) = self.claim_next_task(agent_id, fallback_capabilities)? {
+                return Ok(Some(task));
+            }
+            if tokio::time::timeout_at(deadline, notified).await.is_err() {
+                return Ok(None);
+            }
+        }
+    }
+
+    pub fn start_task(&self, task_id: u64, agent_id: &str) -> Result<Task, WriteError> {
+        let entry = self.tasks.get_mut(&task_id)
+            .ok_or_else(|| format!("Task {} not found", task_id))?;
+
+        let mut task = entry.clone();
+        if task.status != TaskStatus::Claimed {
+            return Err(format!("Task {} is not claimed (status: {:?})", task_id, task.status).into());
+        }
+        if task.assigned_agent.as_deref() != Some(agent_id) {
+            return Err(format!("Task {} is assigned to {:?}, not {}", task_id, task.assigned_agent, agent_id).into());
+        }
+
+        task.status = TaskStatus::InProgress;
+        task.lease_expires_at = self.lease_deadline();
+        task.updated_at = Utc::now();
 
+        let event_id = self.task_event_ids.next();
+        let event = TaskEvent {
+            id: event_id,
+            task_id,
+            event_type: TaskEventType::Started,
+            agent_id: Some(agent_id.to_string()),
+            details: None,
+            data: None,
+            timestamp: Utc::now(),
+        };
+        let recorded = vec![ReplicationEvent::TaskEventRecorded { event }];
+        self.commit_task(entry, &task, |task| ReplicationEvent::TaskStarted { task }, recorded)?;
         info!(task_id, agent_id, "Task started");
-        Ok(task_clone)
+        Ok(task)
     }
 
-    pub fn complete_task(&self, task_id: u64, agent_id: &str, result: String) -> Result<Task, String> {
-        let mut entry = self.tasks.get_mut(&task_id)
+    /// Complete a task, optionally storing its result and artifacts as
+    /// memories tagged `task:<id>`. Returns it along with the tasks this
+    /// changed in turn (see [`finish_follow_ups`](Self::finish_follow_ups)).
+    pub fn complete_task(&self, task_id: u64, req: CompleteTaskRequest) -> Result<(Task, Vec<Task>), WriteError> {
+        let agent_id = req.agent_id.as_str();
+        let result = req.result;
+        let entry = self.tasks.get_mut(&task_id)
             .ok_or_else(|| format!("Task {} not found", task_id))?;
 
-        let task = entry.value_mut();
+        let mut task = entry.clone();
+        if is_finished(&task.status) {
+            return Err(format!("Task {} is already finished (status: {:?})", task_id, task.status).into());
+        }
         if task.assigned_agent.as_deref() != Some(agent_id) {
-            return Err(format!("Task {} is assigned to {:?}, not {}", task_id, task.assigned_agent, agent_id));
+            return Err(format!("Task {} is assigned to {:?}, not {}", task_id, task.assigned_agent, agent_id).into());
         }
 
+        let previous = task.status.clone();
         task.status = TaskStatus::Completed;
         task.result = Some(result.clone());
+        task.artifacts = req.artifacts;
+        task.progress = Some(100);
+        task.lease_expires_at = None;
         task.updated_at = Utc::now();
 
-        let task_clone = task.clone();
-        drop(entry);
+        let (memories, mut events) = if req.store_as_memory {
+            self.task_result_memories(&task, agent_id)
+        } else {
+            (Vec::new(), Vec::new())
+        };
+        task.result_memory_ids = memories.iter().map(|m| m.id).collect();
 
-        let event_id = self.next_task_event_id.fetch_add(1, Ordering::Relaxed);
+        let event_id = self.task_event_ids.next();
         let event = TaskEvent {
             id: event_id,
             task_id,
             event_type: TaskEventType::Completed,
             agent_id: Some(agent_id.to_string()),
             details: Some(result),
+            data: None,
             timestamp: Utc::now(),
         };
-        self.task_events.entry(task_id).or_default().push(event);
-
-        self.emit_replication(ReplicationEvent::TaskCompleted { task: task_clone.clone() });
+        events.push(ReplicationEvent::TaskEventRecorded { event });
+        self.commit_task(entry, &task, |task| ReplicationEvent::TaskCompleted { task }, events)?;
+        self.index_new_memories(&memories);
         info!(task_id, agent_id, "Task completed");
-        Ok(task_clone)
+
+        let follow_ups = self.finish_follow_ups(&previous, &task);
+        Ok((task, follow_ups))
     }
 
-    pub fn fail_task(&self, task_id: u64, agent_id: &str, reason: String) -> Result<Task, String> {
-        let mut entry = self.tasks.get_mut(&task_id)
+    /// Build memories holding a completed task's result and artifacts so
+    /// other agents can recall them, with the events that add them.
+    fn task_result_memories(&self, task: &Task, agent_id: &str) -> (Vec<Memory>, Vec<ReplicationEvent>) {
+        let mut contents = Vec::new();
+        if let Some(result) = task.result.as_deref().filter(|r| !r.trim().is_empty()) {
+            contents.push((
+                format!("Result of task #{} ({}): {}", task.id, task.title, result),
+                serde_json::json!({ "task_id": task.id }),
+            ));
+        }
+        for artifact in &task.artifacts {
+            contents.push((
+                format!("{} from task #{} ({}): {}", artifact.name, task.id, task.title, artifact.content),
+                serde_json::json!({
+                    "task_id": task.id,
+                    "artifact": artifact.name,
+                    "artifact_metadata": artifact.metadata,
+                }),
+            ));
+        }
+
+        let mut memories = Vec::new();
+        let mut events = Vec::new();
+        for (content, metadata) in contents {
+            let req = AddMemoryRequest {
+                content,
+                memory_type: MemoryType::Fact,
+                agent_id: Some(agent_id.to_string()),
+                user_id: None,
+                session_id: None,
+                tags: vec!["task-result".into(), format!("task:{}", task.id)],
+                metadata,
+            };
+            let (memory, added) = self.new_memory(req, "Initial creation");
+            memories.push(memory);
+            events.extend(added);
+        }
+        (memories, events)
+    }
+
+    /// Fail a task. Returns it along with the parents this failed in turn.
+    pub fn fail_task(&self, task_id: u64, agent_id: &str, reason: String) -> Result<(Task, Vec<Task>), WriteError> {
+        let entry = self.tasks.get_mut(&task_id)
             .ok_or_else(|| format!("Task {} not found", task_id))?;
 
-        let task = entry.value_mut();
+        let mut task = entry.clone();
+        if is_finished(&task.status) {
+            return Err(format!("Task {} is already finished (status: {:?})", task_id, task.status).into());
+        }
+        if task.assigned_agent.as_deref() != Some(agent_id) {
+            return Err(format!("Task {} is assigned to {:?}, not {}", task_id, task.assigned_agent, agent_id).into());
+        }
+
+        let previous = task.status.clone();
         task.status = TaskStatus::Failed;
         task.result = Some(reason.clone());
+        task.lease_expires_at = None;
         task.updated_at = Utc::now();
 
-        let task_clone = task.clone();
-        drop(entry);
-
-        let event_id = self.next_task_event_id.fetch_add(1, Ordering::Relaxed);
+        let event_id = self.task_event_ids.next();
         let event = TaskEvent {
             id: event_id,
             task_id,
             event_type: TaskEventType::Failed,
             agent_id: Some(agent_id.to_string()),
             details: Some(reason),
+            data: None,
+            timestamp: Utc::now(),
+        };
+        let recorded = vec![ReplicationEvent::TaskEventRecorded { event }];
+        self.commit_task(entry, &task, |task| ReplicationEvent::TaskFailed { task }, recorded)?;
+        info!(task_id, agent_id, "Task failed");
+        let follow_ups = self.finish_follow_ups(&previous, &task);
+        Ok((task, follow_ups))
+    }
+
+    /// Cancel a task that has not finished yet. Returns it along with the
+    /// parents this let roll up.
+    pub fn cancel_task(&self, task_id: u64, agent_id: &str, reason: Option<String>) -> Result<(Task, Vec<Task>), WriteError> {
+        let entry = self.tasks.get_mut(&task_id)
+            .ok_or_else(|| format!("Task {} not found", task_id))?;
+
+        let mut task = entry.clone();
+        if is_finished(&task.status) {
+            return Err(format!("Task {} is already finished (status: {:?})", task_id, task.status).into());
+        }
+
+        let previous = task.status.clone();
+        task.status = TaskStatus::Cancelled;
+        task.result = reason.clone();
+        // Nobody may complete or fail it any more
+        task.assigned_agent = None;
+        task.lease_expires_at = None;
+        task.updated_at = Utc::now();
+
+        let event_id = self.task_event_ids.next();
+        let event = TaskEvent {
+            id: event_id,
+            task_id,
+            event_type: TaskEventType::Cancelled,
+            agent_id: Some(agent_id.to_string()),
+            details: reason,
+            data: None,
+            timestamp: Utc::now(),
+        };
+        let recorded = vec![ReplicationEvent::TaskEventRecorded { event }];
+        self.commit_task(entry, &task, |task| ReplicationEvent::TaskCancelled { task }, recorded)?;
+        info!(task_id, agent_id, "Task cancelled");
+        let follow_ups = self.finish_follow_ups(&previous, &task);
+        Ok((task, follow_ups))
+    }
+
+    /// React to a task finishing: unblock its dependents if it completed and
+    /// roll its parent up. Returns every task changed as a result, in order.
+    ///
+    /// Does nothing unless the task was unfinished (`previous`) before, so a
+    /// task that finishes twice can't roll its parent up twice. Tasks this
+    /// finishes in turn (cancelled dependents, rolled-up parents) are handled
+    /// from a worklist rather than by recursing, so a long chain of them
+    /// can't overflow the stack.
+    fn finish_follow_ups(&self, previous: &TaskStatus, finished: &Task) -> Vec<Task> {
+        if is_finished(previous) || !is_finished(&finished.status) {
+            return Vec::new();
+        }
+        // Blocked tasks only ever leave that state here, so one scan serves
+        // the whole cascade
+        let blocked = self.blocked_by_dependency();
+        let mut follow_ups = Vec::new();
+        let mut worklist = vec![finished.clone()];
+        while let Some(task) = worklist.pop() {
+            let waiting = blocked.get(&task.id).map(Vec::as_slice).unwrap_or_default();
+            if task.status == TaskStatus::Completed {
+                follow_ups.extend(self.unblock_dependents(task.id, waiting));
+            } else {
+                let cancelled = self.cancel_dependents(&task, waiting);
+                follow_ups.extend(cancelled.iter().cloned());
+                worklist.extend(cancelled);
+            }
+            if let Some(parent) = self.roll_up_parent(&task) {
+                follow_ups.push(parent.clone());
+                worklist.push(parent);
+            }
+        }
+        follow_ups
+    }
+
+    /// Cancel the blocked tasks in `waiting` (those that waited on `dead`,
+    /// which failed or was cancelled and so will never complete). Returns
+    /// the tasks that were cancelled.
+    fn cancel_dependents(&self, dead: &Task, waiting: &[u64]) -> Vec<Task> {
+        let mut changed = Vec::new();
+        for &task_id in waiting {
+            let Some(entry) = self.tasks.get_mut(&task_id) else {
+                continue;
+            };
+            if entry.status != TaskStatus::Blocked {
+                continue;
+            }
+            let details = format!("Dependency {} will never complete (status: {:?})", dead.id, dead.status);
+            let mut task = entry.clone();
+            task.status = TaskStatus::Cancelled;
+            task.result = Some(details.clone());
+            task.updated_at = Utc::now();
+
+            let event_id = self.task_event_ids.next();
+            let event = TaskEvent {
+                id: event_id,
+                task_id,
+                event_type: TaskEventType::Cancelled,
+                agent_id: None,
+                details: Some(details),
+                data: Some(serde_json::json!({ "dependency_id": dead.id })),
+                timestamp: Utc::now(),
+            };
+            let recorded = vec![ReplicationEvent::TaskEventRecorded { event }];
+            if self.commit_task(entry, &task, |task| ReplicationEvent::TaskCancelled { task }, recorded).is_err() {
+                continue;
+            }
+            info!(task_id, dependency = dead.id, "Task cancelled, dependency will never complete");
+            changed.push(task);
+        }
+        changed
+    }
+
+    /// Complete or fail `child`'s parent according to its [`RollupPolicy`]
+    /// once its subtasks allow it. Returns the parent if it finished.
+    fn roll_up_parent(&self, child: &Task) -> Option<Task> {
+        let parent_id = child.parent_id?;
+        let policy = self
+            .tasks
+            .get(&parent_id)
+            .filter(|p| !is_finished(&p.status))
+            .map(|p| p.rollup.unwrap_or(self.config.task_rollup_policy))?;
+
+        let subtasks = self.list_subtasks(parent_id);
+        let failed: Vec<u64> = subtasks.iter().filter(|t| t.status == TaskStatus::Failed).map(|t| t.id).collect();
+        let completed = subtasks.iter().filter(|t| t.status == TaskStatus::Completed).count();
+        let open = subtasks.iter().filter(|t| !is_finished(&t.status)).count();
+        let ids = |ids: &[u64]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(", ");
+
+        let (status, details) = match policy {
+            RollupPolicy::Manual => return None,
+            RollupPolicy::FailFast if !failed.is_empty() => {
+                (TaskStatus::Failed, format!("Subtask {} failed", ids(&failed)))
+            }
+            RollupPolicy::FailFast if open == 0 && completed > 0 => {
+                (TaskStatus::Completed, format!("All {} subtasks completed", completed))
+            }
+            RollupPolicy::BestEffort if open == 0 && completed + failed.len() > 0 => {
+                let mut details = format!("{} subtasks completed", completed);
+                if !failed.is_empty() {
+                    details.push_str(&format!(", {} failed ({})", failed.len(), ids(&failed)));
+                }
+                (TaskStatus::Completed, details)
+            }
+            _ => return None,
+        };
+
+        let entry = self.tasks.get_mut(&parent_id)?;
+        if is_finished(&entry.status) {
+            return None;
+        }
+        let mut parent = entry.clone();
+        parent.status = status.clone();
+        parent.result = Some(details.clone());
+        if status == TaskStatus::Completed {
+            parent.progress = Some(100);
+        }
+        parent.lease_expires_at = None;
+        parent.updated_at = Utc::now();
+
+        let event_id = self.task_event_ids.next();
+        let event = TaskEvent {
+            id: event_id,
+            task_id: parent_id,
+            event_type: if status == TaskStatus::Completed {
+                TaskEventType::Completed
+            } else {
+                TaskEventType::Failed
+            },
+            agent_id: None,
+            details: Some(format!("Rolled up from subtasks: {}", details)),
+            data: Some(serde_json::json!({ "rollup": policy, "subtask_id": child.id })),
+            timestamp: Utc::now(),
+        };
+        let recorded = vec![ReplicationEvent::TaskEventRecorded { event }];
+        let change: fn(Task) -> ReplicationEvent = if status == TaskStatus::Completed {
+            |task| ReplicationEvent::TaskCompleted { task }
+        } else {
+            |task| ReplicationEvent::TaskFailed { task }
+        };
+        self.commit_task(entry, &parent, change, recorded).ok()?;
+        info!(task_id = parent_id, subtask_id = child.id, status = ?status, "Parent task rolled up");
+        Some(parent)
+    }
+
+    /// Move a claimed or in-progress task to another agent.
+    ///
+    /// The task goes back to `Claimed`: the new agent must start it again.
+    /// Returns the updated task and the agent it was taken from.
+    pub fn reassign_task(
+        &self,
+        task_id: u64,
+        agent_id: &str,
+        new_agent_id: &str,
+        reason: Option<String>,
+    ) -> Result<(Task, Option<String>), WriteError> {
+        let entry = self.tasks.get_mut(&task_id)
+            .ok_or_else(|| format!("Task {} not found", task_id))?;
+
+        let mut task = entry.clone();
+        if !matches!(task.status, TaskStatus::Claimed | TaskStatus::InProgress) {
+            return Err(format!("Task {} is not claimed or in progress (status: {:?})", task_id, task.status).into());
+        }
+        if task.assigned_agent.as_deref() == Some(new_agent_id) {
+            return Err(format!("Task {} is already assigned to {}", task_id, new_agent_id).into());
+        }
+
+        let previous_agent = task.assigned_agent.replace(new_agent_id.to_string());
+        task.status = TaskStatus::Claimed;
+        task.lease_expires_at = self.lease_deadline();
+        task.updated_at = Utc::now();
+
+        let mut details = format!(
+            "{} -> {}",
+            previous_agent.as_deref().unwrap_or("unassigned"),
+            new_agent_id
+        );
+        if let Some(reason) = reason {
+            details.push_str(&format!(": {}", reason));
+        }
+        let event_id = self.task_event_ids.next();
+        let event = TaskEvent {
+            id: event_id,
+            task_id,
+            event_type: TaskEventType::Reassigned,
+            agent_id: Some(agent_id.to_string()),
+            details: Some(details),
+            data: None,
+            timestamp: Utc::now(),
+        };
+        let recorded = vec![ReplicationEvent::TaskEventRecorded { event }];
+        self.commit_task(entry, &task, |task| ReplicationEvent::TaskReassigned { task }, recorded)?;
+        info!(task_id, agent_id, new_agent_id, "Task reassigned");
+        Ok((task, previous_agent))
+    }
+
+    /// Expiry for a lease taken or renewed now (`None` when leases are disabled).
+    fn lease_deadline(&self) -> Option<chrono::DateTime<Utc>> {
+        (self.config.task_lease_secs > 0)
+            .then(|| Utc::now() + chrono::Duration::seconds(self.config.task_lease_secs as i64))
+    }
+
+    /// Record a progress report from the agent working on a task. Renews the
+    /// lease like a heartbeat.
+    pub fn report_task_progress(&self, task_id: u64, req: TaskProgressRequest) -> Result<Task, WriteError> {
+        if let Some(percent) = req.percent
+            && percent > 100
+        {
+            return Err(format!("Progress must be between 0 and 100, got {}", percent).into());
+        }
+
+        let entry = self.tasks.get_mut(&task_id)
+            .ok_or_else(|| format!("Task {} not found", task_id))?;
+        if !matches!(entry.status, TaskStatus::Claimed | TaskStatus::InProgress) {
+            return Err(format!("Task {} is not being worked on (status: {:?})", task_id, entry.status).into());
+        }
+        if entry.assigned_agent.as_deref() != Some(req.agent_id.as_str()) {
+            return Err(format!("Task {} is assigned to {:?}, not {}", task_id, entry.assigned_agent, req.agent_id).into());
+        }
+
+        let mut task = entry.clone();
+        if req.percent.is_some() {
+            task.progress = req.percent;
+        }
+        task.lease_expires_at = self.lease_deadline();
+        task.updated_at = Utc::now();
+
+        let details = match (req.percent, req.message) {
+            (Some(percent), Some(message)) => Some(format!("{}%: {}", percent, message)),
+            (Some(percent), None) => Some(format!("{}%", percent)),
+            (None, message) => message,
+        };
+        let event_id = self.task_event_ids.next();
+        let event = TaskEvent {
+            id: event_id,
+            task_id,
+            event_type: TaskEventType::Progress,
+            agent_id: Some(req.agent_id),
+            details,
+            data: (!req.details.is_null()).then_some(req.details),
             timestamp: Utc::now(),
         };
-        self.task_events.entry(task_id).or_default().push(event);
+        let recorded = vec![ReplicationEvent::TaskEventRecorded { event }];
+        self.commit_task(entry, &task, |task| ReplicationEvent::TaskUpdated { task }, recorded)?;
+        Ok(task)
+    }
+
+    /// Renew the lease on a task the agent is working on.
+    pub fn heartbeat_task(&self, task_id: u64, agent_id: &str) -> Result<Task, WriteError> {
+        let entry = self.tasks.get_mut(&task_id)
+            .ok_or_else(|| format!("Task {} not found", task_id))?;
+
+        let mut task = entry.clone();
+        if !matches!(task.status, TaskStatus::Claimed | TaskStatus::InProgress) {
+            return Err(format!("Task {} is not claimed or in progress (status: {:?})", task_id, task.status).into());
+        }
+        if task.assigned_agent.as_deref() != Some(agent_id) {
+            return Err(format!("Task {} is assigned to {:?}, not {}", task_id, task.assigned_agent, agent_id).into());
+        }
+        task.lease_expires_at = self.lease_deadline();
+
+        self.commit_task(entry, &task, |task| ReplicationEvent::TaskUpdated { task }, Vec::new())?;
+        Ok(task)
+    }
+
+    /// Requeue claimed or in-progress tasks whose lease has expired.
+    ///
+    /// Each task goes back to `Pending` with a `Reassigned` event, unless it
+    /// has used up `task_max_attempts` claims, in which case it is marked
+    /// `Failed`. Returns the affected tasks with the agent that lost them,
+    /// and the parents that failed tasks rolled up.
+    pub fn reap_expired_tasks(&self) -> (Vec<(Task, Option<String>)>, Vec<Task>) {
+        if !self.is_maintainer() {
+            return (Vec::new(), Vec::new());
+        }
+        let now = Utc::now();
+        let expired: Vec<u64> = self
+            .tasks
+            .iter()
+            .filter(|t| {
+                matches!(t.status, TaskStatus::Claimed | TaskStatus::InProgress)
+                    && t.lease_expires_at.is_some_and(|at| at <= now)
+            })
+            .map(|t| t.id)
+            .collect();
+
+        let max_attempts = self.config.task_max_attempts;
+        let mut reaped = Vec::new();
+        let mut follow_ups = Vec::new();
+        for task_id in expired {
+            let Some(entry) = self.tasks.get_mut(&task_id) else {
+                continue;
+            };
+            // Re-check under the lock: a heartbeat may have just renewed it
+            if !matches!(entry.status, TaskStatus::Claimed | TaskStatus::InProgress)
+                || entry.lease_expires_at.is_none_or(|at| at > now)
+            {
+                continue;
+            }
+
+            let mut task = entry.clone();
+            let previous_agent = task.assigned_agent.take();
+            let previous = task.status.clone();
+            let exhausted = max_attempts > 0 && task.attempts >= max_attempts;
+            let details = format!(
+                "Lease of {} expired (attempt {} of {})",
+                previous_agent.as_deref().unwrap_or("unassigned"),
+                task.attempts,
+                if max_attempts > 0 { max_attempts.to_string() } else { "unlimited".into() }
+            );
+            task.lease_expires_at = None;
+            task.updated_at = now;
+            if exhausted {
+                task.status = TaskStatus::Failed;
+                task.result = Some(details.clone());
+            } else {
+                task.status = TaskStatus::Pending;
+            }
+
+            let event_id = self.task_event_ids.next();
+            let event = TaskEvent {
+                id: event_id,
+                task_id,
+                event_type: if exhausted { TaskEventType::Failed } else { TaskEventType::Reassigned },
+                agent_id: None,
+                details: Some(details),
+                data: None,
+                timestamp: now,
+            };
+            let recorded = vec![ReplicationEvent::TaskEventRecorded { event }];
+            let change: fn(Task) -> ReplicationEvent = if exhausted {
+                |task| ReplicationEvent::TaskFailed { task }
+            } else {
+                |task| ReplicationEvent::TaskReassigned { task }
+            };
+            if self.commit_task(entry, &task, change, recorded).is_err() {
+                continue;
+            }
+
+            if exhausted {
+                warn!(task_id, attempts = task.attempts, "Task lease expired, attempts exhausted");
+                follow_ups.extend(self.finish_follow_ups(&previous, &task));
+            } else {
+                info!(task_id, attempts = task.attempts, "Task lease expired, requeued");
+            }
+            reaped.push((task, previous_agent));
+        }
+        (reaped, follow_ups)
+    }
+
+    /// Flag open tasks whose deadline has passed, once each, and apply the
+    /// configured [`OverdueAction`]. Returns the tasks that were flagged, and
+    /// the parents that failed tasks rolled up.
+    pub fn sweep_overdue_tasks(&self) -> (Vec<Task>, Vec<Task>) {
+        if !self.is_maintainer() {
+            return (Vec::new(), Vec::new());
+        }
+        let now = Utc::now();
+        let due: Vec<u64> = self
+            .tasks
+            .iter()
+            .filter(|t| t.overdue_at.is_none() && is_overdue(t, now))
+            .map(|t| t.id)
+            .collect();
+        if due.is_empty() {
+            return (Vec::new(), Vec::new());
+        }
+
+        let action = self.config.task_overdue_action;
+        // Escalated tasks jump ahead of every open task that isn't overdue itself
+        let top_priority = self
+            .tasks
+            .iter()
+            .filter(|t| {
+                !due.contains(&t.id) && !is_finished(&t.status)
+            })
+            .map(|t| t.priority)
+            .max()
+            .unwrap_or(0);
+
+        let mut flagged = Vec::new();
+        let mut follow_ups = Vec::new();
+        for task_id in due {
+            let Some(entry) = self.tasks.get_mut(&task_id) else {
+                continue;
+            };
+            if entry.overdue_at.is_some() || !is_overdue(&entry, now) {
+                continue;
+            }
+            let mut task = entry.clone();
+            let deadline = task.deadline.map(|d| d.to_rfc3339()).unwrap_or_default();
+            let mut details = format!("Deadline {} passed", deadline);
+            let previous = task.status.clone();
+            task.overdue_at = Some(now);
+            task.updated_at = now;
+            match action {
+                OverdueAction::Notify => {}
+                OverdueAction::Fail => {
+                    task.status = TaskStatus::Failed;
+                    task.result = Some(details.clone());
+                    task.lease_expires_at = None;
+                    details.push_str("; task failed");
+                }
+                OverdueAction::Escalate => {
+                    if task.priority <= top_priority {
+                        task.priority = top_priority.saturating_add(1);
+                    }
+                    details.push_str(&format!("; priority raised to {}", task.priority));
+                }
+            }
 
-        self.emit_replication(ReplicationEvent::TaskFailed { task: task_clone.clone() });
-        info!(task_id, agent_id, "Task failed");
-        Ok(task_clone)
+            let event_id = self.task_event_ids.next();
+            let event = TaskEvent {
+                id: event_id,
+                task_id,
+                event_type: TaskEventType::Overdue,
+                agent_id: None,
+                details: Some(details),
+                data: None,
+                timestamp: now,
+            };
+            let recorded = vec![ReplicationEvent::TaskEventRecorded { event }];
+            let change: fn(Task) -> ReplicationEvent = if task.status == TaskStatus::Failed {
+                |task| ReplicationEvent::TaskFailed { task }
+            } else {
+                |task| ReplicationEvent::TaskUpdated { task }
+            };
+            if self.commit_task(entry, &task, change, recorded).is_err() {
+                continue;
+            }
+
+            warn!(task_id, action = ?action, "Task overdue");
+            if task.status == TaskStatus::Failed {
+                follow_ups.extend(self.finish_follow_ups(&previous, &task));
+            }
+            flagged.push(task);
+        }
+        (flagged, follow_ups)
     }
 
     pub fn get_task(&self, task_id: u64) -> Option<Task> {
         self.tasks.get(&task_id).map(|t| t.value().clone())
     }
 
+    /// Direct subtasks of a task, oldest first.
+    pub fn list_subtasks(&self, parent_id: u64) -> Vec<Task> {
+        let mut subtasks: Vec<Task> = self
+            .tasks
+            .iter()
+            .filter(|t| t.parent_id == Some(parent_id))
+            .map(|t| t.value().clone())
+            .collect();
+        subtasks.sort_by_key(|t| t.id);
+        subtasks
+    }
+
     pub fn list_tasks(
         &self,
         status: Option<&TaskStatus>,
         agent_id: Option<&str>,
         capabilities: Option<&[String]>,
     ) -> Vec<Task> {
-        self.tasks
+        let mut tasks: Vec<Task> = self.tasks
             .iter()
             .filter(|entry| {
                 let task = entry.value();
@@ -1170,7 +3275,57 @@ impl MemoryEngine {
                 true
             })
             .map(|entry| entry.value().clone())
-            .collect()
+            .collect();
+        tasks.sort_by(cmp_urgency);
+        tasks
+    }
+
+    /// The dependency graph a task belongs to: every task connected to it
+    /// through dependencies, with an edge from each dependency to its dependent.
+    pub fn get_task_graph(&self, task_id: u64) -> Option<TaskGraph> {
+        if !self.tasks.contains_key(&task_id) {
+            return None;
+        }
+
+        let mut dependents: HashMap<u64, Vec<u64>> = HashMap::new();
+        for task in self.tasks.iter() {
+            for dep in &task.dependencies {
+                dependents.entry(*dep).or_default().push(task.id);
+            }
+        }
+
+        // Follow edges in both directions to collect the whole pipeline
+        let mut included = std::collections::BTreeSet::from([task_id]);
+        let mut stack = vec![task_id];
+        while let Some(id) = stack.pop() {
+            let upstream = self.tasks.get(&id).map(|t| t.dependencies.clone()).unwrap_or_default();
+            let downstream = dependents.get(&id).cloned().unwrap_or_default();
+            stack.extend(upstream.into_iter().chain(downstream).filter(|n| included.insert(*n)));
+        }
+
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+        for id in included.iter() {
+            let Some(task) = self.get_task(*id) else {
+                continue;
+            };
+            for dep in &task.dependencies {
+                if included.contains(dep) {
+                    edges.push(TaskGraphEdge { from: *dep, to: task.id });
+                }
+            }
+            nodes.push(TaskGraphNode {
+                blocked_by: self.blocking_dependencies(&task.dependencies),
+                task,
+            });
+        }
+        edges.sort();
+
+        Some(TaskGraph {
+            root: task_id,
+            nodes,
+            edges,
+        })
     }
 
     pub fn get_task_events(&self, task_id: u64) -> Vec<TaskEvent> {
@@ -1217,10 +3372,12 @@ impl MemoryEngine {
 
         let tasks = TasksHealthInfo {
             total: self.tasks.len(),
+            blocked: self.tasks.iter().filter(|t| t.value().status == TaskStatus::Blocked).count(),
             pending: self.tasks.iter().filter(|t| t.value().status == TaskStatus::Pending).count(),
             in_progress: self.tasks.iter().filter(|t| t.value().status == TaskStatus::InProgress).count(),
             completed: self.tasks.iter().filter(|t| t.value().status == TaskStatus::Completed).count(),
             failed: self.tasks.iter().filter(|t| t.value().status == TaskStatus::Failed).count(),
+            overdue: self.tasks.iter().filter(|t| is_overdue(t, Utc::now())).count(),
         };
 
         (embedding, inverted_index, memory_store, knowledge_graph, tasks)
@@ -1235,6 +3392,8 @@ impl MemoryEngine {
             indexed_count: self.embeddings.indexed_count(),
             pool_size: self.embeddings.pool_size(),
             available: self.embeddings.is_available(),
+            index_model: self.embeddings.index_model(),
+            reindex: self.embeddings.reindex_status(),
         }
     }
 
@@ -1283,7 +3442,7 @@ impl MemoryEngine {
             "write" => {
                 for i in 0..iterations {
                     let start = std::time::Instant::now();
-                    self.add_memory(AddMemoryRequest {
+                    if self.add_memory(AddMemoryRequest {
                         content: format!("Benchmark memory {} for write test", i),
                         memory_type: MemoryType::Fact,
                         agent_id: Some(agent_id.to_string()),
@@ -1291,7 +3450,10 @@ impl MemoryEngine {
                         session_id: None,
                         tags: vec!["benchmark".into()],
                         metadata: serde_json::Value::Null,
-                    });
+                    }).is_err() {
+                        errors += 1;
+                        continue;
+                    }
                     latencies_us.push(start.elapsed().as_micros() as f64);
                 }
             }
@@ -1311,7 +3473,10 @@ impl MemoryEngine {
                         })
                         .collect();
                     let start = std::time::Instant::now();
-                    self.add_memories_bulk(reqs);
+                    if self.add_memories_bulk(reqs).is_err() {
+                        errors += 1;
+                        continue;
+                    }
                     latencies_us.push(start.elapsed().as_micros() as f64);
                 }
             }
@@ -1319,7 +3484,7 @@ impl MemoryEngine {
                 // Seed some data if empty
                 if self.memories.is_empty() {
                     for i in 0..10 {
-                        self.add_memory(AddMemoryRequest {
+                        let _ = self.add_memory(AddMemoryRequest {
                             content: format!("Benchmark seed memory {} for search test", i),
                             memory_type: MemoryType::Fact,
                             agent_id: Some(agent_id.to_string()),
@@ -1339,6 +3504,7 @@ impl MemoryEngine {
                         tags: vec![],
                         limit: 10,
                         include_graph: false,
+                        structured: false,
                     });
                     latencies_us.push(start.elapsed().as_micros() as f64);
                 }
@@ -1363,6 +3529,7 @@ impl MemoryEngine {
                         tags: vec![],
                         limit: 10,
                         include_graph: false,
+                        structured: false,
                     }).await;
                     latencies_us.push(start.elapsed().as_micros() as f64);
                 }
@@ -1370,41 +3537,58 @@ impl MemoryEngine {
             "entity_create" => {
                 for i in 0..iterations {
                     let start = std::time::Instant::now();
-                    self.add_entity(AddEntityRequest {
+                    if self.add_entity(AddEntityRequest {
                         name: format!("BenchEntity-{}-{}", agent_id, i),
                         entity_type: "benchmark".into(),
                         description: Some("Benchmark entity".into()),
                         agent_id: Some(agent_id.to_string()),
                         metadata: serde_json::Value::Null,
-                    });
+                    }).is_err() {
+                        errors += 1;
+                        continue;
+                    }
                     latencies_us.push(start.elapsed().as_micros() as f64);
                 }
             }
             "graph_traverse" => {
                 // Create a small graph to traverse
-                let e1 = self.add_entity(AddEntityRequest {
+                let graph = self.add_entity(AddEntityRequest {
                     name: format!("BenchTraverseRoot-{}", agent_id),
                     entity_type: "benchmark".into(),
                     description: None,
                     agent_id: Some(agent_id.to_string()),
                     metadata: serde_json::Value::Null,
+                })
+                .and_then(|e1| {
+                    let e2 = self.add_entity(AddEntityRequest {
+                        name: format!("BenchTraverseChild-{}", agent_id),
+                        entity_type: "benchmark".into(),
+                        description: None,
+                        agent_id: Some(agent_id.to_string()),
+                        metadata: serde_json::Value::Null,
+                    })?;
+                    self.add_relationship(AddRelationshipRequest {
+                        source_entity_id: e1.id,
+                        target_entity_id: e2.id,
+                        relation_type: "benchmark_link".into(),
+                        description: None,
+                        weight: 1.0,
+                        created_by: agent_id.to_string(),
+                        metadata: serde_json::Value::Null,
+                    })?;
+                    Ok(e1)
                 });
-                let e2 = self.add_entity(AddEntityRequest {
-                    name: format!("BenchTraverseChild-{}", agent_id),
-                    entity_type: "benchmark".into(),
-                    description: None,
-                    agent_id: Some(agent_id.to_string()),
-                    metadata: serde_json::Value::Null,
-                });
-                self.add_relationship(AddRelationshipRequest {
-                    source_entity_id: e1.id,
-                    target_entity_id: e2.id,
-                    relation_type: "benchmark_link".into(),
-                    description: None,
-                    weight: 1.0,
-                    created_by: agent_id.to_string(),
-                    metadata: serde_json::Value::Null,
-                });
+                let Ok(e1) = graph else {
+                    errors = iterations;
+                    return BenchmarkOperationResult {
+                        operation: operation.to_string(),
+                        iterations: 0,
+                        total_ms: 0.0,
+                        latency: compute_latency_stats(&[]),
+                        ops_per_second: 0.0,
+                        errors,
+                    };
+                };
 
                 for _ in 0..iterations {
                     let start = std::time::Instant::now();
@@ -1436,6 +3620,21 @@ impl MemoryEngine {
 
     /// Remove all benchmark data created by a specific benchmark run.
     fn cleanup_benchmark_data(&self, agent_id: &str) {
+        let removed = ReplicationEvent::BenchmarkDataRemoved {
+            agent_id: agent_id.to_string(),
+        };
+        let Ok((memories, entities, relationships)) =
+            self.commit(&[removed], || self.remove_benchmark_data(agent_id))
+        else {
+            return;
+        };
+
+        info!(agent_id, memories, entities, relationships, "Benchmark data cleaned up");
+    }
+
+    /// Drop the memories, entities and relationships created under a
+    /// benchmark agent ID. Returns how many of each were removed.
+    fn remove_benchmark_data(&self, agent_id: &str) -> (usize, usize, usize) {
         // Remove memories
         let memory_ids: Vec<u64> = self.memories.iter()
             .filter(|m| m.value().agent_id.as_deref() == Some(agent_id))
@@ -1455,7 +3654,9 @@ impl MemoryEngine {
             .map(|e| *e.key())
             .collect();
         for id in &entity_ids {
-            self.entities.remove(id);
+            if let Some((_, entity)) = self.entities.remove(id) {
+                self.unindex_entity_name(*id, &entity.name);
+            }
         }
 
         // Remove relationships created by benchmark agent
@@ -1467,13 +3668,7 @@ impl MemoryEngine {
             self.relationships.remove(id);
         }
 
-        info!(
-            agent_id,
-            memories = memory_ids.len(),
-            entities = entity_ids.len(),
-            relationships = rel_ids.len(),
-            "Benchmark data cleaned up"
-        );
+        (memory_ids.len(), entity_ids.len(), rel_ids.len())
     }
 
     pub fn stats(&self) -> serde_json::Value {
@@ -1489,11 +3684,13 @@ impl MemoryEngine {
             "embedding_pool_size": self.embeddings.pool_size(),
             "inverted_index_words": self.inverted_index.len(),
             "extraction_available": self.extraction.is_available(),
-            "replication_enabled": self.replication_tx.is_some(),
+            "replication_enabled": self.replication.is_replicating(),
             "tasks_total": self.tasks.len(),
+            "tasks_blocked": self.tasks.iter().filter(|t| t.value().status == TaskStatus::Blocked).count(),
             "tasks_pending": self.tasks.iter().filter(|t| t.value().status == TaskStatus::Pending).count(),
             "tasks_in_progress": self.tasks.iter().filter(|t| t.value().status == TaskStatus::InProgress).count(),
             "tasks_completed": self.tasks.iter().filter(|t| t.value().status == TaskStatus::Completed).count(),
+            "tasks_overdue": self.tasks.iter().filter(|t| is_overdue(t, Utc::now())).count(),
         })
     }
 }
@@ -1533,7 +3730,8 @@ fn compute_latency_stats(latencies: &[f64]) -> LatencyStats {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::HiveMindConfig;
+    use crate::config::{HiveMindConfig, OverdueAction};
+    use crate::vector_index::VectorIndexConfig;
 
     fn test_config() -> HiveMindConfig {
         HiveMindConfig {
@@ -1547,6 +3745,16 @@ mod tests {
             data_dir: "/tmp/hivemind-test".into(),
             snapshot_interval: 60,
             replication_enabled: false,
+            node_id: "test-node".into(),
+            node_number: 0,
+            bm25_k1: 1.2,
+            bm25_b: 0.75,
+            vector_index: VectorIndexConfig::default(),
+            entity_name_matching: true,
+            task_lease_secs: 300,
+            task_max_attempts: 3,
+            task_overdue_action: OverdueAction::Notify,
+            task_rollup_policy: RollupPolicy::FailFast,
         }
     }
 
@@ -1561,7 +3769,7 @@ mod tests {
             session_id: None,
             tags: vec!["preferences".into()],
             metadata: serde_json::Value::Null,
-        });
+        }).unwrap();
 
         assert_eq!(mem.content, "User prefers Rust");
         assert_eq!(mem.agent_id.as_deref(), Some("agent-1"));
@@ -1582,7 +3790,7 @@ mod tests {
             session_id: None,
             tags: vec![],
             metadata: serde_json::Value::Null,
-        });
+        }).unwrap();
 
         let updated = engine
             .update_memory(
@@ -1594,7 +3802,7 @@ mod tests {
                     metadata: None,
                 },
                 "test-agent",
-            )
+            ).unwrap()
             .unwrap();
 
         assert_eq!(updated.content, "User prefers Rust over Python");
@@ -1612,10 +3820,10 @@ mod tests {
             session_id: None,
             tags: vec![],
             metadata: serde_json::Value::Null,
-        });
+        }).unwrap();
 
         let invalidated = engine
-            .invalidate_memory(mem.id, "User changed jobs", "agent-1")
+            .invalidate_memory(mem.id, "User changed jobs", "agent-1").unwrap()
             .unwrap();
         assert!(invalidated.valid_until.is_some());
 
@@ -1626,7 +3834,9 @@ mod tests {
             tags: vec![],
             limit: 10,
             include_graph: false,
-        });
+            structured: false,
+        })
+        .unwrap();
         assert!(results.is_empty());
     }
 
@@ -1641,7 +3851,7 @@ mod tests {
             session_id: None,
             tags: vec![],
             metadata: serde_json::Value::Null,
-        });
+        }).unwrap();
 
         engine.update_memory(
             mem.id,
@@ -1652,9 +3862,9 @@ mod tests {
                 metadata: None,
             },
             "test",
-        );
+        ).unwrap();
 
-        engine.invalidate_memory(mem.id, "outdated", "test");
+        engine.invalidate_memory(mem.id, "outdated", "test").unwrap();
 
         let history = engine.get_memory_history(mem.id);
         assert_eq!(history.len(), 3);
@@ -1675,7 +3885,7 @@ mod tests {
             session_id: None,
             tags: vec!["preferences".into(), "ui".into()],
             metadata: serde_json::Value::Null,
-        });
+        }).unwrap();
 
         engine.add_memory(AddMemoryRequest {
             content: "User likes Italian food".into(),
@@ -1685,7 +3895,7 @@ mod tests {
             session_id: None,
             tags: vec!["preferences".into(), "food".into()],
             metadata: serde_json::Value::Null,
-        });
+        }).unwrap();
 
         engine.add_memory(AddMemoryRequest {
             content: "RaftTimeDB uses openraft".into(),
@@ -1695,7 +3905,7 @@ mod tests {
             session_id: None,
             tags: vec!["technical".into()],
             metadata: serde_json::Value::Null,
-        });
+        }).unwrap();
 
         let results = engine.search(&SearchRequest {
             query: "dark mode".into(),
@@ -1704,7 +3914,9 @@ mod tests {
             tags: vec![],
             limit: 10,
             include_graph: false,
-        });
+            structured: false,
+        })
+        .unwrap();
         assert_eq!(results.len(), 1);
         assert!(results[0].memory.content.contains("dark mode"));
 
@@ -1715,10 +3927,130 @@ mod tests {
             tags: vec!["preferences".into()],
             limit: 10,
             include_graph: false,
-        });
+            structured: false,
+        })
+        .unwrap();
         assert_eq!(results.len(), 2);
     }
 
+    #[test]
+    fn test_search_bm25_ranking() {
+        let engine = MemoryEngine::new(test_config());
+        let add = |content: &str| {
+            engine
+                .add_memory(AddMemoryRequest {
+                    content: content.into(),
+                    memory_type: MemoryType::Fact,
+                    agent_id: None,
+                    user_id: None,
+                    session_id: None,
+                    tags: vec![],
+                    metadata: serde_json::Value::Null,
+                }).unwrap()
+                .id
+        };
+
+        let repeated = add("cache cache cache eviction");
+        let once = add("cache warming strategy for the build pipeline servers");
+        let rare = add("kubernetes rollout notes");
+        for i in 0..5 {
+            add(&format!("deploy pipeline run {}", i));
+        }
+
+        // Repeated words are stored once with their frequency
+        assert_eq!(engine.inverted_index.get("cache").unwrap().get(&repeated), Some(&3));
+        assert_eq!(engine.inverted_index.get("cache").unwrap().len(), 2);
+
+        let search = |query: &str| {
+            engine.search(&SearchRequest {
+                query: query.into(),
+                agent_id: None,
+                user_id: None,
+                tags: vec![],
+                limit: 10,
+                include_graph: false,
+                structured: false,
+            })
+            .unwrap()
+        };
+
+        // Higher term frequency in a shorter memory ranks first
+        let results = search("cache");
+        assert_eq!(results[0].memory.id, repeated);
+        assert_eq!(results[1].memory.id, once);
+        assert!((results[0].score - 1.0).abs() < 1e-6);
+
+        // A rare term outweighs a common one
+        let results = search("pipeline kubernetes");
+        assert_eq!(results[0].memory.id, rare);
+
+        // Unindexing drops the postings and the document length
+        engine.invalidate_memory(rare, "test", "tester").unwrap();
+        assert!(engine.inverted_index.get("kubernetes").is_none());
+        assert!(!engine.doc_lengths.contains_key(&rare));
+        assert_eq!(engine.doc_lengths.len(), 7);
+    }
+
+    #[test]
+    fn test_search_query_language() {
+        let engine = MemoryEngine::new(test_config());
+        let add = |content: &str, memory_type: MemoryType, tags: &[&str]| {
+            engine
+                .add_memory(AddMemoryRequest {
+                    content: content.into(),
+                    memory_type,
+                    agent_id: None,
+                    user_id: None,
+                    session_id: None,
+                    tags: tags.iter().map(|t| t.to_string()).collect(),
+                    metadata: serde_json::Value::Null,
+                }).unwrap()
+                .id
+        };
+        let tune = add("Raise the connection pool size to 50", MemoryType::Procedural, &["infra"]);
+        add("Raise the connection pool size for postgres", MemoryType::Procedural, &["infra"]);
+        add("The pool has a connection limit", MemoryType::Procedural, &["infra"]);
+        let fact = add("The connection pool is shared", MemoryType::Fact, &["infra"]);
+
+        let search = |query: &str| {
+            engine.search(&SearchRequest {
+                query: query.into(),
+                agent_id: None,
+                user_id: None,
+                tags: vec![],
+                limit: 10,
+                include_graph: false,
+                structured: true,
+            })
+        };
+
+        let results = search("\"connection pool\" AND NOT postgres tag:infra type:procedural").unwrap();
+        assert_eq!(results.iter().map(|r| r.memory.id).collect::<Vec<_>>(), vec![tune]);
+
+        // Filter-only queries scan every memory
+        let results = search("type:fact").unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].memory.id, fact);
+
+        let err = search("tag:infra AND").unwrap_err();
+        assert_eq!(err.message, "'AND' needs a clause on both sides");
+
+        // Without `structured` the same text is just words, and never rejected
+        let plain = engine
+            .search(&SearchRequest {
+                query: "why is the \"connection pool NOT shared (".into(),
+                agent_id: None,
+                user_id: None,
+                tags: vec![],
+                limit: 10,
+                include_graph: false,
+                structured: false,
+            })
+            .unwrap();
+        assert_eq!(plain.len(), 4);
+        assert_eq!(plain[0].memory.id, fact);
+    }
+
     #[test]
     fn test_search_filters_by_user() {
         let engine = MemoryEngine::new(test_config());
@@ -1731,7 +4063,7 @@ mod tests {
             session_id: None,
             tags: vec![],
             metadata: serde_json::Value::Null,
-        });
+        }).unwrap();
 
         engine.add_memory(AddMemoryRequest {
             content: "Bob prefers dogs".into(),
@@ -1741,7 +4073,7 @@ mod tests {
             session_id: None,
             tags: vec![],
             metadata: serde_json::Value::Null,
-        });
+        }).unwrap();
 
         let results = engine.search(&SearchRequest {
             query: "prefers".into(),
@@ -1750,7 +4082,9 @@ mod tests {
             tags: vec![],
             limit: 10,
             include_graph: false,
-        });
+            structured: false,
+        })
+        .unwrap();
         assert_eq!(results.len(), 1);
         assert!(results[0].memory.content.contains("cats"));
     }
@@ -1765,7 +4099,7 @@ mod tests {
             description: None,
             agent_id: None,
             metadata: serde_json::Value::Null,
-        });
+        }).unwrap();
 
         let rafttimedb = engine.add_entity(AddEntityRequest {
             name: "RaftTimeDB".into(),
@@ -1773,7 +4107,7 @@ mod tests {
             description: Some("Distributed clustering for SpacetimeDB".into()),
             agent_id: None,
             metadata: serde_json::Value::Null,
-        });
+        }).unwrap();
 
         let rel = engine.add_relationship(AddRelationshipRequest {
             source_entity_id: alice.id,
@@ -1783,7 +4117,7 @@ mod tests {
             weight: 1.0,
             created_by: "test".into(),
             metadata: serde_json::Value::Null,
-        });
+        }).unwrap();
 
         assert_eq!(rel.source_entity_id, alice.id);
         assert_eq!(rel.target_entity_id, rafttimedb.id);
@@ -1793,6 +4127,262 @@ mod tests {
         assert_eq!(rels[0].1.name, "RaftTimeDB");
     }
 
+    #[test]
+    fn test_search_include_graph() {
+        let engine = MemoryEngine::new(test_config());
+        let entity = |name: &str| {
+            engine.add_entity(AddEntityRequest {
+                name: name.into(),
+                entity_type: "Project".into(),
+                description: None,
+                agent_id: None,
+                metadata: serde_json::Value::Null,
+            }).unwrap()
+        };
+        let relate = |source: &Entity, target: &Entity, relation: &str| {
+            engine.add_relationship(AddRelationshipRequest {
+                source_entity_id: source.id,
+                target_entity_id: target.id,
+                relation_type: relation.into(),
+                description: None,
+                weight: 1.0,
+                created_by: "test".into(),
+                metadata: serde_json::Value::Null,
+            }).unwrap()
+        };
+        let raft = entity("RaftTimeDB");
+        let openraft = entity("openraft");
+        let spacetime = entity("SpacetimeDB");
+        entity("Raft"); // substring of a mentioned name, but not a whole word
+        let uses = relate(&raft, &openraft, "uses");
+        let wraps = relate(&raft, &spacetime, "wraps");
+        engine.relationships.get_mut(&wraps.id).unwrap().valid_until = Some(Utc::now());
+
+        engine.add_memory(AddMemoryRequest {
+            content: "RaftTimeDB uses openraft".into(),
+            memory_type: MemoryType::Semantic,
+            agent_id: None,
+            user_id: None,
+            session_id: None,
+            tags: vec![],
+            metadata: serde_json::Value::Null,
+        }).unwrap();
+
+        let mut req = SearchRequest {
+            query: "openraft".into(),
+            agent_id: None,
+            user_id: None,
+            tags: vec![],
+            limit: 10,
+            include_graph: false,
+            structured: false,
+        };
+        let results = engine.search(&req).unwrap();
+        assert!(results[0].related_entities.is_empty());
+
+        req.include_graph = true;
+        let results = engine.search(&req).unwrap();
+        let names: Vec<&str> = results[0].related_entities.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["RaftTimeDB", "openraft"]);
+        // Only live relationships are included
+        let rel_ids: Vec<u64> = results[0].related_relationships.iter().map(|r| r.id).collect();
+        assert_eq!(rel_ids, vec![uses.id]);
+    }
+
+    #[tokio::test]
+    async fn test_session_lifecycle() {
+        let engine = MemoryEngine::new(test_config());
+        let session = engine.start_session(StartSessionRequest {
+            agent_id: "agent-1".into(),
+            user_id: Some("alice".into()),
+            session_id: Some("s1".into()),
+            metadata: serde_json::json!({"repo": "hivemind"}),
+        }).unwrap();
+        assert_eq!(engine.list_sessions(Some("agent-1")).len(), 1);
+
+        let remember = |content: &str, tags: &[&str], session_id: &str| {
+            engine.add_memory(AddMemoryRequest {
+                content: content.into(),
+                memory_type: MemoryType::Episodic,
+                agent_id: Some("agent-1".into()),
+                user_id: Some("alice".into()),
+                session_id: Some(session_id.into()),
+                tags: tags.iter().map(|t| t.to_string()).collect(),
+                metadata: serde_json::Value::Null,
+            }).unwrap()
+        };
+        remember("Investigated flaky snapshot test", &["tool:cargo"], "s1");
+        remember("Switched snapshots to binary encoding", &["decision", "tool:git"], "s1");
+        remember("All tests pass", &["outcome", "tool:cargo"], "s1");
+        remember("Unrelated session", &["decision"], "other");
+
+        // No LLM configured: summarize falls back to the heuristic summary
+        let episode = engine
+            .end_session(&session.session_id, EndSessionRequest {
+                summarize: true,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(episode.agent_id, "agent-1");
+        assert_eq!(episode.started_at, session.started_at);
+        assert!(episode.summary.starts_with("3 memories recorded"));
+        assert_eq!(episode.key_decisions, vec!["Switched snapshots to binary encoding"]);
+        assert_eq!(episode.tools_used, vec!["cargo", "git"]);
+        assert_eq!(episode.outcome, "All tests pass");
+        assert_eq!(episode.metadata["repo"], "hivemind");
+        assert_eq!(episode.metadata["summarized_by"], "heuristic");
+        assert!(engine.list_sessions(None).is_empty());
+
+        // Unknown sessions need an agent; explicit fields win over derived ones
+        assert!(engine.end_session("other", EndSessionRequest::default()).await.is_err());
+        let other = engine
+            .end_session("other", EndSessionRequest {
+                agent_id: Some("agent-2".into()),
+                outcome: Some("abandoned".into()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(other.outcome, "abandoned");
+        assert_eq!(other.key_decisions, vec!["Unrelated session"]);
+
+        let by_agent = engine.list_episodes(&EpisodeQuery {
+            agent_id: Some("agent-1".into()),
+            ..Default::default()
+        });
+        assert_eq!(by_agent.len(), 1);
+        let future = engine.list_episodes(&EpisodeQuery {
+            since: Some(Utc::now() + chrono::Duration::hours(1)),
+            ..Default::default()
+        });
+        assert!(future.is_empty());
+
+        let results = engine.search_episodes(&EpisodeSearchRequest {
+            query: "binary snapshots".into(),
+            filter: EpisodeQuery::default(),
+        });
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].episode.id, episode.id);
+
+        // Episodes and open sessions survive a snapshot round trip
+        engine.start_session(StartSessionRequest {
+            agent_id: "agent-1".into(),
+            user_id: None,
+            session_id: Some("s2".into()),
+            metadata: serde_json::Value::Null,
+        }).unwrap();
+        let mut restored = MemoryEngine::new(test_config());
+        restored.restore_from_snapshot(engine.create_snapshot());
+        assert_eq!(restored.get_episode(episode.id).unwrap().summary, episode.summary);
+        assert_eq!(restored.list_sessions(None).len(), 1);
+    }
+
+    #[test]
+    fn test_entity_mentions() {
+        let engine = MemoryEngine::new(test_config());
+        let entity = |name: &str| {
+            engine.add_entity(AddEntityRequest {
+                name: name.into(),
+                entity_type: "Project".into(),
+                description: None,
+                agent_id: None,
+                metadata: serde_json::Value::Null,
+            }).unwrap()
+        };
+        let memory = |content: &str| {
+            engine.add_memory(AddMemoryRequest {
+                content: content.into(),
+                memory_type: MemoryType::Fact,
+                agent_id: None,
+                user_id: None,
+                session_id: None,
+                tags: vec![],
+                metadata: serde_json::Value::Null,
+            }).unwrap()
+        };
+        let hive = entity("HiveMindDB");
+        let raft = entity("RaftTimeDB");
+
+        let m1 = memory("HiveMindDB replicates through RaftTimeDB");
+        let m2 = memory("hiveminddb snapshots are compressed");
+        let m3 = memory("Nothing relevant here");
+
+        let names: Vec<String> = engine.get_memory_entities(m1.id).into_iter().map(|e| e.name).collect();
+        assert_eq!(names, vec!["HiveMindDB", "RaftTimeDB"]);
+        assert!(engine.get_memory_entities(m3.id).is_empty());
+        let ids: Vec<u64> = engine.get_entity_memories(hive.id).iter().map(|m| m.id).collect();
+        assert_eq!(ids, vec![m2.id, m1.id]);
+
+        // Explicit links are idempotent and require both sides to exist
+        assert!(engine.link_memory_entity(m3.id, raft.id, MentionSource::Extraction).unwrap().is_some());
+        assert!(engine.link_memory_entity(m3.id, raft.id, MentionSource::NameMatch).unwrap().is_none());
+        assert!(engine.link_memory_entity(m3.id, 999, MentionSource::Extraction).unwrap().is_none());
+        assert_eq!(engine.get_memory_mentions(m3.id)[0].source, MentionSource::Extraction);
+
+        // Invalidated memories drop out of the entity's list
+        engine.invalidate_memory(m2.id, "outdated", "test").unwrap();
+        assert_eq!(engine.get_entity_memories(hive.id).len(), 1);
+
+        // Links survive a snapshot round trip and replicated events
+        let snapshot = engine.create_snapshot();
+        assert_eq!(snapshot.mentions.len(), 4);
+        let mut restored = MemoryEngine::new(test_config());
+        restored.restore_from_snapshot(snapshot);
+        assert_eq!(restored.get_entity_memories(raft.id).len(), 2);
+        restored.apply_replication_event(ReplicationEvent::MentionAdded {
+            mention: engine.get_memory_mentions(m1.id)[0].clone(),
+        });
+        assert_eq!(restored.get_memory_mentions(m1.id).len(), 2);
+
+        // Edited memories are matched again
+        engine.update_memory(
+            m3.id,
+            UpdateMemoryRequest {
+                content: Some("Now about HiveMindDB".into()),
+                tags: None,
+                confidence: None,
+                metadata: None,
+            },
+            "test",
+        ).unwrap();
+        assert_eq!(engine.get_memory_entities(m3.id).len(), 2);
+
+        // Entities created or renamed later pick up the memories naming them
+        let m4 = memory("The Raft Log is compacted nightly");
+        let log = entity("Raft Log");
+        assert_eq!(engine.get_memory_entities(m4.id)[0].id, log.id);
+        let old = entity("Compactor");
+        engine.update_entity(
+            old.id,
+            UpdateEntityRequest { name: Some("Compacted Nightly".into()), entity_type: None, description: None, metadata: None },
+        ).unwrap();
+        assert_eq!(engine.get_entity_memories(old.id)[0].id, m4.id);
+        assert!(!engine.entity_names.contains_key("compactor"));
+
+        // Name matching can be turned off
+        let mut config = test_config();
+        config.entity_name_matching = false;
+        let quiet = MemoryEngine::new(config);
+        quiet.add_entity(AddEntityRequest {
+            name: "HiveMindDB".into(),
+            entity_type: "Project".into(),
+            description: None,
+            agent_id: None,
+            metadata: serde_json::Value::Null,
+        }).unwrap();
+        let m = quiet.add_memory(AddMemoryRequest {
+            content: "HiveMindDB is fast".into(),
+            memory_type: MemoryType::Fact,
+            agent_id: None,
+            user_id: None,
+            session_id: None,
+            tags: vec![],
+            metadata: serde_json::Value::Null,
+        }).unwrap();
+        assert!(quiet.get_memory_entities(m.id).is_empty());
+    }
+
     #[test]
     fn test_graph_traversal() {
         let engine = MemoryEngine::new(test_config());
@@ -1803,21 +4393,21 @@ mod tests {
             description: None,
             agent_id: None,
             metadata: serde_json::Value::Null,
-        });
+        }).unwrap();
         let b = engine.add_entity(AddEntityRequest {
             name: "B".into(),
             entity_type: "Node".into(),
             description: None,
             agent_id: None,
             metadata: serde_json::Value::Null,
-        });
+        }).unwrap();
         let c = engine.add_entity(AddEntityRequest {
             name: "C".into(),
             entity_type: "Node".into(),
             description: None,
             agent_id: None,
             metadata: serde_json::Value::Null,
-        });
+        }).unwrap();
 
         engine.add_relationship(AddRelationshipRequest {
             source_entity_id: a.id,
@@ -1827,7 +4417,7 @@ mod tests {
             weight: 1.0,
             created_by: "test".into(),
             metadata: serde_json::Value::Null,
-        });
+        }).unwrap();
         engine.add_relationship(AddRelationshipRequest {
             source_entity_id: b.id,
             target_entity_id: c.id,
@@ -1836,7 +4426,7 @@ mod tests {
             weight: 1.0,
             created_by: "test".into(),
             metadata: serde_json::Value::Null,
-        });
+        }).unwrap();
 
         let result = engine.traverse(a.id, 1);
         let names: Vec<&str> = result.iter().map(|(e, _)| e.name.as_str()).collect();
@@ -1861,7 +4451,7 @@ mod tests {
             description: None,
             agent_id: None,
             metadata: serde_json::Value::Null,
-        });
+        }).unwrap();
 
         let found = engine.find_entity_by_name("rafttimedb").unwrap();
         assert_eq!(found.name, "RaftTimeDB");
@@ -1878,7 +4468,7 @@ mod tests {
             agent_type: "claude-code".into(),
             capabilities: vec!["coding".into(), "research".into()],
             metadata: serde_json::Value::Null,
-        });
+        }).unwrap();
 
         assert_eq!(agent.agent_id, "claude-1");
         assert_eq!(agent.status, AgentStatus::Online);
@@ -1899,7 +4489,7 @@ mod tests {
             session_id: None,
             tags: vec![],
             metadata: serde_json::Value::Null,
-        });
+        }).unwrap();
 
         engine.add_entity(AddEntityRequest {
             name: "Test".into(),
@@ -1907,7 +4497,7 @@ mod tests {
             description: None,
             agent_id: None,
             metadata: serde_json::Value::Null,
-        });
+        }).unwrap();
 
         let stats = engine.stats();
         assert_eq!(stats["memories"], 1);
@@ -1923,35 +4513,133 @@ mod tests {
         engine.add_memory(AddMemoryRequest {
             content: "Snapshot test memory".into(),
             memory_type: MemoryType::Fact,
-            agent_id: Some("agent-1".into()),
-            user_id: Some("user-1".into()),
+            agent_id: Some("agent-1".into()),
+            user_id: Some("user-1".into()),
+            session_id: None,
+            tags: vec!["test".into()],
+            metadata: serde_json::Value::Null,
+        }).unwrap();
+
+        engine.add_entity(AddEntityRequest {
+            name: "SnapshotEntity".into(),
+            entity_type: "Test".into(),
+            description: None,
+            agent_id: None,
+            metadata: serde_json::Value::Null,
+        }).unwrap();
+
+        let snapshot = engine.create_snapshot();
+        assert_eq!(snapshot.memories.len(), 1);
+        assert_eq!(snapshot.entities.len(), 1);
+
+        // Restore into a new engine
+        let mut engine2 = MemoryEngine::new(test_config());
+        engine2.restore_from_snapshot(snapshot);
+
+        assert_eq!(engine2.get_memory(1).unwrap().content, "Snapshot test memory");
+        assert_eq!(engine2.find_entity_by_name("SnapshotEntity").unwrap().name, "SnapshotEntity");
+
+        // IDs should continue past the restored state
+        let new_mem = engine2.add_memory(AddMemoryRequest {
+            content: "New memory after restore".into(),
+            memory_type: MemoryType::Fact,
+            agent_id: None,
+            user_id: None,
+            session_id: None,
+            tags: vec![],
+            metadata: serde_json::Value::Null,
+        }).unwrap();
+        assert!(new_mem.id > 1);
+    }
+
+    #[test]
+    fn test_wal_replay_on_top_of_snapshot() {
+        let dir = std::env::temp_dir().join(format!("hivemind-engine-wal-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let wal = Arc::new(WriteAheadLog::open(dir.to_str().unwrap()).unwrap());
+
+        let mut engine = MemoryEngine::new(test_config());
+        engine.set_wal(wal.clone());
+        let kept = engine.add_memory(AddMemoryRequest {
+            content: "Before the snapshot".into(),
+            memory_type: MemoryType::Fact,
+            agent_id: None,
+            user_id: None,
+            session_id: None,
+            tags: vec![],
+            metadata: serde_json::Value::Null,
+        }).unwrap();
+        let snapshot = engine.create_snapshot();
+        // The memory and its history record
+        assert_eq!(snapshot.wal_seq, 2);
+
+        // Writes after the snapshot only exist in the WAL
+        let later = engine.add_memory(AddMemoryRequest {
+            content: "Written after the snapshot".into(),
+            memory_type: MemoryType::Fact,
+            agent_id: None,
+            user_id: None,
+            session_id: None,
+            tags: vec![],
+            metadata: serde_json::Value::Null,
+        }).unwrap();
+        engine.invalidate_memory(kept.id, "superseded", "test").unwrap();
+
+        let mut engine2 = MemoryEngine::new(test_config());
+        let wal_seq = snapshot.wal_seq;
+        engine2.restore_from_snapshot(snapshot);
+        for event in wal.replay(wal_seq).unwrap() {
+            engine2.apply_replication_event(event);
+        }
+
+        assert_eq!(engine2.get_memory(later.id).unwrap().content, "Written after the snapshot");
+        assert!(engine2.get_memory(kept.id).unwrap().valid_until.is_some());
+        let results = engine2.search(&SearchRequest {
+            query: "after snapshot".into(),
+            agent_id: None,
+            user_id: None,
+            tags: vec![],
+            limit: 10,
+            include_graph: false,
+            structured: false,
+        })
+        .unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(engine2.add_memory(AddMemoryRequest {
+            content: "Next".into(),
+            memory_type: MemoryType::Fact,
+            agent_id: None,
+            user_id: None,
             session_id: None,
-            tags: vec!["test".into()],
+            tags: vec![],
             metadata: serde_json::Value::Null,
-        });
+        }).unwrap().id > later.id);
 
-        engine.add_entity(AddEntityRequest {
-            name: "SnapshotEntity".into(),
-            entity_type: "Test".into(),
-            description: None,
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_unlogged_write_is_not_applied() {
+        let dir = std::env::temp_dir().join(format!("hivemind-engine-unlogged-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let wal = Arc::new(WriteAheadLog::open(dir.to_str().unwrap()).unwrap());
+
+        let mut engine = MemoryEngine::new(test_config());
+        engine.set_wal(wal.clone());
+        let memory = engine.add_memory(AddMemoryRequest {
+            content: "Logged".into(),
+            memory_type: MemoryType::Fact,
             agent_id: None,
+            user_id: None,
+            session_id: None,
+            tags: vec![],
             metadata: serde_json::Value::Null,
-        });
-
-        let snapshot = engine.create_snapshot();
-        assert_eq!(snapshot.memories.len(), 1);
-        assert_eq!(snapshot.entities.len(), 1);
-
-        // Restore into a new engine
-        let mut engine2 = MemoryEngine::new(test_config());
-        engine2.restore_from_snapshot(snapshot);
-
-        assert_eq!(engine2.get_memory(1).unwrap().content, "Snapshot test memory");
-        assert_eq!(engine2.find_entity_by_name("SnapshotEntity").unwrap().name, "SnapshotEntity");
+        }).unwrap();
+        let task = engine.create_task(make_task_request("Logged task")).unwrap();
 
-        // IDs should continue past the restored state
-        let new_mem = engine2.add_memory(AddMemoryRequest {
-            content: "New memory after restore".into(),
+        wal.fail_appends();
+        let added = engine.add_memory(AddMemoryRequest {
+            content: "Never logged".into(),
             memory_type: MemoryType::Fact,
             agent_id: None,
             user_id: None,
@@ -1959,7 +4647,61 @@ mod tests {
             tags: vec![],
             metadata: serde_json::Value::Null,
         });
-        assert!(new_mem.id > 1);
+        assert!(matches!(added, Err(WriteError::Unavailable)));
+        assert_eq!(engine.list_memories(None, None, true).len(), 1);
+
+        let update = UpdateMemoryRequest {
+            content: Some("Changed".into()),
+            tags: None,
+            confidence: None,
+            metadata: None,
+        };
+        assert!(matches!(engine.update_memory(memory.id, update, "test"), Err(WriteError::Unavailable)));
+        assert_eq!(engine.get_memory(memory.id).unwrap().content, "Logged");
+        assert_eq!(engine.get_memory_history(memory.id).len(), 1);
+
+        assert!(matches!(engine.claim_task(task.id, "agent-1"), Err(WriteError::Unavailable)));
+        let unchanged = engine.get_task(task.id).unwrap();
+        assert_eq!(unchanged.status, TaskStatus::Pending);
+        assert_eq!(engine.get_task_events(task.id).len(), 1);
+        // Refusals are still reported as such
+        assert!(matches!(engine.start_task(task.id, "agent-1"), Err(WriteError::Rejected(_))));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_replayed_update_drops_stale_vector() {
+        let engine = MemoryEngine::new(test_config());
+        let add = |content: &str| {
+            engine.add_memory(AddMemoryRequest {
+                content: content.into(),
+                memory_type: MemoryType::Fact,
+                agent_id: None,
+                user_id: None,
+                session_id: None,
+                tags: vec![],
+                metadata: serde_json::Value::Null,
+            }).unwrap()
+        };
+        let edited = add("Original wording");
+        let retagged = add("Same wording");
+        engine.embeddings.restore_vectors(embeddings::EmbeddingSnapshot {
+            provider: "test".into(),
+            model: "test".into(),
+            dimensions: 2,
+            vectors: vec![(edited.id, vec![1.0, 0.0]), (retagged.id, vec![0.0, 1.0])],
+        });
+
+        // Only a content change makes the restored vector stale
+        engine.apply_replication_event(ReplicationEvent::MemoryUpdated {
+            memory: Memory { content: "New wording".into(), ..edited.clone() },
+        });
+        engine.apply_replication_event(ReplicationEvent::MemoryUpdated {
+            memory: Memory { tags: vec!["tagged".into()], ..retagged.clone() },
+        });
+        assert!(!engine.embeddings.is_indexed(edited.id));
+        assert!(engine.embeddings.is_indexed(retagged.id));
     }
 
     // ====================================================================
@@ -1976,13 +4718,25 @@ mod tests {
             dependencies: vec![],
             deadline: None,
             metadata: serde_json::Value::Null,
+            parent_id: None,
+            rollup: None,
+            template_id: None,
+        }
+    }
+
+    fn complete_request(agent_id: &str, result: &str) -> CompleteTaskRequest {
+        CompleteTaskRequest {
+            agent_id: agent_id.into(),
+            result: result.into(),
+            artifacts: vec![],
+            store_as_memory: false,
         }
     }
 
     #[test]
     fn test_create_task() {
         let engine = MemoryEngine::new(test_config());
-        let task = engine.create_task(make_task_request("Fix bug"));
+        let task = engine.create_task(make_task_request("Fix bug")).unwrap();
 
         assert_eq!(task.title, "Fix bug");
         assert_eq!(task.status, TaskStatus::Pending);
@@ -1998,7 +4752,7 @@ mod tests {
     #[test]
     fn test_claim_task() {
         let engine = MemoryEngine::new(test_config());
-        let task = engine.create_task(make_task_request("Fix bug"));
+        let task = engine.create_task(make_task_request("Fix bug")).unwrap();
 
         let claimed = engine.claim_task(task.id, "agent-1").unwrap();
         assert_eq!(claimed.status, TaskStatus::Claimed);
@@ -2011,18 +4765,18 @@ mod tests {
     #[test]
     fn test_double_claim_rejected() {
         let engine = MemoryEngine::new(test_config());
-        let task = engine.create_task(make_task_request("Fix bug"));
+        let task = engine.create_task(make_task_request("Fix bug")).unwrap();
 
         engine.claim_task(task.id, "agent-1").unwrap();
         let result = engine.claim_task(task.id, "agent-2");
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("not pending"));
+        assert!(result.unwrap_err().to_string().contains("not pending"));
     }
 
     #[test]
     fn test_start_task() {
         let engine = MemoryEngine::new(test_config());
-        let task = engine.create_task(make_task_request("Fix bug"));
+        let task = engine.create_task(make_task_request("Fix bug")).unwrap();
         engine.claim_task(task.id, "agent-1").unwrap();
 
         let started = engine.start_task(task.id, "agent-1").unwrap();
@@ -2032,7 +4786,7 @@ mod tests {
     #[test]
     fn test_start_task_wrong_agent() {
         let engine = MemoryEngine::new(test_config());
-        let task = engine.create_task(make_task_request("Fix bug"));
+        let task = engine.create_task(make_task_request("Fix bug")).unwrap();
         engine.claim_task(task.id, "agent-1").unwrap();
 
         let result = engine.start_task(task.id, "agent-2");
@@ -2042,11 +4796,11 @@ mod tests {
     #[test]
     fn test_complete_task() {
         let engine = MemoryEngine::new(test_config());
-        let task = engine.create_task(make_task_request("Fix bug"));
+        let task = engine.create_task(make_task_request("Fix bug")).unwrap();
         engine.claim_task(task.id, "agent-1").unwrap();
         engine.start_task(task.id, "agent-1").unwrap();
 
-        let completed = engine.complete_task(task.id, "agent-1", "Bug fixed".into()).unwrap();
+        let (completed, _) = engine.complete_task(task.id, complete_request("agent-1", "Bug fixed")).unwrap();
         assert_eq!(completed.status, TaskStatus::Completed);
         assert_eq!(completed.result.as_deref(), Some("Bug fixed"));
 
@@ -2057,20 +4811,490 @@ mod tests {
     #[test]
     fn test_fail_task() {
         let engine = MemoryEngine::new(test_config());
-        let task = engine.create_task(make_task_request("Fix bug"));
+        let task = engine.create_task(make_task_request("Fix bug")).unwrap();
         engine.claim_task(task.id, "agent-1").unwrap();
 
-        let failed = engine.fail_task(task.id, "agent-1", "Could not reproduce".into()).unwrap();
+        // Only the assigned agent can fail it, and only once
+        assert!(engine.fail_task(task.id, "agent-2", "Not mine".into()).is_err());
+        let (failed, _) = engine.fail_task(task.id, "agent-1", "Could not reproduce".into()).unwrap();
         assert_eq!(failed.status, TaskStatus::Failed);
         assert_eq!(failed.result.as_deref(), Some("Could not reproduce"));
+        assert!(engine.fail_task(task.id, "agent-1", "Again".into()).unwrap_err().to_string().contains("already finished"));
+        assert!(engine.complete_task(task.id, complete_request("agent-1", "Late")).is_err());
+    }
+
+    #[test]
+    fn test_cancel_task() {
+        let engine = MemoryEngine::new(test_config());
+        let task = engine.create_task(make_task_request("Fix bug")).unwrap();
+        engine.claim_task(task.id, "agent-1").unwrap();
+
+        let (cancelled, _) = engine.cancel_task(task.id, "coordinator", Some("Stale".into())).unwrap();
+        assert_eq!(cancelled.status, TaskStatus::Cancelled);
+        let events = engine.get_task_events(task.id);
+        assert_eq!(events.last().unwrap().event_type, TaskEventType::Cancelled);
+        assert_eq!(events.last().unwrap().agent_id.as_deref(), Some("coordinator"));
+
+        // Finished tasks cannot be cancelled again
+        assert!(engine.cancel_task(task.id, "coordinator", None).unwrap_err().to_string().contains("already finished"));
+
+        // The agent that held it can no longer complete or fail it
+        assert_eq!(cancelled.assigned_agent, None);
+        assert!(engine.complete_task(task.id, complete_request("agent-1", "Done anyway")).is_err());
+        assert!(engine.fail_task(task.id, "agent-1", "Gave up".into()).is_err());
+        assert_eq!(engine.get_task(task.id).unwrap().status, TaskStatus::Cancelled);
+    }
+
+    #[test]
+    fn test_task_dependencies() {
+        let engine = MemoryEngine::new(test_config());
+        let with_deps = |title: &str, dependencies: Vec<u64>| {
+            engine.create_task(CreateTaskRequest {
+                dependencies,
+                ..make_task_request(title)
+            })
+        };
+        let build = engine.create_task(make_task_request("Build")).unwrap();
+        let lint = engine.create_task(make_task_request("Lint")).unwrap();
+        let test = with_deps("Test", vec![build.id, build.id]).unwrap();
+        let release = with_deps("Release", vec![test.id, lint.id]).unwrap();
+        assert_eq!(test.dependencies, vec![build.id]);
+        assert_eq!(test.status, TaskStatus::Blocked);
+
+        // Unknown and dead dependencies are rejected
+        assert!(with_deps("Bad", vec![999]).unwrap_err().to_string().contains("not found"));
+        engine.cancel_task(lint.id, "coordinator", None).unwrap();
+        assert!(with_deps("Bad", vec![lint.id]).unwrap_err().to_string().contains("never complete"));
+
+        // Blocked tasks cannot be claimed
+        let err = engine.claim_task(test.id, "agent-1").unwrap_err();
+        assert!(err.to_string().contains("blocked"), "{}", err);
+
+        engine.claim_task(build.id, "agent-1").unwrap();
+        let (_, unblocked) = engine.complete_task(build.id, complete_request("agent-1", "ok")).unwrap();
+        assert_eq!(unblocked.len(), 1);
+        assert_eq!(unblocked[0].id, test.id);
+        assert_eq!(engine.get_task(test.id).unwrap().status, TaskStatus::Pending);
+        assert_eq!(
+            engine.get_task_events(test.id).last().unwrap().event_type,
+            TaskEventType::Unblocked
+        );
+        engine.claim_task(test.id, "agent-1").unwrap();
+
+        let graph = engine.get_task_graph(test.id).unwrap();
+        let ids: Vec<u64> = graph.nodes.iter().map(|n| n.task.id).collect();
+        assert_eq!(ids, vec![build.id, lint.id, test.id, release.id]);
+        assert_eq!(
+            graph.edges,
+            vec![
+                TaskGraphEdge { from: build.id, to: test.id },
+                TaskGraphEdge { from: lint.id, to: release.id },
+                TaskGraphEdge { from: test.id, to: release.id },
+            ]
+        );
+        let release_node = graph.nodes.iter().find(|n| n.task.id == release.id).unwrap();
+        assert_eq!(release_node.blocked_by, vec![test.id, lint.id]);
+        assert!(engine.get_task_graph(999).is_none());
+    }
+
+    #[test]
+    fn test_dependency_cycle_rejected() {
+        let mut config = test_config();
+        config.replication_enabled = true;
+        config.node_number = 1;
+        let engine = MemoryEngine::new(config);
+        let a = engine.create_task(make_task_request("A")).unwrap();
+        // A task replicated from node 2 that depends on the next local ID:
+        // the next local task would close a cycle by depending on it.
+        let next_id = a.id + 1;
+        let remote_id = (2 << crate::ids::COUNTER_BITS) | 1;
+        engine.apply_replication_event(ReplicationEvent::TaskCreated {
+            task: Task {
+                id: remote_id,
+                dependencies: vec![next_id],
+                ..a.clone()
+            },
+        });
+        let err = engine
+            .create_task(CreateTaskRequest { dependencies: vec![remote_id], ..make_task_request("B") })
+            .unwrap_err();
+        assert!(err.to_string().contains("cycle"), "{}", err);
+
+        // Other rejections don't use up IDs
+        assert!(engine
+            .create_task(CreateTaskRequest { dependencies: vec![999], ..make_task_request("C") })
+            .is_err());
+        assert_eq!(engine.create_task(make_task_request("D")).unwrap().id, next_id + 1);
+    }
+
+    #[test]
+    fn test_dependents_of_dead_tasks_are_cancelled() {
+        let engine = MemoryEngine::new(test_config());
+        let with_deps = |title: &str, dependencies: Vec<u64>| {
+            engine
+                .create_task(CreateTaskRequest { dependencies, ..make_task_request(title) })
+                .unwrap()
+        };
+        let build = engine.create_task(make_task_request("Build")).unwrap();
+        let lint = engine.create_task(make_task_request("Lint")).unwrap();
+        let test = with_deps("Test", vec![build.id]);
+        let release = with_deps("Release", vec![test.id, lint.id]);
+
+        engine.claim_task(build.id, "agent-1").unwrap();
+        let (_, follow_ups) = engine.fail_task(build.id, "agent-1", "broken".into()).unwrap();
+        let ids: Vec<(u64, TaskStatus)> = follow_ups.into_iter().map(|t| (t.id, t.status)).collect();
+        assert_eq!(ids, vec![(test.id, TaskStatus::Cancelled), (release.id, TaskStatus::Cancelled)]);
+        let event = engine.get_task_events(release.id).pop().unwrap();
+        assert_eq!(event.event_type, TaskEventType::Cancelled);
+        assert!(event.details.unwrap().contains(&test.id.to_string()));
+        // Tasks that don't depend on it are left alone
+        assert_eq!(engine.get_task(lint.id).unwrap().status, TaskStatus::Pending);
+    }
+
+    #[test]
+    fn test_long_dependency_chain_is_cancelled() {
+        let engine = MemoryEngine::new(test_config());
+        let head = engine.create_task(make_task_request("Head")).unwrap();
+        // Deep enough to overflow the stack if each link recursed
+        let mut previous = head.clone();
+        for _ in 0..50_000 {
+            let task = Task {
+                id: engine.task_ids.next(),
+                status: TaskStatus::Blocked,
+                dependencies: vec![previous.id],
+                ..head.clone()
+            };
+            engine.apply_replication_event(ReplicationEvent::TaskCreated { task: task.clone() });
+            previous = task;
+        }
+
+        let (_, follow_ups) = engine.cancel_task(head.id, "user-1", None).unwrap();
+        assert_eq!(follow_ups.len(), 50_000);
+        assert_eq!(engine.get_task(previous.id).unwrap().status, TaskStatus::Cancelled);
+    }
+
+    #[test]
+    fn test_task_lease_expiry() {
+        let mut config = test_config();
+        config.task_max_attempts = 2;
+        let engine = MemoryEngine::new(config);
+        let expire = |task_id: u64| {
+            engine.tasks.get_mut(&task_id).unwrap().lease_expires_at =
+                Some(Utc::now() - chrono::Duration::seconds(1));
+        };
+        let task = engine.create_task(make_task_request("Fix bug")).unwrap();
+
+        let claimed = engine.claim_task(task.id, "agent-1").unwrap();
+        assert_eq!(claimed.attempts, 1);
+        assert!(claimed.lease_expires_at.unwrap() > Utc::now());
+        assert!(engine.reap_expired_tasks().0.is_empty());
+
+        // A heartbeat from the assignee renews the lease; others are refused
+        expire(task.id);
+        assert!(engine.heartbeat_task(task.id, "agent-2").is_err());
+        engine.heartbeat_task(task.id, "agent-1").unwrap();
+        assert!(engine.reap_expired_tasks().0.is_empty());
+
+        // First expiry requeues the task
+        expire(task.id);
+        let (reaped, _) = engine.reap_expired_tasks();
+        assert_eq!(reaped.len(), 1);
+        assert_eq!(reaped[0].1.as_deref(), Some("agent-1"));
+        let requeued = engine.get_task(task.id).unwrap();
+        assert_eq!(requeued.status, TaskStatus::Pending);
+        assert!(requeued.assigned_agent.is_none() && requeued.lease_expires_at.is_none());
+        assert_eq!(engine.get_task_events(task.id).last().unwrap().event_type, TaskEventType::Reassigned);
+
+        // The second claim is the last attempt
+        engine.claim_task(task.id, "agent-2").unwrap();
+        engine.start_task(task.id, "agent-2").unwrap();
+        expire(task.id);
+        engine.reap_expired_tasks();
+        let failed = engine.get_task(task.id).unwrap();
+        assert_eq!(failed.status, TaskStatus::Failed);
+        assert_eq!(failed.result.as_deref(), Some("Lease of agent-2 expired (attempt 2 of 2)"));
+    }
+
+    #[test]
+    fn test_agent_heartbeat_renews_leases() {
+        let engine = MemoryEngine::new(test_config());
+        let task = engine.create_task(make_task_request("Fix bug")).unwrap();
+        engine.claim_task(task.id, "agent-1").unwrap();
+
+        let almost_expired = Utc::now() + chrono::Duration::seconds(10);
+        engine.tasks.get_mut(&task.id).unwrap().lease_expires_at = Some(almost_expired);
+        engine.heartbeat_agent("agent-1").unwrap();
+        assert!(engine.get_task(task.id).unwrap().lease_expires_at.unwrap() > almost_expired);
+
+        // Leases can be disabled entirely
+        let mut config = test_config();
+        config.task_lease_secs = 0;
+        let engine = MemoryEngine::new(config);
+        let task = engine.create_task(make_task_request("Fix bug")).unwrap();
+        assert!(engine.claim_task(task.id, "agent-1").unwrap().lease_expires_at.is_none());
+    }
+
+    #[test]
+    fn test_claim_next_task() {
+        let engine = MemoryEngine::new(test_config());
+        engine.register_agent(RegisterAgentRequest {
+            agent_id: "writer".into(),
+            name: "Writer".into(),
+            agent_type: "worker".into(),
+            capabilities: vec!["docs".into()],
+            metadata: serde_json::Value::Null,
+        }).unwrap();
+
+        let docs = |title: &str, priority: u32| {
+            let mut req = make_task_request(title);
+            req.required_capabilities = vec!["docs".into()];
+            req.priority = priority;
+            engine.create_task(req).unwrap()
+        };
+        let low = docs("Low", 1);
+        let old_high = docs("Old high", 5);
+        let new_high = docs("New high", 5);
+        let code = engine.create_task(make_task_request("Needs code")).unwrap();
+        let mut blocked = make_task_request("Blocked");
+        blocked.required_capabilities = vec![];
+        blocked.priority = 9;
+        blocked.dependencies = vec![code.id];
+        engine.create_task(blocked).unwrap();
+
+        // Highest priority first, oldest among equals; blocked and incapable skipped
+        assert_eq!(engine.claim_next_task("writer", &[]).unwrap().unwrap().id, old_high.id);
+        assert_eq!(engine.claim_next_task("writer", &[]).unwrap().unwrap().id, new_high.id);
+        assert_eq!(engine.claim_next_task("writer", &[]).unwrap().unwrap().id, low.id);
+        assert!(engine.claim_next_task("writer", &[]).unwrap().is_none());
+
+        // Registered agents can't claim past their capabilities by id either
+        let err = engine.claim_task(code.id, "writer").unwrap_err();
+        assert!(err.to_string().contains("lacks capabilities"));
+
+        // Unregistered agents are matched against the capabilities they declare
+        assert!(engine.claim_next_task("anon", &["docs".into()]).unwrap().is_none());
+        let claimed = engine.claim_next_task("anon", &["code".into()]).unwrap().unwrap();
+        assert_eq!(claimed.id, code.id);
+        assert_eq!(claimed.assigned_agent.as_deref(), Some("anon"));
+    }
+
+    #[tokio::test]
+    async fn test_claim_next_task_long_poll() {
+        let engine = Arc::new(MemoryEngine::new(test_config()));
+        let wait = std::time::Duration::from_millis(50);
+        assert!(engine.claim_next_task_wait("agent-1", &["code".into()], wait).await.unwrap().is_none());
+
+        let waiter = {
+            let engine = engine.clone();
+            tokio::spawn(async move {
+                engine
+                    .claim_next_task_wait("agent-1", &["code".into()], std::time::Duration::from_secs(5))
+                    .await
+            })
+        };
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        let task = engine.create_task(make_task_request("Arrives later")).unwrap();
+
+        let claimed = waiter.await.unwrap().unwrap().unwrap();
+        assert_eq!(claimed.id, task.id);
+        assert_eq!(claimed.status, TaskStatus::Claimed);
+    }
+
+    #[test]
+    fn test_task_deadlines() {
+        let engine = MemoryEngine::new(test_config());
+
+        let mut req = make_task_request("Bad");
+        req.deadline = Some("next tuesday".into());
+        assert!(engine.create_task(req).unwrap_err().to_string().contains("Invalid deadline"));
+        let mut req = make_task_request("Late");
+        req.deadline = Some("2001-01-01T00:00:00Z".into());
+        assert!(engine.create_task(req).unwrap_err().to_string().contains("in the past"));
+
+        let soon = (Utc::now() + chrono::Duration::hours(1)).to_rfc3339();
+        let none = engine.create_task(make_task_request("No deadline")).unwrap();
+        let mut req = make_task_request("Far");
+        req.deadline = Some("2999-12-31".into());
+        let far = engine.create_task(req).unwrap();
+        assert_eq!(far.deadline.unwrap().to_rfc3339(), "2999-12-31T23:59:59+00:00");
+        let mut req = make_task_request("Soon");
+        req.deadline = Some(soon);
+        let soon = engine.create_task(req).unwrap();
+
+        // Equal priority: earliest deadline first, no deadline last
+        let order: Vec<u64> = engine.list_tasks(None, None, None).iter().map(|t| t.id).collect();
+        assert_eq!(order, vec![soon.id, far.id, none.id]);
+        assert_eq!(engine.claim_next_task("agent-1", &["code".into()]).unwrap().unwrap().id, soon.id);
+
+        // Free-form deadlines from before they were typed are dropped on load
+        let mut legacy = serde_json::to_value(&none).unwrap();
+        legacy["deadline"] = "end of sprint".into();
+        assert!(serde_json::from_value::<Task>(legacy.clone()).unwrap().deadline.is_none());
+        legacy["deadline"] = "2030-05-01T12:00:00Z".into();
+        assert!(serde_json::from_value::<Task>(legacy).unwrap().deadline.is_some());
+    }
+
+    /// Create a task and move its deadline into the past.
+    fn create_overdue_task(engine: &MemoryEngine, title: &str, priority: u32) -> Task {
+        let mut req = make_task_request(title);
+        req.priority = priority;
+        req.deadline = Some("2999-01-01".into());
+        let task = engine.create_task(req).unwrap();
+        engine.tasks.get_mut(&task.id).unwrap().deadline = Some(Utc::now() - chrono::Duration::minutes(1));
+        task
+    }
+
+    #[test]
+    fn test_overdue_sweep() {
+        let engine = MemoryEngine::new(test_config());
+        let task = create_overdue_task(&engine, "Report", 1);
+        engine.create_task(make_task_request("On time")).unwrap();
+        assert_eq!(engine.health_details().4.overdue, 1);
+
+        let (flagged, _) = engine.sweep_overdue_tasks();
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].id, task.id);
+        assert_eq!(flagged[0].status, TaskStatus::Pending);
+        assert!(flagged[0].overdue_at.is_some());
+        let events = engine.get_task_events(task.id);
+        assert_eq!(events.last().unwrap().event_type, TaskEventType::Overdue);
+
+        // Flagged once only; still counted until it finishes
+        assert!(engine.sweep_overdue_tasks().0.is_empty());
+        assert_eq!(engine.health_details().4.overdue, 1);
+        engine.cancel_task(task.id, "user-1", None).unwrap();
+        assert_eq!(engine.health_details().4.overdue, 0);
+
+        let mut config = test_config();
+        config.task_overdue_action = OverdueAction::Fail;
+        let engine = MemoryEngine::new(config);
+        let task = create_overdue_task(&engine, "Report", 1);
+        engine.claim_task(task.id, "agent-1").unwrap();
+        let failed = engine.sweep_overdue_tasks().0.remove(0);
+        assert_eq!(failed.status, TaskStatus::Failed);
+        assert!(failed.lease_expires_at.is_none());
+        assert!(failed.result.unwrap().contains("Deadline"));
+
+        let mut config = test_config();
+        config.task_overdue_action = OverdueAction::Escalate;
+        let engine = MemoryEngine::new(config);
+        let mut urgent = make_task_request("Urgent");
+        urgent.priority = 7;
+        engine.create_task(urgent).unwrap();
+        let task = create_overdue_task(&engine, "Report", 1);
+        let escalated = engine.sweep_overdue_tasks().0.remove(0);
+        assert_eq!(escalated.priority, 8);
+        assert_eq!(engine.claim_next_task("agent-1", &["code".into()]).unwrap().unwrap().id, task.id);
+    }
+
+    #[test]
+    fn test_task_progress() {
+        let engine = MemoryEngine::new(test_config());
+        let task = engine.create_task(make_task_request("Migrate")).unwrap();
+        let progress = |agent: &str, percent: Option<u8>| TaskProgressRequest {
+            agent_id: agent.into(),
+            percent,
+            message: Some("copying rows".into()),
+            details: serde_json::json!({ "rows": 1200 }),
+        };
+
+        // Only the assignee of a task in flight can report progress
+        assert!(engine.report_task_progress(task.id, progress("agent-1", Some(10))).is_err());
+        engine.claim_task(task.id, "agent-1").unwrap();
+        assert!(engine.report_task_progress(task.id, progress("agent-2", Some(10))).is_err());
+        assert!(engine.report_task_progress(task.id, progress("agent-1", Some(101))).is_err());
+
+        engine.tasks.get_mut(&task.id).unwrap().lease_expires_at = Some(Utc::now());
+        let updated = engine.report_task_progress(task.id, progress("agent-1", Some(40))).unwrap();
+        assert_eq!(updated.progress, Some(40));
+        assert!(updated.lease_expires_at.unwrap() > Utc::now() + chrono::Duration::seconds(200));
+
+        // A message-only report keeps the last percentage
+        let updated = engine.report_task_progress(task.id, progress("agent-1", None)).unwrap();
+        assert_eq!(updated.progress, Some(40));
+
+        let events = engine.get_task_events(task.id);
+        let reports: Vec<&TaskEvent> = events.iter().filter(|e| e.event_type == TaskEventType::Progress).collect();
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].details.as_deref(), Some("40%: copying rows"));
+        assert_eq!(reports[0].data.as_ref().unwrap()["rows"], 1200);
+    }
+
+    #[test]
+    fn test_complete_task_stores_result_memories() {
+        let engine = MemoryEngine::new(test_config());
+        let task = engine.create_task(make_task_request("Benchmark parser")).unwrap();
+        engine.claim_task(task.id, "agent-1").unwrap();
+
+        let (completed, _) = engine
+            .complete_task(task.id, CompleteTaskRequest {
+                agent_id: "agent-1".into(),
+                result: "Parser is 3x faster".into(),
+                artifacts: vec![TaskArtifact {
+                    name: "flamegraph".into(),
+                    content: "Most time is spent in tokenize".into(),
+                    metadata: serde_json::json!({ "format": "svg" }),
+                }],
+                store_as_memory: true,
+            })
+            .unwrap();
+        assert_eq!(completed.progress, Some(100));
+        assert_eq!(completed.artifacts.len(), 1);
+        assert_eq!(completed.result_memory_ids.len(), 2);
+        assert_eq!(engine.get_task(task.id).unwrap().result_memory_ids, completed.result_memory_ids);
+
+        let artifact = engine.get_memory(completed.result_memory_ids[1]).unwrap();
+        assert!(artifact.content.starts_with("flamegraph from task #"));
+        assert!(artifact.tags.contains(&format!("task:{}", task.id)));
+        assert_eq!(artifact.metadata["task_id"], task.id);
+        assert_eq!(artifact.agent_id.as_deref(), Some("agent-1"));
+
+        // Stored results are recallable by keyword
+        let hits = engine.search(&SearchRequest {
+            query: "parser faster".into(),
+            agent_id: None,
+            user_id: None,
+            tags: vec![],
+            limit: 5,
+            include_graph: false,
+            structured: false,
+        }).unwrap();
+        assert_eq!(hits[0].memory.id, completed.result_memory_ids[0]);
+    }
+
+    #[test]
+    fn test_reassign_task() {
+        let engine = MemoryEngine::new(test_config());
+        let task = engine.create_task(make_task_request("Fix bug")).unwrap();
+
+        // Pending tasks are claimed, not reassigned
+        assert!(engine.reassign_task(task.id, "coordinator", "agent-2", None).is_err());
+
+        engine.claim_task(task.id, "agent-1").unwrap();
+        engine.start_task(task.id, "agent-1").unwrap();
+        let (reassigned, previous) = engine
+            .reassign_task(task.id, "coordinator", "agent-2", Some("agent-1 went offline".into()))
+            .unwrap();
+        assert_eq!(previous.as_deref(), Some("agent-1"));
+        assert_eq!(reassigned.assigned_agent.as_deref(), Some("agent-2"));
+        assert_eq!(reassigned.status, TaskStatus::Claimed);
+
+        let event = engine.get_task_events(task.id).pop().unwrap();
+        assert_eq!(event.event_type, TaskEventType::Reassigned);
+        assert_eq!(event.details.as_deref(), Some("agent-1 -> agent-2: agent-1 went offline"));
+
+        // The old agent can no longer work on it; the new one can
+        assert!(engine.start_task(task.id, "agent-1").is_err());
+        engine.start_task(task.id, "agent-2").unwrap();
+        assert!(engine.reassign_task(task.id, "coordinator", "agent-2", None).is_err());
     }
 
     #[test]
     fn test_list_tasks_with_filters() {
         let engine = MemoryEngine::new(test_config());
-        engine.create_task(make_task_request("Task 1"));
-        let task2 = engine.create_task(make_task_request("Task 2"));
-        engine.create_task(make_task_request("Task 3"));
+        engine.create_task(make_task_request("Task 1")).unwrap();
+        let task2 = engine.create_task(make_task_request("Task 2")).unwrap();
+        engine.create_task(make_task_request("Task 3")).unwrap();
         engine.claim_task(task2.id, "agent-1").unwrap();
 
         // All tasks
@@ -2089,8 +5313,8 @@ mod tests {
     #[test]
     fn test_task_stats() {
         let engine = MemoryEngine::new(test_config());
-        engine.create_task(make_task_request("Task 1"));
-        let task2 = engine.create_task(make_task_request("Task 2"));
+        engine.create_task(make_task_request("Task 1")).unwrap();
+        let task2 = engine.create_task(make_task_request("Task 2")).unwrap();
         engine.claim_task(task2.id, "agent-1").unwrap();
         engine.start_task(task2.id, "agent-1").unwrap();
 
@@ -2103,7 +5327,7 @@ mod tests {
     #[test]
     fn test_task_snapshot_roundtrip() {
         let engine = MemoryEngine::new(test_config());
-        let task = engine.create_task(make_task_request("Snapshot task"));
+        let task = engine.create_task(make_task_request("Snapshot task")).unwrap();
         engine.claim_task(task.id, "agent-1").unwrap();
 
         let snapshot = engine.create_snapshot();
@@ -2118,7 +5342,606 @@ mod tests {
         assert_eq!(restored.status, TaskStatus::Claimed);
 
         // IDs should continue past restored state
-        let new_task = engine2.create_task(make_task_request("New task"));
+        let new_task = engine2.create_task(make_task_request("New task")).unwrap();
         assert!(new_task.id > task.id);
     }
+
+    #[test]
+    fn test_subtask_rollup() {
+        let engine = MemoryEngine::new(test_config());
+        let subtask = |title: &str, parent: &Task| {
+            let task = engine
+                .create_task(CreateTaskRequest {
+                    parent_id: Some(parent.id),
+                    ..make_task_request(title)
+                })
+                .unwrap();
+            engine.claim_task(task.id, "agent-1").unwrap();
+            task
+        };
+        let err = engine
+            .create_task(CreateTaskRequest { parent_id: Some(999), ..make_task_request("Orphan") })
+            .unwrap_err();
+        assert!(err.to_string().contains("not found"));
+
+        // Completes when every subtask completes; cancelled ones don't count
+        let plan = engine.create_task(make_task_request("Plan")).unwrap();
+        let release = engine
+            .create_task(CreateTaskRequest { dependencies: vec![plan.id], ..make_task_request("Release") })
+            .unwrap();
+        let a = subtask("A", &plan);
+        let b = subtask("B", &plan);
+        let c = subtask("C", &plan);
+        assert_eq!(engine.list_subtasks(plan.id).iter().map(|t| t.id).collect::<Vec<_>>(), vec![a.id, b.id, c.id]);
+        let (_, follow_ups) = engine.complete_task(a.id, complete_request("agent-1", "ok")).unwrap();
+        assert!(follow_ups.is_empty());
+        engine.cancel_task(c.id, "coordinator", None).unwrap();
+        let (_, follow_ups) = engine.complete_task(b.id, complete_request("agent-1", "ok")).unwrap();
+        assert_eq!(follow_ups.len(), 2);
+        assert_eq!((follow_ups[0].id, &follow_ups[0].status), (plan.id, &TaskStatus::Completed));
+        assert_eq!((follow_ups[1].id, &follow_ups[1].status), (release.id, &TaskStatus::Pending));
+        let event = engine.get_task_events(plan.id).pop().unwrap();
+        assert_eq!(event.event_type, TaskEventType::Completed);
+        assert!(event.agent_id.is_none());
+        assert!(event.details.unwrap().contains("Rolled up"));
+        assert!(engine.create_task(CreateTaskRequest { parent_id: Some(plan.id), ..make_task_request("Late") }).is_err());
+
+        // A failed subtask fails every ancestor under fail-fast
+        let root = engine.create_task(make_task_request("Root")).unwrap();
+        let middle = engine
+            .create_task(CreateTaskRequest { parent_id: Some(root.id), ..make_task_request("Middle") })
+            .unwrap();
+        let leaf = subtask("Leaf", &middle);
+        let other = subtask("Other", &middle);
+        let (_, follow_ups) = engine.fail_task(leaf.id, "agent-1", "boom".into()).unwrap();
+        let ids: Vec<(u64, TaskStatus)> = follow_ups.into_iter().map(|t| (t.id, t.status)).collect();
+        assert_eq!(ids, vec![(middle.id, TaskStatus::Failed), (root.id, TaskStatus::Failed)]);
+        assert!(engine.get_task(middle.id).unwrap().result.unwrap().contains(&leaf.id.to_string()));
+        // Later subtask results don't change a finished parent
+        let (_, follow_ups) = engine.complete_task(other.id, complete_request("agent-1", "ok")).unwrap();
+        assert!(follow_ups.is_empty());
+
+        // Best effort waits for every subtask and completes regardless
+        let lenient = engine
+            .create_task(CreateTaskRequest { rollup: Some(RollupPolicy::BestEffort), ..make_task_request("Lenient") })
+            .unwrap();
+        let d = subtask("D", &lenient);
+        let e = subtask("E", &lenient);
+        let (_, follow_ups) = engine.fail_task(d.id, "agent-1", "flaky".into()).unwrap();
+        assert!(follow_ups.is_empty());
+        let (_, follow_ups) = engine.complete_task(e.id, complete_request("agent-1", "ok")).unwrap();
+        assert_eq!(follow_ups[0].status, TaskStatus::Completed);
+        assert!(follow_ups[0].result.as_deref().unwrap().contains("1 failed"));
+
+        // Manual parents are left alone
+        let manual = engine
+            .create_task(CreateTaskRequest { rollup: Some(RollupPolicy::Manual), ..make_task_request("Manual") })
+            .unwrap();
+        let f = subtask("F", &manual);
+        let (_, follow_ups) = engine.complete_task(f.id, complete_request("agent-1", "ok")).unwrap();
+        assert!(follow_ups.is_empty());
+        assert_eq!(engine.get_task(manual.id).unwrap().status, TaskStatus::Pending);
+
+        // A finished subtask can't finish again and roll its parent up late
+        let open = engine.create_task(make_task_request("Open")).unwrap();
+        let g = subtask("G", &open);
+        subtask("H", &open);
+        engine.complete_task(g.id, complete_request("agent-1", "ok")).unwrap();
+        assert!(engine.fail_task(g.id, "agent-1", "late".into()).is_err());
+        assert!(engine.cancel_task(g.id, "coordinator", None).is_err());
+        assert_eq!(engine.get_task(open.id).unwrap().status, TaskStatus::Pending);
+    }
+
+    fn make_template_request(title: &str, schedule: TaskSchedule) -> CreateTaskTemplateRequest {
+        CreateTaskTemplateRequest {
+            title: title.into(),
+            description: format!("Recurring {}", title),
+            priority: 2,
+            required_capabilities: vec!["code".into()],
+            created_by: "coordinator".into(),
+            schedule,
+            not_before: None,
+            deadline_secs: None,
+            enabled: true,
+            metadata: serde_json::Value::Null,
+        }
+    }
+
+    #[test]
+    fn test_task_schedules() {
+        let at = |s: &str| chrono::DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        let nightly = TaskSchedule::Cron { expression: "0 2 * * *".into() };
+        assert_eq!(nightly.next_run(at("2026-01-01T12:00:00Z"), false).unwrap(), at("2026-01-02T02:00:00Z"));
+        assert_eq!(nightly.next_run(at("2026-01-02T02:00:00Z"), true).unwrap(), at("2026-01-02T02:00:00Z"));
+        // Missed runs are skipped, not replayed
+        assert_eq!(
+            nightly.advance(at("2026-01-02T02:00:00Z"), at("2026-01-05T09:00:00Z")).unwrap(),
+            at("2026-01-06T02:00:00Z")
+        );
+
+        let hourly = TaskSchedule::Interval { every_secs: 3600 };
+        assert_eq!(hourly.next_run(at("2026-01-01T12:30:00Z"), false).unwrap(), at("2026-01-01T13:30:00Z"));
+        assert_eq!(
+            hourly.advance(at("2026-01-01T12:30:00Z"), at("2026-01-01T15:10:00Z")).unwrap(),
+            at("2026-01-01T15:30:00Z")
+        );
+
+        assert!(TaskSchedule::Cron { expression: "not a cron".into() }.validate().unwrap_err().to_string().contains("Invalid cron"));
+        assert!(TaskSchedule::Interval { every_secs: 0 }.validate().is_err());
+        assert!(TaskSchedule::Interval { every_secs: u64::MAX }.validate().is_err());
+
+        // Out-of-range intervals are errors, not panics
+        let huge = TaskSchedule::Interval { every_secs: u64::MAX };
+        assert!(huge.next_run(Utc::now(), false).is_err());
+        assert!(huge.advance(Utc::now() - chrono::Duration::seconds(5), Utc::now()).is_err());
+    }
+
+    #[test]
+    fn test_lowest_live_node_runs_maintenance() {
+        let liveness = Duration::from_secs(NODE_LIVENESS_SECS);
+        let node = |node_number| {
+            let mut engine = MemoryEngine::new(HiveMindConfig {
+                replication_enabled: true,
+                node_number,
+                ..test_config()
+            });
+            engine.started = Instant::now().checked_sub(liveness).unwrap();
+            engine
+        };
+        let (a, b) = (node(1), node(2));
+        let schedule = TaskSchedule::Interval { every_secs: 3600 };
+        let due_at = Utc::now() - chrono::Duration::seconds(5);
+
+        // A template created on node 1, and one restored from a snapshot
+        // taken before replication was enabled (node 0's ID range)
+        let mut owned = a
+            .create_task_template(make_template_request("From a", schedule.clone()))
+            .unwrap();
+        owned.next_run_at = Some(due_at);
+        let owned_id = owned.id;
+        let unowned = TaskTemplate { id: 7, ..owned.clone() };
+        b.apply_replication_event(ReplicationEvent::TaskTemplateCreated { template: owned.clone() });
+        b.apply_replication_event(ReplicationEvent::TaskTemplateCreated { template: unowned });
+
+        // A node that only just started waits to hear from the others
+        let fresh = MemoryEngine::new(HiveMindConfig {
+            replication_enabled: true,
+            node_number: 2,
+            ..test_config()
+        });
+        fresh.apply_replication_event(ReplicationEvent::TaskTemplateCreated { template: owned });
+        assert!(fresh.run_due_templates().is_empty());
+
+        // Node 1 is alive, so node 2 leaves the sweeps to it
+        b.apply_replication_event(ReplicationEvent::NodeHeartbeat { node_number: 1 });
+        assert!(b.run_due_templates().is_empty());
+        // Heartbeats from higher-numbered nodes don't matter
+        b.apply_replication_event(ReplicationEvent::NodeHeartbeat { node_number: 3 });
+        assert!(b.run_due_templates().is_empty());
+
+        // Node 1 went away: node 2 takes over every template, whichever
+        // node's range its ID came from
+        b.live_nodes.insert(1, Instant::now().checked_sub(liveness).unwrap());
+        let mut created: Vec<u64> = b.run_due_templates().iter().filter_map(|t| t.template_id).collect();
+        created.sort();
+        assert_eq!(created, vec![7, owned_id]);
+    }
+
+    #[test]
+    fn test_task_templates() {
+        let engine = MemoryEngine::new(test_config());
+        let schedule = TaskSchedule::Interval { every_secs: 3600 };
+        let bad = CreateTaskTemplateRequest {
+            schedule: TaskSchedule::Cron { expression: "61 * * * *".into() },
+            ..make_template_request("Bad", schedule.clone())
+        };
+        assert!(engine.create_task_template(bad).is_err());
+        let unbounded = CreateTaskTemplateRequest {
+            deadline_secs: Some(u64::MAX),
+            ..make_template_request("Unbounded", schedule.clone())
+        };
+        assert!(engine.create_task_template(unbounded).is_err());
+
+        // Without not_before the first run is one interval away
+        let mut req = make_template_request("Re-check CI", schedule.clone());
+        req.deadline_secs = Some(600);
+        let template = engine.create_task_template(req).unwrap();
+        let first_run = template.next_run_at.unwrap();
+        assert!(first_run > Utc::now() + chrono::Duration::minutes(59));
+        assert!(engine.run_due_templates().is_empty());
+
+        // A future not_before is the first run
+        let start = Utc::now() + chrono::Duration::days(1);
+        let later = engine
+            .create_task_template(CreateTaskTemplateRequest {
+                not_before: Some(start),
+                ..make_template_request("Consolidate", schedule)
+            })
+            .unwrap();
+        assert_eq!(later.next_run_at, Some(start));
+
+        // Make the first template due
+        let due_at = Utc::now() - chrono::Duration::seconds(5);
+        engine.task_templates.get_mut(&template.id).unwrap().next_run_at = Some(due_at);
+        let created = engine.run_due_templates();
+        assert_eq!(created.len(), 1);
+        let task = &created[0];
+        assert_eq!(task.title, "Re-check CI");
+        assert_eq!(task.template_id, Some(template.id));
+        assert_eq!(task.status, TaskStatus::Pending);
+        assert!(task.deadline.is_some());
+        assert_eq!(engine.get_task(task.id).unwrap().template_id, Some(template.id));
+
+        let template = engine.get_task_template(template.id).unwrap();
+        assert_eq!(template.run_count, 1);
+        assert_eq!(template.last_task_id, Some(task.id));
+        assert_eq!(template.next_run_at, Some(due_at + chrono::Duration::hours(1)));
+        assert!(engine.run_due_templates().is_empty());
+
+        // Paused templates don't run; resuming reschedules from now
+        let paused = engine
+            .update_task_template(template.id, UpdateTaskTemplateRequest {
+                enabled: Some(false),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(paused.next_run_at.is_none());
+        let resumed = engine
+            .update_task_template(template.id, UpdateTaskTemplateRequest {
+                enabled: Some(true),
+                priority: Some(5),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(resumed.priority, 5);
+        assert!(resumed.next_run_at.unwrap() > Utc::now());
+
+        // Templates survive a snapshot round trip
+        let snapshot = engine.create_snapshot();
+        assert_eq!(snapshot.task_templates.len(), 2);
+        let mut engine2 = MemoryEngine::new(test_config());
+        engine2.restore_from_snapshot(snapshot);
+        assert_eq!(engine2.list_task_templates().len(), 2);
+        let next = engine2
+            .create_task_template(make_template_request("Next", TaskSchedule::Interval { every_secs: 60 }))
+            .unwrap();
+        assert!(next.id > later.id);
+
+        assert!(engine.delete_task_template(later.id).unwrap().is_some());
+        assert!(engine.get_task_template(later.id).is_none());
+        assert!(engine.delete_task_template(later.id).unwrap().is_none());
+    }
+
+    /// Public `&self` methods of the engine and channel hub that change
+    /// replicated state, with the event each must emit.
+    const REPLICATED_METHODS: &[(&str, &str)] = &[
+        ("add_memory", "memory_added"),
+        ("add_memories_bulk", "memory_added"),
+        ("update_memory", "memory_updated"),
+        ("invalidate_memory", "memory_invalidated"),
+        ("start_session", "session_started"),
+        ("end_session", "episode_recorded"),
+        ("add_entity", "entity_added"),
+        ("update_entity", "entity_updated"),
+        ("link_memory_entity", "mention_added"),
+        ("add_relationship", "relationship_added"),
+        ("invalidate_relationship", "relationship_invalidated"),
+        ("register_agent", "agent_registered"),
+        ("heartbeat_agent", "agent_updated"),
+        ("create_task_template", "task_template_created"),
+        ("update_task_template", "task_template_updated"),
+        ("delete_task_template", "task_template_deleted"),
+        ("run_due_templates", "task_created"),
+        ("create_task", "task_created"),
+        ("claim_task", "task_claimed"),
+        ("claim_next_task", "task_claimed"),
+        ("claim_next_task_wait", "task_claimed"),
+        ("start_task", "task_started"),
+        ("report_task_progress", "task_updated"),
+        ("heartbeat_task", "task_updated"),
+        ("complete_task", "task_completed"),
+        ("fail_task", "task_failed"),
+        ("cancel_task", "task_cancelled"),
+        ("reassign_task", "task_reassigned"),
+        ("reap_expired_tasks", "task_reassigned"),
+        ("sweep_overdue_tasks", "task_updated"),
+        ("run_benchmark", "benchmark_data_removed"),
+        ("create_channel", "channel_created"),
+        ("subscribe", "channel_subscribed"),
+        ("subscribe_by_name", "channel_subscribed"),
+    ];
+
+    /// Methods that only read, touch node-local state (WebSocket counters,
+    /// the derived embedding index, node liveness), apply events that were already
+    /// replicated, or change state solely through the methods above.
+    const UNREPLICATED_METHODS: &[&str] = &[
+        // MemoryEngine
+        "replication_status", "replication_events_after", "replication_sink", "config", "embeddings", "create_snapshot",
+        "spawn_embedding_backfill", "start_embedding_reindex", "apply_inbound_event",
+        "apply_replication_event", "get_memory", "get_memory_history", "search", "search_hybrid",
+        "list_memories", "extract_and_store", "list_sessions", "get_episode", "list_episodes",
+        "search_episodes", "get_entity", "find_entity_by_name", "get_memory_mentions",
+        "get_memory_entities", "get_entity_memories", "get_entity_relationships", "traverse",
+        "get_agent", "list_agents", "get_task_template", "list_task_templates", "get_task",
+        "list_subtasks", "list_tasks", "get_task_graph", "get_task_events", "health_details",
+        "embedding_info", "stats", "send_node_heartbeat", "record_node_heartbeat", "commit_inbound",
+        "peer_seq",
+        // ChannelHub
+        "restore_from_snapshot", "fill_snapshot", "apply_channel_created", "apply_channel_subscribed",
+        "ws_connect", "ws_disconnect", "active_ws_count", "get_channel", "get_channel_by_name",
+        "list_channels", "broadcast_to_channel", "broadcast_to_channel_by_name", "get_subscribers",
+    ];
+
+    /// Names of the `pub`/`pub(crate)` methods taking `&self` in a source file.
+    fn self_methods(source: &str) -> Vec<String> {
+        let code = source.split("#[cfg(test)]").next().unwrap();
+        let mut names = Vec::new();
+        for (start, _) in code.match_indices("fn ") {
+            let prefix = code[..start].trim_end_matches("async ").trim_end();
+            if !(prefix.ends_with("pub") || prefix.ends_with("pub(crate)")) {
+                continue;
+            }
+            let rest = &code[start + 3..];
+            let signature = &rest[..rest.find(['{', ';']).unwrap_or(rest.len())];
+            if signature.contains("&self") {
+                let end = signature.find(['(', '<']).unwrap();
+                names.push(signature[..end].to_string());
+            }
+        }
+        names
+    }
+
+    #[test]
+    fn test_every_method_is_classified() {
+        for source in [include_str!("memory_engine.rs"), include_str!("channels.rs")] {
+            for name in self_methods(source) {
+                assert!(
+                    REPLICATED_METHODS.iter().any(|(m, _)| *m == name) || UNREPLICATED_METHODS.contains(&name.as_str()),
+                    "`{}` is not classified: if it changes state, emit a ReplicationEvent and add it to \
+                     REPLICATED_METHODS (and exercise it in test_every_mutation_is_replicated), \
+                     otherwise add it to UNREPLICATED_METHODS",
+                    name
+                );
+            }
+        }
+    }
+
+    /// Comparable summary of everything a follower should end up with.
+    fn replicated_state(engine: &MemoryEngine, hub: &crate::channels::ChannelHub) -> serde_json::Value {
+        let mut s = engine.create_snapshot();
+        hub.fill_snapshot(&mut s);
+        let mut memories: Vec<_> = s.memories.iter().map(|m| (m.id, m.content.clone(), m.valid_until)).collect();
+        let mut history: Vec<_> = s.history.iter().flat_map(|(_, h)| h.iter().map(|h| h.id)).collect();
+        let mut entities: Vec<_> = s.entities.iter().map(|e| (e.id, e.description.clone())).collect();
+        let mut relationships: Vec<_> = s.relationships.iter().map(|r| (r.id, r.valid_until)).collect();
+        let mut agents: Vec<_> = s.agents.iter().map(|a| (a.agent_id.clone(), a.last_seen)).collect();
+        let mut tasks: Vec<_> = s.tasks.iter().map(|t| (t.id, t.status.clone(), t.assigned_agent.clone(), t.overdue_at)).collect();
+        let mut task_events: Vec<_> = s.task_events.iter().flat_map(|(_, e)| e.iter().map(|e| e.id)).collect();
+        let mut templates: Vec<_> = s.task_templates.iter().map(|t| (t.id, t.enabled, t.run_count)).collect();
+        let mut subscriptions = s.channel_subscriptions.clone();
+        memories.sort_by_key(|m| m.0);
+        history.sort();
+        entities.sort();
+        relationships.sort();
+        agents.sort();
+        tasks.sort_by_key(|t| t.0);
+        task_events.sort();
+        templates.sort();
+        subscriptions.sort();
+        serde_json::json!({
+            "memories": memories,
+            "history": history,
+            "entities": entities,
+            "relationships": relationships,
+            "mentions": s.mentions.len(),
+            "sessions": s.sessions.len(),
+            "episodes": s.episodes.len(),
+            "agents": agents,
+            "tasks": tasks,
+            "task_events": task_events,
+            "templates": templates,
+            "channels": s.channels.len(),
+            "subscriptions": subscriptions,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_every_mutation_is_replicated() {
+        use crate::channels::ChannelHub;
+
+        let dir = std::env::temp_dir().join(format!("hivemind-engine-repl-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let wal = Arc::new(WriteAheadLog::open(dir.to_str().unwrap()).unwrap());
+        let mut engine = MemoryEngine::new(test_config());
+        engine.set_wal(wal.clone());
+        let mut hub = ChannelHub::new();
+        hub.set_replication(engine.replication_sink());
+
+        let mut covered = std::collections::HashSet::new();
+        let mut seen = 0;
+        let mut expect = |method: &'static str| {
+            let expected = REPLICATED_METHODS.iter().find(|(m, _)| *m == method).unwrap().1;
+            let types: Vec<String> = wal
+                .replay(seen)
+                .unwrap()
+                .iter()
+                .map(|e| serde_json::to_value(e).unwrap()["type"].as_str().unwrap().to_string())
+                .collect();
+            seen = wal.last_seq();
+            assert!(types.iter().any(|t| t == expected), "{} emitted {:?}, expected {}", method, types, expected);
+            covered.insert(method);
+        };
+        let memory_request = |content: &str| AddMemoryRequest {
+            content: content.into(),
+            memory_type: MemoryType::Fact,
+            agent_id: Some("agent-1".into()),
+            user_id: None,
+            session_id: Some("s1".into()),
+            tags: vec![],
+            metadata: serde_json::Value::Null,
+        };
+
+        // Memories, sessions and the graph
+        let memory = engine.add_memory(memory_request("Alice maintains the parser")).unwrap();
+        expect("add_memory");
+        engine.add_memories_bulk(vec![memory_request("Bob reviews the parser")]).unwrap();
+        expect("add_memories_bulk");
+        let update = UpdateMemoryRequest { content: Some("Alice owns the parser".into()), tags: None, confidence: None, metadata: None };
+        engine.update_memory(memory.id, update, "test").unwrap();
+        expect("update_memory");
+        engine.start_session(StartSessionRequest {
+            agent_id: "agent-1".into(),
+            user_id: None,
+            session_id: Some("s1".into()),
+            metadata: serde_json::Value::Null,
+        }).unwrap();
+        expect("start_session");
+        engine.end_session("s1", EndSessionRequest::default()).await.unwrap();
+        expect("end_session");
+        let entity_request = |name: &str| AddEntityRequest {
+            name: name.into(),
+            entity_type: "person".into(),
+            description: None,
+            agent_id: None,
+            metadata: serde_json::Value::Null,
+        };
+        let alice = engine.add_entity(entity_request("Alice")).unwrap();
+        expect("add_entity");
+        let bob = engine.add_entity(entity_request("Bob")).unwrap();
+        let described = UpdateEntityRequest { description: Some("Parser owner".into()), ..Default::default() };
+        engine.update_entity(alice.id, described).unwrap();
+        expect("update_entity");
+        // Alice was linked by name when the entity was added
+        engine.link_memory_entity(memory.id, bob.id, MentionSource::Extraction).unwrap();
+        expect("link_memory_entity");
+        let rel = engine.add_relationship(AddRelationshipRequest {
+            source_entity_id: alice.id,
+            target_entity_id: bob.id,
+            relation_type: "works_with".into(),
+            description: None,
+            weight: 1.0,
+            created_by: "test".into(),
+            metadata: serde_json::Value::Null,
+        }).unwrap();
+        expect("add_relationship");
+        engine.invalidate_relationship(rel.id).unwrap();
+        expect("invalidate_relationship");
+        engine.invalidate_memory(memory.id, "superseded", "test").unwrap();
+        expect("invalidate_memory");
+
+        // Agents and templates
+        engine.register_agent(RegisterAgentRequest {
+            agent_id: "agent-1".into(),
+            name: "Agent 1".into(),
+            agent_type: "coder".into(),
+            capabilities: vec!["code".into()],
+            metadata: serde_json::Value::Null,
+        }).unwrap();
+        expect("register_agent");
+        // Heartbeats only refresh a stale `last_seen`
+        engine.agents.get_mut("agent-1").unwrap().last_seen -= chrono::Duration::seconds(AGENT_SEEN_RESOLUTION_SECS);
+        engine.heartbeat_agent("agent-1").unwrap();
+        expect("heartbeat_agent");
+        let last_seq = wal.last_seq();
+        engine.heartbeat_agent("agent-1").unwrap();
+        assert_eq!(wal.last_seq(), last_seq);
+        let template = engine
+            .create_task_template(make_template_request("Nightly", TaskSchedule::Interval { every_secs: 3600 }))
+            .unwrap();
+        expect("create_task_template");
+        engine.task_templates.get_mut(&template.id).unwrap().next_run_at = Some(Utc::now() - chrono::Duration::seconds(1));
+        let templated = engine.run_due_templates();
+        expect("run_due_templates");
+        let pause = UpdateTaskTemplateRequest { enabled: Some(false), ..Default::default() };
+        engine.update_task_template(template.id, pause).unwrap();
+        expect("update_task_template");
+        engine.delete_task_template(template.id).unwrap();
+        expect("delete_task_template");
+
+        // The task lifecycle
+        engine.cancel_task(templated[0].id, "coordinator", None).unwrap();
+        expect("cancel_task");
+        let a = engine.create_task(make_task_request("A")).unwrap();
+        expect("create_task");
+        engine.claim_task(a.id, "agent-1").unwrap();
+        expect("claim_task");
+        engine.start_task(a.id, "agent-1").unwrap();
+        expect("start_task");
+        let progress = TaskProgressRequest { agent_id: "agent-1".into(), percent: Some(50), message: None, details: serde_json::Value::Null };
+        engine.report_task_progress(a.id, progress).unwrap();
+        expect("report_task_progress");
+        engine.heartbeat_task(a.id, "agent-1").unwrap();
+        expect("heartbeat_task");
+        engine.complete_task(a.id, complete_request("agent-1", "done")).unwrap();
+        expect("complete_task");
+
+        let b = engine.create_task(make_task_request("B")).unwrap();
+        assert_eq!(engine.claim_next_task("agent-1", &[]).unwrap().unwrap().id, b.id);
+        expect("claim_next_task");
+        engine.fail_task(b.id, "agent-1", "broken".into()).unwrap();
+        expect("fail_task");
+
+        let c = engine.create_task(make_task_request("C")).unwrap();
+        engine.claim_task(c.id, "agent-1").unwrap();
+        engine.reassign_task(c.id, "agent-1", "agent-2", None).unwrap();
+        expect("reassign_task");
+
+        let d = engine.create_task(make_task_request("D")).unwrap();
+        engine.cancel_task(c.id, "coordinator", None).unwrap();
+        let waited = engine.claim_next_task_wait("agent-1", &[], std::time::Duration::ZERO).await.unwrap();
+        assert_eq!(waited.unwrap().id, d.id);
+        expect("claim_next_task_wait");
+        engine.tasks.get_mut(&d.id).unwrap().lease_expires_at = Some(Utc::now() - chrono::Duration::seconds(1));
+        engine.reap_expired_tasks();
+        expect("reap_expired_tasks");
+        engine.tasks.get_mut(&d.id).unwrap().deadline = Some(Utc::now() - chrono::Duration::seconds(1));
+        engine.sweep_overdue_tasks();
+        expect("sweep_overdue_tasks");
+
+        engine
+            .run_benchmark(&BenchmarkRequest {
+                operations: vec!["write".into()],
+                iterations: 2,
+                concurrency: 1,
+                cleanup: true,
+            })
+            .await;
+        expect("run_benchmark");
+
+        // Channels
+        let channel = hub.create_channel(CreateChannelRequest {
+            name: "team".into(),
+            description: None,
+            channel_type: ChannelType::Public,
+            created_by: "agent-1".into(),
+        }).unwrap();
+        expect("create_channel");
+        hub.subscribe(channel.id, "agent-1").unwrap();
+        expect("subscribe");
+        hub.subscribe_by_name("team", "agent-2").unwrap();
+        expect("subscribe_by_name");
+        // Reconnecting clients resubscribe; that isn't a new subscription
+        let last_seq = wal.last_seq();
+        hub.subscribe_by_name("team", "agent-2").unwrap();
+        assert_eq!(wal.last_seq(), last_seq);
+
+        let all: std::collections::HashSet<&str> = REPLICATED_METHODS.iter().map(|(m, _)| *m).collect();
+        assert_eq!(covered, all, "every replicated method must be exercised above");
+
+        // A follower applying the stream ends up with the same state
+        let follower = MemoryEngine::new(test_config());
+        let follower_hub = ChannelHub::new();
+        for event in wal.replay(0).unwrap() {
+            match event {
+                ReplicationEvent::ChannelCreated { channel } => follower_hub.apply_channel_created(channel),
+                ReplicationEvent::ChannelSubscribed { channel_id, agent_id } => {
+                    follower_hub.apply_channel_subscribed(channel_id, &agent_id)
+                }
+                other => follower.apply_replication_event(other),
+            }
+        }
+        assert_eq!(replicated_state(&follower, &follower_hub), replicated_state(&engine, &hub));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
@@ -1,16 +1,24 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use tokio::sync::watch;
 use tracing::{debug, error, info, warn};
 
+use crate::embeddings::EmbeddingSnapshot;
 use crate::types::*;
 
 /// Persistence layer for HiveMindDB.
 ///
-/// Provides two mechanisms:
+/// Provides four mechanisms:
 /// 1. **Snapshot persistence**: Periodic JSON snapshots to disk for restart recovery
-/// 2. **RaftTimeDB replication**: Forward writes to RaftTimeDB via WebSocket for
+/// 2. **Write-ahead log**: Every mutation is appended and fsync'd before it is
+///    acknowledged, and replayed on top of the snapshot at startup
+/// 3. **RaftTimeDB replication**: Forward writes to RaftTimeDB via WebSocket for
 ///    cross-node replication through Raft consensus
+/// 4. **Catch-up bootstrap**: A new or lagging node loads a peer's (or
+///    RaftTimeDB's) snapshot plus the events after it before serving
 ///
 /// The in-memory DashMap stores remain the source of truth for reads.
 /// Persistence is async and best-effort — the system works without a running
@@ -25,53 +33,198 @@ pub struct Snapshot {
     pub entities: Vec<Entity>,
     pub relationships: Vec<Relationship>,
     pub episodes: Vec<Episode>,
+    /// Sessions started but not yet ended.
+    #[serde(default)]
+    pub sessions: Vec<Session>,
     pub agents: Vec<Agent>,
     pub history: Vec<(u64, Vec<MemoryHistory>)>,
     pub channels: Vec<Channel>,
+    /// Durable channel subscriptions: channel_id → agent_ids.
+    #[serde(default)]
+    pub channel_subscriptions: Vec<(u64, Vec<String>)>,
     #[serde(default)]
     pub tasks: Vec<Task>,
     #[serde(default)]
     pub task_events: Vec<(u64, Vec<TaskEvent>)>,
+    /// Recurring task templates.
+    #[serde(default)]
+    pub task_templates: Vec<TaskTemplate>,
+    /// Memory → entity mention links.
+    #[serde(default)]
+    pub mentions: Vec<EntityMention>,
+    /// Persisted vector index (absent in older snapshots or without embeddings).
+    #[serde(default)]
+    pub embeddings: Option<EmbeddingSnapshot>,
+    /// Last write-ahead log sequence number reflected in this snapshot.
+    #[serde(default)]
+    pub wal_seq: u64,
+    /// Highest outbox sequence number applied per origin node, so events a
+    /// peer resends after this node restarts are still recognised.
+    #[serde(default)]
+    pub peer_seqs: Vec<(String, u64)>,
 }
 
 impl Snapshot {
-    pub const CURRENT_VERSION: u32 = 2;
+    pub const CURRENT_VERSION: u32 = 9;
+}
+
+/// On-disk encoding for snapshots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotFormat {
+    /// Versioned, deflate-compressed MessagePack with a trailing CRC32.
+    Binary,
+    /// Pretty-printed JSON (human-readable export format).
+    Json,
+}
+
+impl SnapshotFormat {
+    fn file_name(self) -> &'static str {
+        match self {
+            SnapshotFormat::Binary => "hivemind-snapshot.bin",
+            SnapshotFormat::Json => "hivemind-snapshot.json",
+        }
+    }
+}
+
+impl std::str::FromStr for SnapshotFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "binary" | "bin" => Ok(SnapshotFormat::Binary),
+            "json" => Ok(SnapshotFormat::Json),
+            other => Err(format!("Unknown snapshot format '{}' (expected binary or json)", other)),
+        }
+    }
+}
+
+/// Binary snapshot layout:
+///
+/// ```text
+/// magic (8) | format version (u16 LE) | payload length (u64 LE) | payload | crc32 (u32 LE)
+/// ```
+///
+/// The payload is deflate-compressed MessagePack; the CRC covers everything before it.
+const BINARY_MAGIC: &[u8; 8] = b"HMDBSNAP";
+const BINARY_FORMAT_VERSION: u16 = 1;
+const BINARY_HEADER_LEN: usize = 8 + 2 + 8;
+
+/// Encode a snapshot in the given format.
+pub fn encode_snapshot(snapshot: &Snapshot, format: SnapshotFormat) -> anyhow::Result<Vec<u8>> {
+    match format {
+        SnapshotFormat::Json => Ok(serde_json::to_vec_pretty(snapshot)?),
+        SnapshotFormat::Binary => {
+            let packed = rmp_serde::to_vec_named(snapshot)?;
+            let mut encoder =
+                flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(&packed)?;
+            let payload = encoder.finish()?;
+
+            let mut out = Vec::with_capacity(BINARY_HEADER_LEN + payload.len() + 4);
+            out.extend_from_slice(BINARY_MAGIC);
+            out.extend_from_slice(&BINARY_FORMAT_VERSION.to_le_bytes());
+            out.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+            out.extend_from_slice(&payload);
+            let crc = crc32fast::hash(&out);
+            out.extend_from_slice(&crc.to_le_bytes());
+            Ok(out)
+        }
+    }
+}
+
+/// Decode a snapshot, detecting the format from its contents.
+///
+/// Binary snapshots are rejected if the checksum or length does not match.
+pub fn decode_snapshot(bytes: &[u8]) -> anyhow::Result<Snapshot> {
+    if !bytes.starts_with(BINARY_MAGIC) {
+        return Ok(serde_json::from_slice(bytes)?);
+    }
+
+    if bytes.len() < BINARY_HEADER_LEN + 4 {
+        anyhow::bail!("Binary snapshot truncated ({} bytes)", bytes.len());
+    }
+    let version = u16::from_le_bytes([bytes[8], bytes[9]]);
+    if version != BINARY_FORMAT_VERSION {
+        anyhow::bail!("Unsupported binary snapshot format version {}", version);
+    }
+    // The length comes from the file, so it may be anything
+    let payload_len = u64::from_le_bytes(bytes[10..18].try_into()?);
+    let expected_len = usize::try_from(payload_len)
+        .ok()
+        .and_then(|len| len.checked_add(BINARY_HEADER_LEN + 4))
+        .ok_or_else(|| anyhow::anyhow!("Binary snapshot payload length {} is out of range", payload_len))?;
+    if bytes.len() != expected_len {
+        anyhow::bail!(
+            "Binary snapshot length mismatch (expected {} bytes, found {})",
+            expected_len,
+            bytes.len()
+        );
+    }
+
+    let (body, trailer) = bytes.split_at(expected_len - 4);
+    let stored_crc = u32::from_le_bytes(trailer.try_into()?);
+    let actual_crc = crc32fast::hash(body);
+    if stored_crc != actual_crc {
+        anyhow::bail!(
+            "Binary snapshot checksum mismatch (stored {:08x}, computed {:08x})",
+            stored_crc,
+            actual_crc
+        );
+    }
+
+    let mut decoder = flate2::read::DeflateDecoder::new(&body[BINARY_HEADER_LEN..]);
+    let mut packed = Vec::new();
+    std::io::Read::read_to_end(&mut decoder, &mut packed)?;
+    Ok(rmp_serde::from_slice(&packed)?)
 }
 
 /// Manages snapshot persistence to disk.
 pub struct SnapshotManager {
     data_dir: PathBuf,
+    format: SnapshotFormat,
 }
 
 impl SnapshotManager {
     pub fn new(data_dir: &str) -> Self {
         Self {
             data_dir: PathBuf::from(data_dir),
+            format: SnapshotFormat::Binary,
         }
     }
 
-    fn snapshot_path(&self) -> PathBuf {
-        self.data_dir.join("hivemind-snapshot.json")
+    /// Use a different encoding for saved snapshots (loading detects either).
+    pub fn with_format(mut self, format: SnapshotFormat) -> Self {
+        self.format = format;
+        self
     }
 
-    fn snapshot_tmp_path(&self) -> PathBuf {
-        self.data_dir.join("hivemind-snapshot.json.tmp")
+    fn snapshot_path(&self, format: SnapshotFormat) -> PathBuf {
+        self.data_dir.join(format.file_name())
     }
 
     /// Save a snapshot to disk (atomic write via temp file + rename).
+    ///
+    /// The file and the rename are fsync'd before returning, since callers
+    /// truncate the write-ahead log right after.
     pub async fn save(&self, snapshot: &Snapshot) -> anyhow::Result<()> {
         // Ensure data dir exists
         tokio::fs::create_dir_all(&self.data_dir).await?;
 
-        let json = serde_json::to_string_pretty(snapshot)?;
-        let tmp = self.snapshot_tmp_path();
-        let target = self.snapshot_path();
+        let bytes = encode_snapshot(snapshot, self.format)?;
+        let target = self.snapshot_path(self.format);
+        let tmp = self.data_dir.join(format!("{}.tmp", self.format.file_name()));
 
-        tokio::fs::write(&tmp, json).await?;
+        let mut file = tokio::fs::File::create(&tmp).await?;
+        tokio::io::AsyncWriteExt::write_all(&mut file, &bytes).await?;
+        file.sync_all().await?;
+        drop(file);
         tokio::fs::rename(&tmp, &target).await?;
+        tokio::fs::File::open(&self.data_dir).await?.sync_all().await?;
 
         info!(
             path = %target.display(),
+            format = ?self.format,
+            bytes = bytes.len(),
             memories = snapshot.memories.len(),
             entities = snapshot.entities.len(),
             "Snapshot saved"
@@ -79,24 +232,964 @@ impl SnapshotManager {
         Ok(())
     }
 
-    /// Load a snapshot from disk.
+    /// Load the most recent snapshot from disk, in whichever format it was saved.
+    ///
+    /// If the newest file can't be read or decoded, the snapshot in the other
+    /// format is tried before giving up. That one may be older than the
+    /// write-ahead log's truncation point, which [`WriteAheadLog::replay`]
+    /// refuses.
     pub async fn load(&self) -> anyhow::Result<Option<Snapshot>> {
-        let path = self.snapshot_path();
-        if !path.exists() {
-            debug!(path = %path.display(), "No snapshot found");
+        let mut candidates = Vec::new();
+        for format in [SnapshotFormat::Binary, SnapshotFormat::Json] {
+            let path = self.snapshot_path(format);
+            if let Ok(meta) = tokio::fs::metadata(&path).await {
+                let modified = meta.modified().unwrap_or(std::time::UNIX_EPOCH);
+                candidates.push((modified, path));
+            }
+        }
+        if candidates.is_empty() {
+            debug!(dir = %self.data_dir.display(), "No snapshot found");
             return Ok(None);
         }
+        candidates.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
 
-        let json = tokio::fs::read_to_string(&path).await?;
-        let snapshot: Snapshot = serde_json::from_str(&json)?;
+        let mut first_error = None;
+        for (_, path) in candidates {
+            let decoded = match tokio::fs::read(&path).await {
+                Ok(bytes) => decode_snapshot(&bytes),
+                Err(e) => Err(e.into()),
+            };
+            match decoded {
+                Ok(snapshot) => return Ok(Some(self.loaded(&path, snapshot))),
+                Err(e) => {
+                    warn!(path = %path.display(), error = %e, "Failed to load snapshot, trying an older one");
+                    first_error.get_or_insert_with(|| {
+                        anyhow::anyhow!("Failed to load snapshot {}: {}", path.display(), e)
+                    });
+                }
+            }
+        }
+        Err(first_error.expect("at least one snapshot was tried"))
+    }
 
+    fn loaded(&self, path: &std::path::Path, snapshot: Snapshot) -> Snapshot {
         info!(
+            path = %path.display(),
             version = snapshot.version,
             memories = snapshot.memories.len(),
             entities = snapshot.entities.len(),
             "Snapshot loaded"
         );
-        Ok(Some(snapshot))
+        snapshot
+    }
+}
+
+/// A single write-ahead log entry, also the unit of the catch-up event feed.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WalRecord {
+    pub seq: u64,
+    pub event: ReplicationEvent,
+}
+
+/// One page of the catch-up event feed served to bootstrapping nodes.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EventPage {
+    pub events: Vec<WalRecord>,
+    /// Newest sequence number the source has, so the reader knows when it caught up
+    pub last_seq: u64,
+}
+
+/// Borrowed form of [`WalRecord`] used when appending.
+#[derive(Serialize)]
+struct WalRecordRef<'a> {
+    seq: u64,
+    event: &'a ReplicationEvent,
+}
+
+/// The part of a [`WalRecord`] needed to index it.
+#[derive(Deserialize)]
+struct WalRecordHead {
+    seq: u64,
+}
+
+struct WalWriter {
+    file: std::fs::File,
+    /// Length of the file up to the end of the last complete entry.
+    len: u64,
+    last_seq: u64,
+    /// Entries up to here may be gone (truncated or covered by a snapshot).
+    floor: u64,
+    /// Entries up to here were dropped from the file.
+    truncated: u64,
+    /// First sequence number of every batch logged but not yet applied.
+    in_flight: std::collections::BTreeSet<u64>,
+    /// Bumped whenever the file is rewritten, which invalidates `WalMark` offsets.
+    rewrites: u64,
+    /// Sequence number and file offset of every entry, in sequence order.
+    offsets: std::collections::VecDeque<(u64, u64)>,
+}
+
+impl WalWriter {
+    /// Index of the first entry with a sequence number greater than `seq`.
+    fn position_after(&self, seq: u64) -> usize {
+        self.offsets.partition_point(|&(s, _)| s <= seq)
+    }
+}
+
+/// Append-only write-ahead log of replication events.
+///
+/// Each event is written as one JSON line and fsync'd before `append` returns,
+/// so anything acknowledged since the last snapshot survives a crash. Entries
+/// already covered by a saved snapshot are dropped by `truncate_through`,
+/// which records how far it went in `hivemind-wal.floor`: truncation can
+/// leave the file empty, and a snapshot older than that can't be replayed on.
+/// Each entry's file offset is kept in memory, so the catch-up feed reads
+/// only the page it serves.
+pub struct WriteAheadLog {
+    path: PathBuf,
+    floor_path: PathBuf,
+    writer: Mutex<WalWriter>,
+}
+
+impl WriteAheadLog {
+    /// Open (or create) the log in `data_dir`, discarding a torn final line.
+    pub fn open(data_dir: &str) -> anyhow::Result<Self> {
+        let dir = PathBuf::from(data_dir);
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join("hivemind-wal.jsonl");
+        let floor_path = dir.join("hivemind-wal.floor");
+
+        let (records, torn) = read_records::<WalRecord>(&path)?;
+        if torn {
+            warn!(path = %path.display(), "Discarding torn write-ahead log tail");
+            rewrite_wal(&path, &records)?;
+        }
+        drop(records);
+        let (offsets, len) = index_wal(&path)?;
+        let truncated = offsets
+            .front()
+            .map(|&(seq, _)| seq - 1)
+            .unwrap_or(0)
+            .max(read_wal_floor(&floor_path)?);
+        let last_seq = offsets.back().map(|&(seq, _)| seq).unwrap_or(0).max(truncated);
+
+        let file = open_wal_for_append(&path)?;
+        info!(path = %path.display(), entries = offsets.len(), last_seq, "Write-ahead log opened");
+
+        Ok(Self {
+            path,
+            floor_path,
+            writer: Mutex::new(WalWriter {
+                file,
+                len,
+                last_seq,
+                floor: truncated,
+                truncated,
+                in_flight: Default::default(),
+                rewrites: 0,
+                offsets,
+            }),
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, WalWriter> {
+        self.writer.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Make every later append fail, as on a full disk.
+    #[cfg(test)]
+    pub(crate) fn fail_appends(&self) {
+        self.lock().file = std::fs::File::open(&self.path).unwrap();
+    }
+
+    /// Append an event and fsync it. Returns the assigned sequence number.
+    pub fn append(&self, event: &ReplicationEvent) -> anyhow::Result<u64> {
+        let mark = self.append_all(std::slice::from_ref(event))?;
+        self.applied(mark);
+        Ok(mark.last_seq + 1)
+    }
+
+    /// Append events with a single write and fsync: either all of them are
+    /// logged or none is.
+    ///
+    /// The batch counts as in flight until [`applied`](Self::applied) (or
+    /// [`undo`](Self::undo)) is called with the returned mark; snapshots
+    /// don't claim to cover it before then.
+    pub fn append_all(&self, events: &[ReplicationEvent]) -> anyhow::Result<WalMark> {
+        let mut writer = self.lock();
+        let mark = WalMark {
+            last_seq: writer.last_seq,
+            len: writer.len,
+            rewrites: writer.rewrites,
+        };
+        if events.is_empty() {
+            return Ok(mark);
+        }
+
+        let mut lines = Vec::new();
+        let mut offsets = Vec::with_capacity(events.len());
+        for (seq, event) in (mark.last_seq + 1..).zip(events) {
+            offsets.push((seq, mark.len + lines.len() as u64));
+            serde_json::to_writer(&mut lines, &WalRecordRef { seq, event })?;
+            lines.push(b'\n');
+        }
+        append_synced(&mut writer.file, mark.len, &lines)?;
+
+        writer.offsets.extend(offsets);
+        writer.len += lines.len() as u64;
+        writer.last_seq += events.len() as u64;
+        writer.in_flight.insert(mark.last_seq + 1);
+        Ok(mark)
+    }
+
+    /// Mark the batch appended at `mark` as applied to the stores.
+    pub fn applied(&self, mark: WalMark) {
+        self.lock().in_flight.remove(&(mark.last_seq + 1));
+    }
+
+    /// Remove the entries appended at `mark`, for a write that failed after
+    /// they were logged. Nothing may have been appended since.
+    pub fn undo(&self, mark: WalMark) -> anyhow::Result<()> {
+        let mut writer = self.lock();
+        writer.in_flight.remove(&(mark.last_seq + 1));
+        if writer.last_seq <= mark.last_seq {
+            return Ok(());
+        }
+        writer.last_seq = mark.last_seq;
+        let kept = writer.position_after(mark.last_seq);
+        writer.offsets.truncate(kept);
+        if writer.rewrites == mark.rewrites {
+            // The entries are still the tail of the same file
+            writer.len = mark.len;
+            writer.file.set_len(mark.len)?;
+        } else {
+            let (records, _) = read_records::<WalRecord>(&self.path)?;
+            let kept: Vec<WalRecord> = records.into_iter().filter(|r| r.seq <= mark.last_seq).collect();
+            rewrite_wal(&self.path, &kept)?;
+            writer.file = open_wal_for_append(&self.path)?;
+            (writer.offsets, writer.len) = index_wal(&self.path)?;
+            writer.rewrites += 1;
+        }
+        writer.file.sync_data()?;
+        Ok(())
+    }
+
+    /// Sequence number of the most recently appended event.
+    pub fn last_seq(&self) -> u64 {
+        self.lock().last_seq
+    }
+
+    /// Sequence number up to which every logged event has been applied:
+    /// what a snapshot taken now covers.
+    pub fn applied_seq(&self) -> u64 {
+        let writer = self.lock();
+        writer.in_flight.first().map(|seq| seq - 1).unwrap_or(writer.last_seq)
+    }
+
+    /// Return all events with a sequence number greater than `after_seq`.
+    ///
+    /// Also moves the sequence counter past `after_seq`, so a log that was
+    /// truncated down to nothing keeps numbering after the snapshot. Fails if
+    /// entries after `after_seq` were already truncated (the snapshot is older
+    /// than the one the log was truncated for): replaying the rest would
+    /// silently lose those writes.
+    pub fn replay(&self, after_seq: u64) -> anyhow::Result<Vec<ReplicationEvent>> {
+        let mut writer = self.lock();
+        if after_seq < writer.truncated {
+            anyhow::bail!(
+                "The snapshot covers the write-ahead log up to entry {}, but {} was already \
+                 truncated through entry {}; the writes in between would be lost. Restore the \
+                 newer snapshot, or move {} and {} aside to start from this snapshot alone",
+                after_seq,
+                self.path.display(),
+                writer.truncated,
+                self.path.display(),
+                self.floor_path.display()
+            );
+        }
+        writer.last_seq = writer.last_seq.max(after_seq);
+        writer.floor = writer.floor.max(after_seq);
+
+        let (records, _) = read_records::<WalRecord>(&self.path)?;
+        Ok(records
+            .into_iter()
+            .filter(|r| r.seq > after_seq)
+            .map(|r| r.event)
+            .collect())
+    }
+
+    /// Drop every entry up to and including `seq` (called after a snapshot save).
+    pub fn truncate_through(&self, seq: u64) -> anyhow::Result<()> {
+        let mut writer = self.lock();
+        if seq > writer.truncated {
+            write_atomic(&self.floor_path, seq.to_string().as_bytes())?;
+            writer.truncated = seq;
+        }
+
+        let (records, _) = read_records::<WalRecord>(&self.path)?;
+        let remaining: Vec<WalRecord> = records.into_iter().filter(|r| r.seq > seq).collect();
+        rewrite_wal(&self.path, &remaining)?;
+        writer.file = open_wal_for_append(&self.path)?;
+        (writer.offsets, writer.len) = index_wal(&self.path)?;
+        writer.rewrites += 1;
+        writer.floor = writer.floor.max(seq);
+
+        debug!(through = seq, remaining = remaining.len(), "Write-ahead log truncated");
+        Ok(())
+    }
+
+    /// Up to `limit` entries after `after_seq`, for a node catching up from
+    /// a snapshot taken at `after_seq`. `None` if some of those entries were
+    /// already truncated: the reader needs a newer snapshot.
+    ///
+    /// Reads only those entries, starting at the first one's file offset.
+    pub fn events_after(&self, after_seq: u64, limit: usize) -> anyhow::Result<Option<EventPage>> {
+        use std::io::{BufRead, Seek};
+
+        let writer = self.lock();
+        if after_seq < writer.floor {
+            return Ok(None);
+        }
+        let start = writer.position_after(after_seq);
+        let mut events = Vec::new();
+        if let Some(&(_, offset)) = writer.offsets.get(start) {
+            let count = limit.min(writer.offsets.len() - start);
+            let mut file = std::fs::File::open(&self.path)?;
+            file.seek(std::io::SeekFrom::Start(offset))?;
+            let mut reader = std::io::BufReader::new(file);
+            let mut line = String::new();
+            events.reserve(count);
+            for _ in 0..count {
+                line.clear();
+                reader.read_line(&mut line)?;
+                events.push(serde_json::from_str::<WalRecord>(&line)?);
+            }
+        }
+        Ok(Some(EventPage {
+            events,
+            last_seq: writer.last_seq,
+        }))
+    }
+}
+
+/// Where the write-ahead log ended before an append.
+#[derive(Debug, Clone, Copy)]
+pub struct WalMark {
+    last_seq: u64,
+    len: u64,
+    rewrites: u64,
+}
+
+/// Read `hivemind-wal.floor`: the entry the log was last truncated through.
+fn read_wal_floor(path: &Path) -> anyhow::Result<u64> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => text.trim().parse().map_err(|_| {
+            anyhow::anyhow!("Unreadable write-ahead log floor {}: {:?}", path.display(), text)
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Sequence number and offset of every entry in the log file, and its length.
+fn index_wal(path: &Path) -> anyhow::Result<(std::collections::VecDeque<(u64, u64)>, u64)> {
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let mut offsets = std::collections::VecDeque::new();
+    let mut offset = 0;
+    for line in data.split_inclusive(|b| *b == b'\n') {
+        let head: WalRecordHead = serde_json::from_slice(line)?;
+        offsets.push_back((head.seq, offset));
+        offset += line.len() as u64;
+    }
+    Ok((offsets, offset))
+}
+
+fn open_wal_for_append(path: &Path) -> std::io::Result<std::fs::File> {
+    std::fs::OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// Append `bytes` to a JSON-lines file and fsync them.
+///
+/// `len` is where the last complete entry ends. Anything past it (left by an
+/// append that failed part way, e.g. on a full disk) is cut off first, and a
+/// failed write is cut back right away, so a bad line never ends up in front
+/// of entries appended later.
+fn append_synced(file: &mut std::fs::File, len: u64, bytes: &[u8]) -> anyhow::Result<()> {
+    if file.metadata()?.len() != len {
+        file.set_len(len)?;
+    }
+    if let Err(e) = file.write_all(bytes).and_then(|_| file.sync_data()) {
+        if let Err(cut) = file.set_len(len) {
+            warn!(error = %cut, "Could not cut back a failed append; retrying on the next one");
+        }
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// Read all well-formed records. The flag is set if the last line was bad
+/// (torn by a crash mid-write); a bad line anywhere else is an error, since
+/// the entries after it were acknowledged.
+fn read_records<T: serde::de::DeserializeOwned>(path: &Path) -> anyhow::Result<(Vec<T>, bool)> {
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((vec![], false)),
+        Err(e) => return Err(e.into()),
+    };
+    // A torn tail can end inside a multi-byte character
+    let data = String::from_utf8_lossy(&data);
+
+    let lines: Vec<(usize, &str)> = data
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .collect();
+    let mut records = Vec::with_capacity(lines.len());
+    for (i, &(number, line)) in lines.iter().enumerate() {
+        match serde_json::from_str::<T>(line) {
+            Ok(record) => records.push(record),
+            Err(_) if i + 1 == lines.len() => return Ok((records, true)),
+            Err(e) => anyhow::bail!(
+                "Corrupt entry on line {} of {}, followed by {} more: {}",
+                number + 1,
+                path.display(),
+                lines.len() - i - 1,
+                e
+            ),
+        }
+    }
+    Ok((records, false))
+}
+
+/// Atomically replace the log file with the given records.
+fn rewrite_wal(path: &Path, records: &[WalRecord]) -> anyhow::Result<()> {
+    rewrite_records(
+        path,
+        records.iter().map(|record| WalRecordRef {
+            seq: record.seq,
+            event: &record.event,
+        }),
+    )
+}
+
+/// Atomically replace a JSON-lines file with the given records.
+fn rewrite_records<T: Serialize>(path: &Path, records: impl IntoIterator<Item = T>) -> anyhow::Result<()> {
+    let tmp = path.with_extension("jsonl.tmp");
+    let mut file = std::fs::File::create(&tmp)?;
+    for record in records {
+        let mut line = serde_json::to_vec(&record)?;
+        line.push(b'\n');
+        file.write_all(&line)?;
+    }
+    file.sync_all()?;
+    std::fs::rename(&tmp, path)?;
+    sync_parent_dir(path)?;
+    Ok(())
+}
+
+/// Replace a small file via temp file + fsync + rename, so a crash leaves
+/// either the old or the new contents.
+fn write_atomic(path: &Path, bytes: &[u8]) -> anyhow::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    let mut file = std::fs::File::create(&tmp)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    std::fs::rename(&tmp, path)?;
+    sync_parent_dir(path)?;
+    Ok(())
+}
+
+/// Make a rename or file creation in `path`'s directory durable.
+fn sync_parent_dir(path: &Path) -> std::io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    std::fs::File::open(dir)?.sync_all()
+}
+
+/// Most events the replication client sends ahead of RaftTimeDB's acknowledgements.
+pub const OUTBOX_WINDOW: u64 = 1024;
+
+/// Events read from the outbox file per send batch.
+const OUTBOX_BATCH: usize = 128;
+
+/// Acknowledged entries tolerated at the head of the outbox file before it is compacted.
+const OUTBOX_COMPACT_AFTER: u64 = 4096;
+
+/// A single replication outbox entry.
+#[derive(Debug, Deserialize)]
+struct OutboxRecord {
+    seq: u64,
+    queued_at: DateTime<Utc>,
+    event: ReplicationEvent,
+}
+
+/// Borrowed form of [`OutboxRecord`] used when appending.
+#[derive(Serialize)]
+struct OutboxRecordRef<'a> {
+    seq: u64,
+    queued_at: DateTime<Utc>,
+    event: &'a ReplicationEvent,
+}
+
+/// Contents of `replication-outbox.ack`. `last_seq` is kept alongside the
+/// acknowledgement because compaction can leave the outbox file empty, and
+/// numbering must never restart: peers would skip the reused numbers as resends.
+#[derive(Serialize, Deserialize)]
+struct OutboxMarks {
+    acked_seq: u64,
+    last_seq: u64,
+}
+
+impl OutboxMarks {
+    fn read(path: &Path) -> anyhow::Result<Option<Self>> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        if let Ok(marks) = serde_json::from_str::<OutboxMarks>(&text) {
+            return Ok(Some(marks));
+        }
+        // Older versions stored the bare acknowledged sequence number
+        match text.trim().parse::<u64>() {
+            Ok(seq) => Ok(Some(OutboxMarks { acked_seq: seq, last_seq: seq })),
+            Err(_) => anyhow::bail!(
+                "Unreadable replication outbox acknowledgement {}: {:?}",
+                path.display(),
+                text
+            ),
+        }
+    }
+}
+
+/// Where an outbox entry starts in the file, so reads can seek to it.
+struct OutboxEntry {
+    seq: u64,
+    offset: u64,
+    queued_at: DateTime<Utc>,
+}
+
+/// The parts of an [`OutboxRecord`] needed to index it.
+#[derive(Deserialize)]
+struct OutboxRecordHead {
+    seq: u64,
+    queued_at: DateTime<Utc>,
+}
+
+struct OutboxState {
+    file: std::fs::File,
+    last_seq: u64,
+    acked_seq: u64,
+    /// Every entry still in the file, in sequence order.
+    entries: std::collections::VecDeque<OutboxEntry>,
+    /// Length of the file in bytes.
+    len: u64,
+}
+
+impl OutboxState {
+    /// Index of the first entry with a sequence number greater than `seq`.
+    fn position_after(&self, seq: u64) -> usize {
+        self.entries.partition_point(|e| e.seq <= seq)
+    }
+
+    /// Highest sequence number already dropped from the file.
+    fn compacted_seq(&self) -> u64 {
+        self.entries.front().map(|e| e.seq - 1).unwrap_or(self.last_seq)
+    }
+}
+
+/// Disk-backed queue of events waiting for RaftTimeDB to acknowledge them.
+///
+/// Every local write gets the next outbox sequence number and is fsync'd to
+/// `replication-outbox.jsonl` before `push` returns. Entries stay there until
+/// an acknowledgement covers them, so events in flight when the connection
+/// drops (or the process dies) are resent after reconnecting, and a long
+/// RaftTimeDB outage costs disk rather than memory: only each entry's
+/// sequence number, file offset and queue time are kept in memory. The
+/// acknowledged and last sequence numbers are kept in
+/// `replication-outbox.ack`, replaced atomically; numbering never restarts.
+pub struct ReplicationOutbox {
+    path: PathBuf,
+    ack_path: PathBuf,
+    state: Mutex<OutboxState>,
+    notify: tokio::sync::Notify,
+}
+
+impl ReplicationOutbox {
+    /// Open (or create) the outbox in `data_dir`, discarding a torn final line.
+    pub fn open(data_dir: &str) -> anyhow::Result<Self> {
+        let dir = PathBuf::from(data_dir);
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join("replication-outbox.jsonl");
+        let ack_path = dir.join("replication-outbox.ack");
+
+        let marks = OutboxMarks::read(&ack_path)?;
+        let acked_seq = marks.as_ref().map(|m| m.acked_seq).unwrap_or(0);
+
+        let (records, torn) = read_records::<OutboxRecord>(&path)?;
+        if torn {
+            warn!(path = %path.display(), "Discarding torn replication outbox tail");
+            rewrite_outbox(&path, &records)?;
+        }
+        drop(records);
+        let (entries, len) = index_outbox(&path)?;
+        let last_seq = entries
+            .back()
+            .map(|e| e.seq)
+            .unwrap_or(0)
+            .max(marks.map(|m| m.last_seq.max(m.acked_seq)).unwrap_or(0));
+
+        let file = open_wal_for_append(&path)?;
+        info!(
+            path = %path.display(),
+            last_seq,
+            acked_seq,
+            backlog = last_seq - acked_seq,
+            "Replication outbox opened"
+        );
+
+        Ok(Self {
+            path,
+            ack_path,
+            state: Mutex::new(OutboxState {
+                file,
+                last_seq,
+                acked_seq,
+                entries,
+                len,
+            }),
+            notify: tokio::sync::Notify::new(),
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, OutboxState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Queue an event and fsync it. Returns the assigned sequence number.
+    pub fn push(&self, event: &ReplicationEvent) -> anyhow::Result<u64> {
+        self.push_all(std::slice::from_ref(event))
+    }
+
+    /// Queue events with a single write and fsync: either all of them are
+    /// queued or none is. Returns the last assigned sequence number.
+    pub fn push_all(&self, events: &[ReplicationEvent]) -> anyhow::Result<u64> {
+        let seq = {
+            let mut state = self.lock();
+            let queued_at = Utc::now();
+
+            let mut lines = Vec::new();
+            let mut offsets = Vec::with_capacity(events.len());
+            for (seq, event) in (state.last_seq + 1..).zip(events) {
+                offsets.push((seq, state.len + lines.len() as u64));
+                serde_json::to_writer(
+                    &mut lines,
+                    &OutboxRecordRef {
+                        seq,
+                        queued_at,
+                        event,
+                    },
+                )?;
+                lines.push(b'\n');
+            }
+            let len = state.len;
+            append_synced(&mut state.file, len, &lines)?;
+
+            for (seq, offset) in offsets {
+                state.entries.push_back(OutboxEntry {
+                    seq,
+                    offset,
+                    queued_at,
+                });
+            }
+            state.len += lines.len() as u64;
+            state.last_seq += events.len() as u64;
+            state.last_seq
+        };
+        self.notify.notify_one();
+        Ok(seq)
+    }
+
+    /// Wait until an event is pushed.
+    pub async fn notified(&self) {
+        self.notify.notified().await
+    }
+
+    /// Sequence number of the most recently queued event.
+    pub fn last_seq(&self) -> u64 {
+        self.lock().last_seq
+    }
+
+    /// Highest sequence number RaftTimeDB has acknowledged.
+    pub fn acked_seq(&self) -> u64 {
+        self.lock().acked_seq
+    }
+
+    /// Up to `limit` queued events with a sequence number greater than `after_seq`.
+    ///
+    /// Reads only those entries, starting at the first one's file offset.
+    pub fn pending(&self, after_seq: u64, limit: usize) -> anyhow::Result<Vec<(u64, ReplicationEvent)>> {
+        use std::io::{BufRead, Seek};
+
+        let state = self.lock();
+        let start = state.position_after(after_seq);
+        let Some(first) = state.entries.get(start) else {
+            return Ok(Vec::new());
+        };
+        let count = limit.min(state.entries.len() - start);
+
+        let mut file = std::fs::File::open(&self.path)?;
+        file.seek(std::io::SeekFrom::Start(first.offset))?;
+        let mut reader = std::io::BufReader::new(file);
+        let mut events = Vec::with_capacity(count);
+        let mut line = String::new();
+        for _ in 0..count {
+            line.clear();
+            reader.read_line(&mut line)?;
+            let record: OutboxRecord = serde_json::from_str(&line)?;
+            events.push((record.seq, record.event));
+        }
+        Ok(events)
+    }
+
+    /// Record that every event up to and including `seq` is committed.
+    /// Acknowledgements are cumulative; stale ones are ignored.
+    pub fn ack(&self, seq: u64) -> anyhow::Result<()> {
+        use std::io::{Read, Seek};
+
+        let mut state = self.lock();
+        let seq = seq.min(state.last_seq);
+        if seq <= state.acked_seq {
+            return Ok(());
+        }
+        let marks = OutboxMarks {
+            acked_seq: seq,
+            last_seq: state.last_seq,
+        };
+        write_atomic(&self.ack_path, &serde_json::to_vec(&marks)?)?;
+        state.acked_seq = seq;
+
+        // Drop acknowledged entries once nothing is outstanding, or once
+        // enough of them pile up ahead of a persistent backlog.
+        if seq == state.last_seq || seq - state.compacted_seq() >= OUTBOX_COMPACT_AFTER {
+            let keep_from = state.position_after(seq);
+            let base = state.entries.get(keep_from).map(|e| e.offset).unwrap_or(state.len);
+
+            let mut file = std::fs::File::open(&self.path)?;
+            file.seek(std::io::SeekFrom::Start(base))?;
+            let mut tail = Vec::new();
+            file.read_to_end(&mut tail)?;
+            write_atomic(&self.path, &tail)?;
+
+            state.entries.drain(..keep_from);
+            for entry in state.entries.iter_mut() {
+                entry.offset -= base;
+            }
+            state.len -= base;
+            state.file = open_wal_for_append(&self.path)?;
+            debug!(through = seq, remaining = state.entries.len(), "Replication outbox compacted");
+        }
+        Ok(())
+    }
+
+    /// Backlog and lag for `/api/v1/system/topology`.
+    pub fn status(&self) -> ReplicationStatus {
+        let state = self.lock();
+        let (last_seq, acked_seq) = (state.last_seq, state.acked_seq);
+        let oldest_unacked_at = state
+            .entries
+            .get(state.position_after(acked_seq))
+            .map(|e| e.queued_at);
+        drop(state);
+        let lag_seconds = oldest_unacked_at
+            .map(|at| Utc::now().signed_duration_since(at).num_seconds().max(0) as u64)
+            .unwrap_or(0);
+
+        ReplicationStatus {
+            last_seq,
+            acked_seq,
+            backlog: last_seq - acked_seq,
+            lag_seconds,
+            oldest_unacked_at,
+        }
+    }
+}
+
+/// Sequence number, offset and queue time of every entry in the outbox file.
+fn index_outbox(path: &Path) -> anyhow::Result<(std::collections::VecDeque<OutboxEntry>, u64)> {
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let mut entries = std::collections::VecDeque::new();
+    let mut offset = 0;
+    for line in data.split_inclusive(|b| *b == b'\n') {
+        let head: OutboxRecordHead = serde_json::from_slice(line)?;
+        entries.push_back(OutboxEntry {
+            seq: head.seq,
+            offset,
+            queued_at: head.queued_at,
+        });
+        offset += line.len() as u64;
+    }
+    Ok((entries, offset))
+}
+
+fn rewrite_outbox(path: &Path, records: &[OutboxRecord]) -> anyhow::Result<()> {
+    rewrite_records(
+        path,
+        records.iter().map(|record| OutboxRecordRef {
+            seq: record.seq,
+            queued_at: record.queued_at,
+            event: &record.event,
+        }),
+    )
+}
+
+/// Destination for replication events: the write-ahead log and the replication outbox.
+///
+/// Cloned into every component that mutates replicated state (the memory engine
+/// and the channel hub) so all writes go through the same log.
+#[derive(Clone, Default)]
+pub struct ReplicationSink {
+    wal: Option<Arc<WriteAheadLog>>,
+    outbox: Option<Arc<ReplicationOutbox>>,
+    health: Arc<SinkHealth>,
+}
+
+/// State shared by every clone of a [`ReplicationSink`].
+#[derive(Default)]
+struct SinkHealth {
+    /// Whether the most recent append failed
+    failing: std::sync::atomic::AtomicBool,
+    /// Held from the WAL append to the outbox push, so a failed push can
+    /// undo the WAL entries without touching another write's
+    order: Mutex<()>,
+}
+
+impl ReplicationSink {
+    pub fn set_wal(&mut self, wal: Arc<WriteAheadLog>) {
+        self.wal = Some(wal);
+    }
+
+    pub fn set_outbox(&mut self, outbox: Arc<ReplicationOutbox>) {
+        self.outbox = Some(outbox);
+    }
+
+    /// Whether events are forwarded to RaftTimeDB.
+    pub fn is_replicating(&self) -> bool {
+        self.outbox.is_some()
+    }
+
+    /// Outbox backlog and lag (`None` when not replicating).
+    pub fn status(&self) -> Option<ReplicationStatus> {
+        self.outbox.as_ref().map(|o| o.status())
+    }
+
+    /// WAL position a snapshot taken now covers: every event up to it has
+    /// been applied (0 without a WAL).
+    pub fn wal_seq(&self) -> u64 {
+        self.wal.as_ref().map(|w| w.applied_seq()).unwrap_or(0)
+    }
+
+    /// Whether the most recent append succeeded.
+    pub fn is_healthy(&self) -> bool {
+        !self.health.failing.load(std::sync::atomic::Ordering::SeqCst)
+    }
+
+    fn record_outcome<T>(&self, result: anyhow::Result<T>) -> anyhow::Result<T> {
+        match result {
+            Ok(value) => {
+                self.health.failing.store(false, std::sync::atomic::Ordering::SeqCst);
+                Ok(value)
+            }
+            Err(e) => {
+                self.health.failing.store(true, std::sync::atomic::Ordering::SeqCst);
+                Err(e)
+            }
+        }
+    }
+
+    /// Catch-up feed for bootstrapping nodes (see [`WriteAheadLog::events_after`]).
+    pub fn events_after(&self, after_seq: u64, limit: usize) -> anyhow::Result<Option<EventPage>> {
+        match self.wal {
+            Some(ref wal) => wal.events_after(after_seq, limit),
+            None => Ok(Some(EventPage { events: Vec::new(), last_seq: 0 })),
+        }
+    }
+
+    /// Queue an event for the replication client without logging it
+    /// locally, for announcements that aren't part of this node's state.
+    pub fn announce(&self, event: &ReplicationEvent) -> anyhow::Result<()> {
+        if let Some(ref outbox) = self.outbox {
+            outbox.push(event)?;
+        }
+        Ok(())
+    }
+
+    /// Durably log events received from another node, then run `apply`.
+    /// They are not forwarded: the origin node already replicated them. The
+    /// events are already committed by the cluster, so they are applied even
+    /// if they can't be logged.
+    pub fn commit_inbound<T>(&self, events: &[ReplicationEvent], apply: impl FnOnce() -> T) -> T {
+        let mark = match self.wal {
+            Some(ref wal) => match self.record_outcome(wal.append_all(events)) {
+                Ok(mark) => Some(mark),
+                Err(e) => {
+                    error!(error = %e, "Failed to append to write-ahead log");
+                    None
+                }
+            },
+            None => None,
+        };
+        let result = apply();
+        if let (Some(wal), Some(mark)) = (&self.wal, mark) {
+            wal.applied(mark);
+        }
+        result
+    }
+
+    /// Durably log a local write's events and queue them for the replication
+    /// client, then run `apply` to make the write visible.
+    ///
+    /// Either every event is logged and queued or none is (the WAL entries
+    /// are undone if the outbox push fails), and nothing is applied unless
+    /// they all are, so a write that fails here leaves no trace.
+    pub fn commit<T>(&self, events: &[ReplicationEvent], apply: impl FnOnce() -> T) -> anyhow::Result<T> {
+        if events.is_empty() {
+            return Ok(apply());
+        }
+        let order = self.health.order.lock().unwrap_or_else(|e| e.into_inner());
+        let mark = match self.wal {
+            Some(ref wal) => Some(self.record_outcome(wal.append_all(events))?),
+            None => None,
+        };
+        if let Some(ref outbox) = self.outbox
+            && let Err(e) = self.record_outcome(outbox.push_all(events))
+        {
+            if let (Some(wal), Some(mark)) = (&self.wal, mark)
+                && let Err(undo) = wal.undo(mark)
+            {
+                error!(error = %undo, "Failed to undo write-ahead log entries of a write that wasn't queued");
+            }
+            return Err(e.context("Failed to queue event in replication outbox"));
+        }
+        drop(order);
+
+        let result = apply();
+        if let (Some(wal), Some(mark)) = (&self.wal, mark) {
+            wal.applied(mark);
+        }
+        Ok(result)
     }
 }
 
@@ -104,36 +1197,144 @@ impl SnapshotManager {
 ///
 /// Connects to a RaftTimeDB node's WebSocket endpoint and forwards
 /// memory write operations as SpacetimeDB reducer calls through Raft consensus.
+/// Events are read from the [`ReplicationOutbox`] and stay there until
+/// acknowledged; after a reconnect everything unacknowledged is sent again.
+/// Events other nodes committed come back on the same subscription and are
+/// handed to the inbound channel for local application.
 ///
 /// This ensures all HiveMindDB nodes in a cluster maintain identical state.
 pub struct ReplicationClient {
     rtdb_url: String,
-    connected: std::sync::atomic::AtomicBool,
+    node_id: String,
+    node_number: u16,
+    connected: Arc<std::sync::atomic::AtomicBool>,
     shutdown: watch::Receiver<bool>,
 }
 
 /// Replication event sent to RaftTimeDB.
-#[derive(Debug, Serialize, Deserialize)]
+///
+/// Every state change in the `MemoryEngine` and `ChannelHub` is described by
+/// one of these, so a follower applying the stream ends up with the same
+/// state as the node that made the change.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ReplicationEvent {
     MemoryAdded { memory: Memory },
     MemoryUpdated { memory: Memory },
-    MemoryInvalidated { memory_id: u64, reason: String },
+    MemoryInvalidated {
+        memory_id: u64,
+        reason: String,
+        /// When the origin invalidated it (absent in events logged by older versions)
+        #[serde(default)]
+        invalidated_at: Option<DateTime<Utc>>,
+    },
+    MemoryHistoryRecorded { history: MemoryHistory },
     EntityAdded { entity: Entity },
+    EntityUpdated { entity: Entity },
     RelationshipAdded { relationship: Relationship },
+    RelationshipInvalidated { relationship: Relationship },
+    MentionAdded { mention: EntityMention },
+    SessionStarted { session: Session },
+    EpisodeRecorded { episode: Episode },
     AgentRegistered { agent: Agent },
+    /// Heartbeats and status changes of a registered agent
+    AgentUpdated { agent: Agent },
     ChannelCreated { channel: Channel },
+    ChannelSubscribed { channel_id: u64, agent_id: String },
     TaskCreated { task: Task },
     TaskClaimed { task: Task },
+    TaskStarted { task: Task },
     TaskCompleted { task: Task },
     TaskFailed { task: Task },
+    TaskCancelled { task: Task },
+    TaskReassigned { task: Task },
+    TaskUnblocked { task: Task },
+    TaskUpdated { task: Task },
+    TaskTemplateCreated { template: TaskTemplate },
+    TaskTemplateUpdated { template: TaskTemplate },
+    TaskTemplateDeleted { template_id: u64 },
+    TaskEventRecorded { event: TaskEvent },
+    /// Everything a benchmark run created under its agent ID was removed
+    BenchmarkDataRemoved { agent_id: String },
+    /// A node announcing it is alive; never logged, only sent to peers
+    NodeHeartbeat { node_number: u16 },
+    /// Logged with an event from another node: where it was in the origin's
+    /// outbox. Never sent to peers
+    PeerEventApplied { origin: String, seq: u64 },
+}
+
+/// A replication event on the wire, tagged with the node that produced it,
+/// the ID range it mints from and its position in that node's outbox.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplicationEnvelope {
+    pub origin: String,
+    /// 0 in events sent by older versions
+    #[serde(default)]
+    pub node_number: u16,
+    #[serde(default)]
+    pub seq: u64,
+    pub event: ReplicationEvent,
+}
+
+/// An explicit acknowledgement frame from RaftTimeDB.
+#[derive(Deserialize)]
+struct AckFrame {
+    ack: u64,
+}
+
+/// What a frame on the RaftTimeDB subscription means to this node.
+#[derive(Debug)]
+pub enum InboundFrame {
+    /// Our own events up to this outbox sequence number are committed.
+    Ack(u64),
+    /// Another node's committed event.
+    Event(Box<ReplicationEnvelope>),
+}
+
+/// Decode a frame from the RaftTimeDB subscription. This node's own events,
+/// which RaftTimeDB echoes back to every subscriber once committed, count as
+/// acknowledgements. Returns `None` for anything unrecognised.
+pub fn decode_inbound(text: &str, node_id: &str) -> Option<InboundFrame> {
+    if let Ok(frame) = serde_json::from_str::<AckFrame>(text) {
+        return Some(InboundFrame::Ack(frame.ack));
+    }
+    match serde_json::from_str::<ReplicationEnvelope>(text) {
+        Ok(envelope) if envelope.origin == node_id => Some(InboundFrame::Ack(envelope.seq)),
+        Ok(envelope) => Some(InboundFrame::Event(Box::new(envelope))),
+        Err(e) => {
+            debug!(error = %e, "Ignoring unrecognised replication frame");
+            None
+        }
+    }
+}
+
+/// This node's replication identity: `configured` if given, otherwise an ID
+/// generated on first start and kept in `data_dir/node-id`.
+pub fn load_or_create_node_id(data_dir: &str, configured: Option<String>) -> anyhow::Result<String> {
+    if let Some(id) = configured.filter(|id| !id.trim().is_empty()) {
+        return Ok(id);
+    }
+    let path = PathBuf::from(data_dir).join("node-id");
+    match std::fs::read_to_string(&path) {
+        Ok(id) if !id.trim().is_empty() => return Ok(id.trim().to_string()),
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+    std::fs::create_dir_all(data_dir)?;
+    let id = uuid::Uuid::new_v4().to_string();
+    std::fs::write(&path, &id)?;
+    info!(node_id = %id, path = %path.display(), "Generated node ID");
+    Ok(id)
 }
 
 impl ReplicationClient {
-    pub fn new(rtdb_url: &str, shutdown: watch::Receiver<bool>) -> Self {
+    pub fn new(rtdb_url: &str, node_id: &str, node_number: u16, shutdown: watch::Receiver<bool>) -> Self {
         Self {
             rtdb_url: rtdb_url.to_string(),
-            connected: std::sync::atomic::AtomicBool::new(false),
+            node_id: node_id.to_string(),
+            node_number,
+            connected: Arc::new(std::sync::atomic::AtomicBool::new(false)),
             shutdown,
         }
     }
@@ -143,12 +1344,19 @@ impl ReplicationClient {
             .load(std::sync::atomic::Ordering::Relaxed)
     }
 
-    /// Start the replication client — connects to RaftTimeDB and forwards events.
+    /// Shared connection flag, for readiness reporting after the client is spawned.
+    pub fn connection_flag(&self) -> Arc<std::sync::atomic::AtomicBool> {
+        self.connected.clone()
+    }
+
+    /// Start the replication client — connects to RaftTimeDB, forwards queued
+    /// events and passes other nodes' committed events to `inbound_tx`.
     ///
     /// Runs until the shutdown signal is received. Reconnects automatically on failure.
     pub async fn run(
         &self,
-        mut event_rx: tokio::sync::mpsc::UnboundedReceiver<ReplicationEvent>,
+        outbox: Arc<ReplicationOutbox>,
+        inbound_tx: tokio::sync::mpsc::UnboundedSender<ReplicationEnvelope>,
     ) {
         info!(url = %self.rtdb_url, "Starting replication client");
 
@@ -159,7 +1367,7 @@ impl ReplicationClient {
                 break;
             }
 
-            match self.connect_and_forward(&mut event_rx).await {
+            match self.connect_and_forward(&outbox, &inbound_tx).await {
                 Ok(()) => {
                     info!("Replication session ended cleanly");
                     break;
@@ -180,9 +1388,10 @@ impl ReplicationClient {
 
     async fn connect_and_forward(
         &self,
-        event_rx: &mut tokio::sync::mpsc::UnboundedReceiver<ReplicationEvent>,
+        outbox: &ReplicationOutbox,
+        inbound_tx: &tokio::sync::mpsc::UnboundedSender<ReplicationEnvelope>,
     ) -> anyhow::Result<()> {
-        use futures_util::SinkExt;
+        use futures_util::{SinkExt, StreamExt};
         use tokio_tungstenite::tungstenite;
 
         // Connect to RaftTimeDB WebSocket
@@ -192,23 +1401,53 @@ impl ReplicationClient {
         );
 
         let (ws_stream, _) = tokio_tungstenite::connect_async(&ws_url).await?;
-        let (mut ws_tx, _ws_rx) = futures_util::StreamExt::split(ws_stream);
+        let (mut ws_tx, mut ws_rx) = ws_stream.split();
 
         self.connected
             .store(true, std::sync::atomic::Ordering::Relaxed);
-        info!("Connected to RaftTimeDB for replication");
+        // Anything sent on an earlier connection but never acknowledged goes again
+        let mut sent_seq = outbox.acked_seq();
+        info!(resend_from = sent_seq + 1, backlog = outbox.last_seq() - sent_seq, "Connected to RaftTimeDB for replication");
 
         let mut shutdown = self.shutdown.clone();
         loop {
+            while sent_seq < outbox.last_seq() && sent_seq - outbox.acked_seq() < OUTBOX_WINDOW {
+                let batch = outbox.pending(sent_seq, OUTBOX_BATCH)?;
+                if batch.is_empty() {
+                    break;
+                }
+                for (seq, event) in batch {
+                    let envelope = ReplicationEnvelope {
+                        origin: self.node_id.clone(),
+                        node_number: self.node_number,
+                        seq,
+                        event,
+                    };
+                    let json = serde_json::to_string(&envelope)?;
+                    ws_tx.send(tungstenite::Message::Text(json.into())).await?;
+                    debug!(seq, event_type = ?std::mem::discriminant(&envelope.event), "Replicated event");
+                    sent_seq = seq;
+                }
+            }
+
             tokio::select! {
-                event = event_rx.recv() => {
-                    match event {
-                        Some(evt) => {
-                            let json = serde_json::to_string(&evt)?;
-                            ws_tx.send(tungstenite::Message::Text(json.into())).await?;
-                            debug!(event_type = ?std::mem::discriminant(&evt), "Replicated event");
+                _ = outbox.notified() => {}
+                frame = ws_rx.next() => {
+                    match frame {
+                        Some(Ok(tungstenite::Message::Text(text))) => {
+                            match decode_inbound(&text, &self.node_id) {
+                                Some(InboundFrame::Ack(seq)) => outbox.ack(seq)?,
+                                Some(InboundFrame::Event(envelope)) => {
+                                    let _ = inbound_tx.send(*envelope);
+                                }
+                                None => {}
+                            }
+                        }
+                        Some(Ok(tungstenite::Message::Close(_))) | None => {
+                            anyhow::bail!("RaftTimeDB closed the replication stream");
                         }
-                        None => break, // Channel closed
+                        Some(Ok(_)) => {}
+                        Some(Err(e)) => return Err(e.into()),
                     }
                 }
                 _ = shutdown.changed() => break,
@@ -219,10 +1458,167 @@ impl ReplicationClient {
     }
 }
 
+/// Where a new or lagging node fetches the current state from at startup:
+/// another HiveMindDB node, via its `/api/v1/replication` endpoints.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BootstrapSource {
+    url: String,
+}
+
+/// Events fetched per catch-up request.
+pub const BOOTSTRAP_PAGE: usize = 1000;
+
+/// Attempts before a node gives up and starts from its local state.
+const BOOTSTRAP_ATTEMPTS: u32 = 5;
+
+impl BootstrapSource {
+    /// A peer's base URL, e.g. `http://node-1:8100`.
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        if !(value.starts_with("http://") || value.starts_with("https://")) {
+            anyhow::bail!("--bootstrap-from must be a peer's base URL (http://host:port), got {:?}", value);
+        }
+        Ok(Self {
+            url: value.trim_end_matches('/').to_string(),
+        })
+    }
+
+    fn snapshot_url(&self) -> String {
+        format!("{}/api/v1/replication/snapshot", self.url)
+    }
+
+    fn events_url(&self, after_seq: u64) -> String {
+        format!(
+            "{}/api/v1/replication/events?after={}&limit={}",
+            self.url, after_seq, BOOTSTRAP_PAGE
+        )
+    }
+}
+
+/// State fetched from a bootstrap source: its snapshot and everything it
+/// logged after the snapshot's sequence number, in order.
+pub struct CatchUp {
+    pub snapshot: Snapshot,
+    pub events: Vec<ReplicationEvent>,
+}
+
+/// Catch-up bootstrap for a new or lagging node.
+///
+/// Fetches the source's snapshot (in the regular snapshot encoding, with
+/// `wal_seq` set to its position in the source's log), then pages through
+/// the events after that position until reaching the source's newest one.
+/// If the source truncated its log past the snapshot in between (it answers
+/// `410 Gone`), a newer snapshot is fetched. Events are applied idempotently,
+/// so overlap with what the replication stream delivers afterwards is harmless.
+pub async fn bootstrap(source: &BootstrapSource) -> anyhow::Result<CatchUp> {
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(120))
+        .build()?;
+
+    for attempt in 1..=BOOTSTRAP_ATTEMPTS {
+        match fetch_catch_up(&client, source).await {
+            Ok(Some(catch_up)) => return Ok(catch_up),
+            Ok(None) => {
+                info!(attempt, "Bootstrap source truncated its log past the snapshot, fetching a newer one")
+            }
+            Err(e) if attempt < BOOTSTRAP_ATTEMPTS => {
+                warn!(attempt, error = %e, "Bootstrap failed, retrying in 5s");
+                tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    anyhow::bail!("No consistent snapshot and log from {:?} after {} attempts", source, BOOTSTRAP_ATTEMPTS)
+}
+
+/// One bootstrap attempt; `None` if the snapshot went stale while reading the log.
+async fn fetch_catch_up(
+    client: &reqwest::Client,
+    source: &BootstrapSource,
+) -> anyhow::Result<Option<CatchUp>> {
+    let bytes = client
+        .get(source.snapshot_url())
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    let snapshot = decode_snapshot(&bytes)?;
+
+    let mut seq = snapshot.wal_seq;
+    let mut events = Vec::new();
+    loop {
+        let response = client.get(source.events_url(seq)).send().await?;
+        if response.status() == reqwest::StatusCode::GONE {
+            return Ok(None);
+        }
+        let page: EventPage = response.error_for_status()?.json().await?;
+        let Some(last) = page.events.last() else {
+            break;
+        };
+        seq = last.seq;
+        events.extend(page.events.into_iter().map(|r| r.event));
+        if seq >= page.last_seq {
+            break;
+        }
+    }
+
+    info!(
+        snapshot_seq = snapshot.wal_seq,
+        caught_up_to = seq,
+        events = events.len(),
+        "Fetched state from bootstrap source"
+    );
+    Ok(Some(CatchUp { snapshot, events }))
+}
+
+/// Readiness as reported by `/health`, separate from liveness: the node
+/// caught up at startup (or had nothing to catch up from) and, when
+/// replicating, is connected to RaftTimeDB.
+pub struct NodeReadiness {
+    bootstrap: BootstrapOutcome,
+    replication_connected: Option<Arc<std::sync::atomic::AtomicBool>>,
+    sink: ReplicationSink,
+}
+
+impl NodeReadiness {
+    pub fn new(
+        bootstrap: BootstrapOutcome,
+        replication_connected: Option<Arc<std::sync::atomic::AtomicBool>>,
+        sink: ReplicationSink,
+    ) -> Self {
+        Self {
+            bootstrap,
+            replication_connected,
+            sink,
+        }
+    }
+
+    pub fn report(&self) -> HealthResponse {
+        let replication_connected = self
+            .replication_connected
+            .as_ref()
+            .map(|c| c.load(std::sync::atomic::Ordering::Relaxed));
+        let wal_healthy = self.sink.is_healthy();
+        HealthResponse {
+            live: true,
+            ready: self.bootstrap != BootstrapOutcome::Failed
+                && replication_connected != Some(false)
+                && wal_healthy,
+            bootstrap: self.bootstrap,
+            wal_healthy,
+            replication_connected,
+        }
+    }
+}
+
 /// Periodic snapshot task — saves snapshots at regular intervals.
+///
+/// After each successful save, write-ahead log entries covered by the
+/// snapshot are truncated.
 pub async fn snapshot_loop(
     manager: SnapshotManager,
     snapshot_fn: impl Fn() -> Snapshot + Send + 'static,
+    wal: Option<Arc<WriteAheadLog>>,
     interval_secs: u64,
     mut shutdown: watch::Receiver<bool>,
 ) {
@@ -232,16 +1628,18 @@ pub async fn snapshot_loop(
         tokio::select! {
             _ = tokio::time::sleep(interval) => {
                 let snapshot = snapshot_fn();
-                if let Err(e) = manager.save(&snapshot).await {
-                    error!(error = %e, "Failed to save snapshot");
+                match manager.save(&snapshot).await {
+                    Ok(()) => truncate_wal(wal.as_deref(), snapshot.wal_seq),
+                    Err(e) => error!(error = %e, "Failed to save snapshot"),
                 }
             }
             _ = shutdown.changed() => {
                 // Final snapshot on shutdown
                 info!("Saving final snapshot before shutdown");
                 let snapshot = snapshot_fn();
-                if let Err(e) = manager.save(&snapshot).await {
-                    error!(error = %e, "Failed to save final snapshot");
+                match manager.save(&snapshot).await {
+                    Ok(()) => truncate_wal(wal.as_deref(), snapshot.wal_seq),
+                    Err(e) => error!(error = %e, "Failed to save final snapshot"),
                 }
                 break;
             }
@@ -249,6 +1647,14 @@ pub async fn snapshot_loop(
     }
 }
 
+fn truncate_wal(wal: Option<&WriteAheadLog>, seq: u64) {
+    if let Some(wal) = wal
+        && let Err(e) = wal.truncate_through(seq)
+    {
+        error!(error = %e, "Failed to truncate write-ahead log");
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -286,11 +1692,18 @@ mod tests {
             }],
             relationships: vec![],
             episodes: vec![],
+            sessions: vec![],
             agents: vec![],
             history: vec![],
             channels: vec![],
+            channel_subscriptions: vec![],
             tasks: vec![],
             task_events: vec![],
+            task_templates: vec![],
+            mentions: vec![],
+            embeddings: None,
+            wal_seq: 0,
+            peer_seqs: vec![],
         };
 
         let json = serde_json::to_string(&snapshot).unwrap();
@@ -329,11 +1742,18 @@ mod tests {
             entities: vec![],
             relationships: vec![],
             episodes: vec![],
+            sessions: vec![],
             agents: vec![],
             history: vec![],
             channels: vec![],
+            channel_subscriptions: vec![],
             tasks: vec![],
             task_events: vec![],
+            task_templates: vec![],
+            mentions: vec![],
+            embeddings: None,
+            wal_seq: 0,
+            peer_seqs: vec![],
         };
 
         manager.save(&snapshot).await.unwrap();
@@ -347,6 +1767,114 @@ mod tests {
         let _ = tokio::fs::remove_dir_all(&dir).await;
     }
 
+    fn sample_snapshot() -> Snapshot {
+        Snapshot {
+            version: Snapshot::CURRENT_VERSION,
+            created_at: chrono::Utc::now(),
+            memories: vec![Memory {
+                id: 7,
+                content: "Binary snapshots are compact".into(),
+                memory_type: MemoryType::Semantic,
+                agent_id: Some("agent-1".into()),
+                user_id: None,
+                session_id: None,
+                confidence: 0.9,
+                tags: vec!["storage".into()],
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+                valid_from: chrono::Utc::now(),
+                valid_until: None,
+                source: "test".into(),
+                metadata: serde_json::json!({"nested": {"n": 1, "list": [true, null, 2.5]}}),
+            }],
+            entities: vec![],
+            relationships: vec![],
+            episodes: vec![],
+            sessions: vec![],
+            agents: vec![],
+            history: vec![],
+            channels: vec![],
+            channel_subscriptions: vec![],
+            tasks: vec![],
+            task_events: vec![],
+            task_templates: vec![],
+            mentions: vec![],
+            embeddings: None,
+            wal_seq: 12,
+            peer_seqs: vec![],
+        }
+    }
+
+    #[test]
+    fn test_binary_snapshot_roundtrip() {
+        let snapshot = sample_snapshot();
+        let bytes = encode_snapshot(&snapshot, SnapshotFormat::Binary).unwrap();
+        assert!(bytes.starts_with(BINARY_MAGIC));
+
+        let loaded = decode_snapshot(&bytes).unwrap();
+        assert_eq!(loaded.wal_seq, 12);
+        assert_eq!(loaded.memories[0].content, "Binary snapshots are compact");
+        assert_eq!(loaded.memories[0].metadata, snapshot.memories[0].metadata);
+        assert_eq!(loaded.memories[0].memory_type, MemoryType::Semantic);
+    }
+
+    #[test]
+    fn test_binary_snapshot_detects_corruption() {
+        let mut bytes = encode_snapshot(&sample_snapshot(), SnapshotFormat::Binary).unwrap();
+
+        let mut flipped = bytes.clone();
+        flipped[BINARY_HEADER_LEN + 3] ^= 0xff;
+        let err = decode_snapshot(&flipped).unwrap_err();
+        assert!(err.to_string().contains("checksum"));
+
+        // A huge length field must not overflow or slice out of bounds
+        let mut huge = bytes.clone();
+        huge[10..18].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(decode_snapshot(&huge).is_err());
+        huge[10..18].copy_from_slice(&(u64::MAX - 21).to_le_bytes());
+        assert!(decode_snapshot(&huge).is_err());
+
+        bytes.truncate(bytes.len() - 10);
+        let err = decode_snapshot(&bytes).unwrap_err();
+        assert!(err.to_string().contains("length"));
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_load_falls_back_when_newest_is_corrupt() {
+        let dir = std::env::temp_dir().join(format!("hivemind-fallback-{}", std::process::id()));
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+
+        let manager = SnapshotManager::new(dir.to_str().unwrap()).with_format(SnapshotFormat::Json);
+        manager.save(&sample_snapshot()).await.unwrap();
+        // A newer binary snapshot that got cut short
+        tokio::fs::write(dir.join(SnapshotFormat::Binary.file_name()), &BINARY_MAGIC[..]).await.unwrap();
+
+        let loaded = SnapshotManager::new(dir.to_str().unwrap()).load().await.unwrap().unwrap();
+        assert_eq!(loaded.memories[0].id, 7);
+
+        // With nothing usable left, the error names the newest file
+        tokio::fs::remove_file(dir.join(SnapshotFormat::Json.file_name())).await.unwrap();
+        let err = SnapshotManager::new(dir.to_str().unwrap()).load().await.unwrap_err();
+        assert!(err.to_string().contains(SnapshotFormat::Binary.file_name()));
+
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_load_detects_json() {
+        let dir = std::env::temp_dir().join(format!("hivemind-json-{}", std::process::id()));
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+
+        let manager = SnapshotManager::new(dir.to_str().unwrap()).with_format(SnapshotFormat::Json);
+        manager.save(&sample_snapshot()).await.unwrap();
+
+        // A manager configured for binary still reads the JSON snapshot
+        let loaded = SnapshotManager::new(dir.to_str().unwrap()).load().await.unwrap().unwrap();
+        assert_eq!(loaded.memories[0].id, 7);
+
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+    }
+
     #[tokio::test]
     async fn test_snapshot_load_missing() {
         let dir = std::env::temp_dir().join("hivemind-nonexistent-dir-12345");
@@ -355,6 +1883,373 @@ mod tests {
         assert!(loaded.is_none());
     }
 
+    fn wal_test_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("hivemind-wal-{}-{}", name, std::process::id()))
+    }
+
+    fn invalidated(memory_id: u64) -> ReplicationEvent {
+        ReplicationEvent::MemoryInvalidated {
+            memory_id,
+            reason: "test".into(),
+            invalidated_at: None,
+        }
+    }
+
+    #[test]
+    fn test_wal_append_and_replay() {
+        let dir = wal_test_dir("replay");
+        let _ = std::fs::remove_dir_all(&dir);
+        let wal = WriteAheadLog::open(dir.to_str().unwrap()).unwrap();
+
+        assert_eq!(wal.append(&invalidated(1)).unwrap(), 1);
+        assert_eq!(wal.append(&invalidated(2)).unwrap(), 2);
+        assert_eq!(wal.append(&invalidated(3)).unwrap(), 3);
+        drop(wal);
+
+        // Reopening picks up where the log left off
+        let wal = WriteAheadLog::open(dir.to_str().unwrap()).unwrap();
+        assert_eq!(wal.last_seq(), 3);
+
+        let events = wal.replay(1).unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(matches!(events[0], ReplicationEvent::MemoryInvalidated { memory_id: 2, .. }));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_wal_truncate_through() {
+        let dir = wal_test_dir("truncate");
+        let _ = std::fs::remove_dir_all(&dir);
+        let wal = WriteAheadLog::open(dir.to_str().unwrap()).unwrap();
+
+        for id in 1..=4 {
+            wal.append(&invalidated(id)).unwrap();
+        }
+        wal.truncate_through(3).unwrap();
+        assert_eq!(wal.replay(3).unwrap().len(), 1);
+
+        // Appends after truncation keep their sequence numbers increasing
+        assert_eq!(wal.append(&invalidated(5)).unwrap(), 5);
+        wal.truncate_through(5).unwrap();
+        drop(wal);
+
+        // An empty log resumes numbering after the snapshot's sequence
+        let wal = WriteAheadLog::open(dir.to_str().unwrap()).unwrap();
+        assert!(wal.replay(5).unwrap().is_empty());
+        assert_eq!(wal.append(&invalidated(6)).unwrap(), 6);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_wal_refuses_snapshot_older_than_truncation() {
+        let dir = wal_test_dir("floor");
+        let _ = std::fs::remove_dir_all(&dir);
+        let wal = WriteAheadLog::open(dir.to_str().unwrap()).unwrap();
+        for id in 1..=4 {
+            wal.append(&invalidated(id)).unwrap();
+        }
+        // Saved a snapshot at 2 (the fallback), then one at 4 (newest)
+        wal.truncate_through(2).unwrap();
+        wal.truncate_through(4).unwrap();
+        drop(wal);
+
+        // The log is empty now, but remembers how far it was truncated
+        let wal = WriteAheadLog::open(dir.to_str().unwrap()).unwrap();
+        assert_eq!(wal.last_seq(), 4);
+        let err = wal.replay(2).unwrap_err();
+        assert!(err.to_string().contains("truncated through entry 4"), "{}", err);
+        assert!(wal.replay(0).is_err());
+        assert!(wal.replay(4).unwrap().is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_wal_discards_torn_tail() {
+        let dir = wal_test_dir("torn");
+        let _ = std::fs::remove_dir_all(&dir);
+        let wal = WriteAheadLog::open(dir.to_str().unwrap()).unwrap();
+        wal.append(&invalidated(1)).unwrap();
+        drop(wal);
+
+        // Simulate a crash in the middle of a write
+        let mut file = open_wal_for_append(&dir.join("hivemind-wal.jsonl")).unwrap();
+        file.write_all(br#"{"seq":2,"event":{"type":"memo"#).unwrap();
+        drop(file);
+
+        let wal = WriteAheadLog::open(dir.to_str().unwrap()).unwrap();
+        assert_eq!(wal.last_seq(), 1);
+        assert_eq!(wal.append(&invalidated(2)).unwrap(), 2);
+        assert_eq!(wal.replay(0).unwrap().len(), 2);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_wal_failed_append_leaves_no_bad_line() {
+        let dir = wal_test_dir("partial");
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("hivemind-wal.jsonl");
+        let wal = WriteAheadLog::open(dir.to_str().unwrap()).unwrap();
+        wal.append(&invalidated(1)).unwrap();
+
+        // What an append that failed part way (e.g. on a full disk) leaves behind
+        let mut file = open_wal_for_append(&path).unwrap();
+        file.write_all(br#"{"seq":2,"event":{"type":"memo"#).unwrap();
+        drop(file);
+
+        // The next append replaces it instead of landing behind it
+        assert_eq!(wal.append(&invalidated(2)).unwrap(), 2);
+        drop(wal);
+        let wal = WriteAheadLog::open(dir.to_str().unwrap()).unwrap();
+        assert_eq!(wal.replay(0).unwrap().len(), 2);
+        drop(wal);
+
+        // A bad line with acknowledged entries after it is not a torn tail
+        let good = std::fs::read_to_string(&path).unwrap();
+        let (first, second) = good.split_once('\n').unwrap();
+        std::fs::write(&path, format!("{}\n{{\"seq\":\n{}", first, second)).unwrap();
+        let err = WriteAheadLog::open(dir.to_str().unwrap()).err().unwrap();
+        assert!(err.to_string().contains("Corrupt entry on line 2"), "{}", err);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_outbox_resends_until_acked() {
+        let dir = wal_test_dir("outbox");
+        let _ = std::fs::remove_dir_all(&dir);
+        let outbox = ReplicationOutbox::open(dir.to_str().unwrap()).unwrap();
+
+        for id in 1..=3 {
+            assert_eq!(outbox.push(&invalidated(id)).unwrap(), id);
+        }
+        outbox.ack(1).unwrap();
+        let status = outbox.status();
+        assert_eq!((status.acked_seq, status.backlog), (1, 2));
+        assert!(status.oldest_unacked_at.is_some());
+        drop(outbox);
+
+        // After a restart, everything unacknowledged is still pending
+        let outbox = ReplicationOutbox::open(dir.to_str().unwrap()).unwrap();
+        let pending = outbox.pending(outbox.acked_seq(), 10).unwrap();
+        assert_eq!(pending.iter().map(|(seq, _)| *seq).collect::<Vec<_>>(), vec![2, 3]);
+        assert_eq!(outbox.pending(2, 10).unwrap().len(), 1);
+
+        // Stale acks are ignored; a full ack empties the file but not the numbering
+        outbox.ack(3).unwrap();
+        outbox.ack(2).unwrap();
+        assert_eq!(outbox.status().backlog, 0);
+        assert!(outbox.pending(0, 10).unwrap().is_empty());
+        drop(outbox);
+
+        let outbox = ReplicationOutbox::open(dir.to_str().unwrap()).unwrap();
+        assert_eq!(outbox.acked_seq(), 3);
+        assert_eq!(outbox.push(&invalidated(4)).unwrap(), 4);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_outbox_compacts_behind_a_backlog() {
+        let dir = wal_test_dir("outbox-compact");
+        let _ = std::fs::remove_dir_all(&dir);
+        let outbox = ReplicationOutbox::open(dir.to_str().unwrap()).unwrap();
+        let total = OUTBOX_COMPACT_AFTER + 10;
+        for id in 1..=total {
+            outbox.push(&invalidated(id)).unwrap();
+        }
+
+        // Acknowledging a long prefix drops it while the backlog stays readable
+        outbox.ack(OUTBOX_COMPACT_AFTER + 2).unwrap();
+        let seqs = |pending: Vec<(u64, ReplicationEvent)>| {
+            pending.into_iter().map(|(seq, _)| seq).collect::<Vec<_>>()
+        };
+        let first = OUTBOX_COMPACT_AFTER + 3;
+        assert_eq!(
+            seqs(outbox.pending(outbox.acked_seq(), 3).unwrap()),
+            vec![first, first + 1, first + 2]
+        );
+        assert_eq!(outbox.status().backlog, 8);
+        assert!(outbox.status().oldest_unacked_at.is_some());
+
+        // New entries land after the compacted tail, also after a restart
+        outbox.push(&invalidated(total + 1)).unwrap();
+        drop(outbox);
+        let outbox = ReplicationOutbox::open(dir.to_str().unwrap()).unwrap();
+        let pending = outbox.pending(total - 1, 10).unwrap();
+        assert_eq!(seqs(pending), vec![total, total + 1]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_outbox_numbering_survives_compaction() {
+        let dir = wal_test_dir("outbox-marks");
+        let _ = std::fs::remove_dir_all(&dir);
+        let outbox = ReplicationOutbox::open(dir.to_str().unwrap()).unwrap();
+        for id in 1..=3 {
+            outbox.push(&invalidated(id)).unwrap();
+        }
+        outbox.ack(3).unwrap();
+        drop(outbox);
+
+        // The file is compacted to nothing; numbering still continues
+        assert_eq!(std::fs::read_to_string(dir.join("replication-outbox.jsonl")).unwrap(), "");
+        let outbox = ReplicationOutbox::open(dir.to_str().unwrap()).unwrap();
+        assert_eq!(outbox.push(&invalidated(4)).unwrap(), 4);
+        drop(outbox);
+
+        // A legacy bare-number ack file is still understood
+        std::fs::write(dir.join("replication-outbox.ack"), "7").unwrap();
+        let outbox = ReplicationOutbox::open(dir.to_str().unwrap()).unwrap();
+        assert_eq!((outbox.acked_seq(), outbox.last_seq()), (7, 7));
+        drop(outbox);
+
+        // A damaged ack file refuses to open rather than restarting at 1
+        std::fs::write(dir.join("replication-outbox.ack"), "").unwrap();
+        assert!(ReplicationOutbox::open(dir.to_str().unwrap()).is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_decode_inbound() {
+        let frame = |origin: &str| {
+            serde_json::json!({
+                "origin": origin,
+                "seq": 7,
+                "event": { "type": "memory_invalidated", "memory_id": 9, "reason": "stale" }
+            })
+            .to_string()
+        };
+
+        // Our own committed event acknowledges it
+        assert!(matches!(decode_inbound(&frame("node-b"), "node-b"), Some(InboundFrame::Ack(7))));
+        match decode_inbound(&frame("node-a"), "node-b") {
+            Some(InboundFrame::Event(envelope)) => {
+                assert_eq!((envelope.origin.as_str(), envelope.seq), ("node-a", 7));
+                assert!(matches!(envelope.event, ReplicationEvent::MemoryInvalidated { memory_id: 9, .. }));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(matches!(decode_inbound(r#"{"ack":12}"#, "node-b"), Some(InboundFrame::Ack(12))));
+        assert!(decode_inbound(r#"{"status":"ok"}"#, "node-b").is_none());
+    }
+
+    #[test]
+    fn test_wal_events_after() {
+        let dir = wal_test_dir("events-after");
+        let _ = std::fs::remove_dir_all(&dir);
+        let wal = WriteAheadLog::open(dir.to_str().unwrap()).unwrap();
+        for id in 1..=4 {
+            wal.append(&invalidated(id)).unwrap();
+        }
+
+        let page = wal.events_after(1, 2).unwrap().unwrap();
+        assert_eq!(page.events.iter().map(|r| r.seq).collect::<Vec<_>>(), vec![2, 3]);
+        assert_eq!(page.last_seq, 4);
+
+        // Once entries are truncated, readers behind them need a newer snapshot
+        wal.truncate_through(3).unwrap();
+        assert!(wal.events_after(2, 10).unwrap().is_none());
+        let page = wal.events_after(3, 10).unwrap().unwrap();
+        assert_eq!(page.events.len(), 1);
+        assert_eq!(page.events[0].seq, 4);
+
+        // Pages are read from the indexed offsets, which follow undone
+        // appends and survive a reopen
+        let undone = wal.append_all(&[invalidated(5), invalidated(6)]).unwrap();
+        wal.undo(undone).unwrap();
+        wal.append(&invalidated(7)).unwrap();
+        let page = wal.events_after(4, 10).unwrap().unwrap();
+        assert_eq!(page.events.iter().map(|r| r.seq).collect::<Vec<_>>(), vec![5]);
+        assert!(matches!(
+            page.events[0].event,
+            ReplicationEvent::MemoryInvalidated { memory_id: 7, .. }
+        ));
+        drop(wal);
+        let wal = WriteAheadLog::open(dir.to_str().unwrap()).unwrap();
+        wal.replay(3).unwrap();
+        let page = wal.events_after(3, 10).unwrap().unwrap();
+        assert_eq!(page.events.iter().map(|r| r.seq).collect::<Vec<_>>(), vec![4, 5]);
+        assert!(wal.events_after(5, 10).unwrap().unwrap().events.is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_bootstrap_source_parse() {
+        assert!(BootstrapSource::parse("rtdb").is_err());
+        let peer = BootstrapSource::parse("http://node-2:8100/").unwrap();
+        assert_eq!(peer.snapshot_url(), "http://node-2:8100/api/v1/replication/snapshot");
+        assert_eq!(
+            peer.events_url(5),
+            format!("http://node-2:8100/api/v1/replication/events?after=5&limit={}", BOOTSTRAP_PAGE)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bootstrap_from_peer() {
+        use axum::extract::Query;
+        use axum::http::StatusCode;
+        use axum::routing::get;
+        use std::collections::HashMap;
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        // A peer whose log was truncated through 2 right after serving its
+        // first snapshot (taken at 1), and that then logged events 3..=5
+        let snapshots_served = Arc::new(AtomicU64::new(0));
+        let served = snapshots_served.clone();
+        let app = axum::Router::new()
+            .route(
+                "/api/v1/replication/snapshot",
+                get(move || {
+                    let served = served.clone();
+                    async move {
+                        let mut snapshot = sample_snapshot();
+                        snapshot.wal_seq = served.fetch_add(1, Ordering::SeqCst) + 1;
+                        encode_snapshot(&snapshot, SnapshotFormat::Binary).unwrap()
+                    }
+                }),
+            )
+            .route(
+                "/api/v1/replication/events",
+                get(|Query(q): Query<HashMap<String, u64>>| async move {
+                    let after = q["after"];
+                    if after < 2 {
+                        return Err(StatusCode::GONE);
+                    }
+                    let events = (after + 1..=5)
+                        .take(2)
+                        .map(|seq| WalRecord { seq, event: invalidated(seq) })
+                        .collect();
+                    Ok(axum::Json(EventPage { events, last_seq: 5 }))
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let source = BootstrapSource::parse(&format!("http://{}", addr)).unwrap();
+        let catch_up = bootstrap(&source).await.unwrap();
+
+        assert_eq!(snapshots_served.load(Ordering::SeqCst), 2);
+        assert_eq!(catch_up.snapshot.wal_seq, 2);
+        assert_eq!(catch_up.snapshot.memories[0].id, 7);
+        let replayed: Vec<u64> = catch_up
+            .events
+            .iter()
+            .map(|e| match e {
+                ReplicationEvent::MemoryInvalidated { memory_id, .. } => *memory_id,
+                other => panic!("unexpected event {:?}", other),
+            })
+            .collect();
+        assert_eq!(replayed, vec![3, 4, 5]);
+    }
+
     #[test]
     fn test_replication_event_serialize() {
         let evt = ReplicationEvent::MemoryAdded {
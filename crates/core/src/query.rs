@@ -0,0 +1,597 @@
+//! Search query language.
+//!
+//! ```text
+//! "connection pool" AND NOT postgres tag:infra type:procedural
+//! ```
+//!
+//! Searches only use it when they ask to (`structured`); otherwise the text is
+//! read as plain words with [`Query::words`].
+//!
+//! - Bare words are matched against memory content and tags. Juxtaposed bare
+//!   words are alternatives — at least one has to match — so plain natural
+//!   language queries behave like a bag of words.
+//! - `"quoted phrases"` must appear verbatim (as consecutive words) in the content.
+//! - `AND`, `OR` and `NOT` (uppercase) combine clauses; parentheses group them.
+//!   Every clause other than a bare word is required.
+//! - `tag:`, `type:`, `agent:`, `user:` and `session:` filter on memory fields.
+//!   Values can be quoted: `tag:"on call"`.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::memory_engine::tokenize;
+use crate::types::{Memory, MemoryType};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryField {
+    Tag,
+    Type,
+    Agent,
+    User,
+    Session,
+}
+
+impl QueryField {
+    fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "tag" => Some(QueryField::Tag),
+            "type" => Some(QueryField::Type),
+            "agent" => Some(QueryField::Agent),
+            "user" => Some(QueryField::User),
+            "session" => Some(QueryField::Session),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryNode {
+    /// A single normalised word (see `tokenize`)
+    Term(String),
+    /// Consecutive normalised words
+    Phrase(Vec<String>),
+    Field(QueryField, String),
+    MemoryType(MemoryType),
+    Not(Box<QueryNode>),
+    And(Vec<QueryNode>),
+    Or(Vec<QueryNode>),
+}
+
+/// A query that failed to parse, with the byte offset of the problem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryError {
+    pub message: String,
+    pub position: usize,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid query at position {}: {}", self.position, self.message)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+fn error(message: impl Into<String>, position: usize) -> QueryError {
+    QueryError { message: message.into(), position }
+}
+
+/// A parsed search query. An empty query has no root and matches nothing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    pub root: Option<QueryNode>,
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Self, QueryError> {
+        let tokens = lex(input)?;
+        let mut parser = Parser { tokens, pos: 0, end: input.len(), depth: 0 };
+        let root = parser.parse_or()?;
+        if let Some((token, position)) = parser.tokens.get(parser.pos) {
+            return Err(match token {
+                Token::RParen => error("unmatched ')'", *position),
+                _ => error("unexpected input", *position),
+            });
+        }
+        Ok(Query { root })
+    }
+
+    /// Treat `input` as a bag of words: no operators, phrases or filters, so
+    /// any text is accepted.
+    pub fn words(input: &str) -> Self {
+        let words = tokenize(input).into_iter().map(QueryNode::Term).collect();
+        Query { root: combine(words, QueryNode::Or) }
+    }
+
+    /// Words that contribute to ranking (terms and phrase words outside `NOT`).
+    pub fn scoring_terms(&self) -> Vec<String> {
+        let mut terms = self.positive_words();
+        terms.sort();
+        terms.dedup();
+        terms
+    }
+
+    /// The query's words without operators or filters, for embedding.
+    pub fn semantic_text(&self) -> String {
+        self.positive_words().join(" ")
+    }
+
+    fn positive_words(&self) -> Vec<String> {
+        let mut terms = Vec::new();
+        if let Some(root) = &self.root {
+            collect_terms(root, &mut terms);
+        }
+        terms
+    }
+
+    /// Whether every match necessarily contains one of the scoring terms.
+    ///
+    /// If not (pure filters, `NOT`, or an `OR` branch without words), candidates
+    /// can't be drawn from the inverted index alone and every memory is checked.
+    pub fn needs_full_scan(&self) -> bool {
+        self.root.as_ref().is_none_or(node_needs_full_scan)
+    }
+
+    /// Evaluate the query against a memory.
+    pub fn matches(&self, memory: &Memory) -> bool {
+        self.evaluate_memory(memory, false)
+    }
+
+    /// Like [`matches`](Self::matches), but bare words count as satisfied.
+    ///
+    /// Used for semantic (vector-only) hits, which are relevant without sharing
+    /// words with the query. Phrases, filters and `NOT` still apply.
+    pub fn matches_semantic(&self, memory: &Memory) -> bool {
+        self.evaluate_memory(memory, true)
+    }
+
+    fn evaluate_memory(&self, memory: &Memory, relaxed: bool) -> bool {
+        let Some(root) = &self.root else {
+            return false;
+        };
+        let content = tokenize(&memory.content);
+        let mut words: HashSet<&str> = content.iter().map(String::as_str).collect();
+        let tag_words: Vec<String> = memory.tags.iter().flat_map(|t| tokenize(t)).collect();
+        words.extend(tag_words.iter().map(String::as_str));
+        evaluate(root, memory, &content, &words, relaxed)
+    }
+}
+
+fn collect_terms(node: &QueryNode, terms: &mut Vec<String>) {
+    match node {
+        QueryNode::Term(t) => terms.push(t.clone()),
+        QueryNode::Phrase(words) => terms.extend(words.iter().cloned()),
+        QueryNode::And(children) | QueryNode::Or(children) => {
+            children.iter().for_each(|c| collect_terms(c, terms))
+        }
+        QueryNode::Field(..) | QueryNode::MemoryType(_) | QueryNode::Not(_) => {}
+    }
+}
+
+fn node_needs_full_scan(node: &QueryNode) -> bool {
+    match node {
+        QueryNode::Term(_) | QueryNode::Phrase(_) => false,
+        QueryNode::Field(..) | QueryNode::MemoryType(_) | QueryNode::Not(_) => true,
+        QueryNode::And(children) => children.iter().all(node_needs_full_scan),
+        QueryNode::Or(children) => children.iter().any(node_needs_full_scan),
+    }
+}
+
+/// With `relaxed`, bare words outside `NOT` are treated as matching.
+fn evaluate(
+    node: &QueryNode,
+    memory: &Memory,
+    content: &[String],
+    words: &HashSet<&str>,
+    relaxed: bool,
+) -> bool {
+    match node {
+        QueryNode::Term(t) => relaxed || words.contains(t.as_str()),
+        QueryNode::Phrase(phrase) => {
+            !phrase.is_empty() && content.windows(phrase.len()).any(|w| w == phrase.as_slice())
+        }
+        QueryNode::MemoryType(t) => memory.memory_type == *t,
+        QueryNode::Field(field, value) => match field {
+            QueryField::Tag => memory.tags.iter().any(|t| t.eq_ignore_ascii_case(value)),
+            QueryField::Agent => memory.agent_id.as_deref() == Some(value.as_str()),
+            QueryField::User => memory.user_id.as_deref() == Some(value.as_str()),
+            QueryField::Session => memory.session_id.as_deref() == Some(value.as_str()),
+            // Resolved to `QueryNode::MemoryType` while parsing
+            QueryField::Type => false,
+        },
+        QueryNode::Not(inner) => !evaluate(inner, memory, content, words, false),
+        QueryNode::And(children) => children
+            .iter()
+            .all(|c| evaluate(c, memory, content, words, relaxed)),
+        QueryNode::Or(children) => children
+            .iter()
+            .any(|c| evaluate(c, memory, content, words, relaxed)),
+    }
+}
+
+// ============================================================================
+// Lexer
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Phrase(String),
+    Field(QueryField, String),
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+}
+
+fn lex(input: &str) -> Result<Vec<(Token, usize)>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '(' {
+            chars.next();
+            tokens.push((Token::LParen, start));
+        } else if c == ')' {
+            chars.next();
+            tokens.push((Token::RParen, start));
+        } else if c == '"' {
+            chars.next();
+            tokens.push((Token::Phrase(read_quoted(&mut chars, start)?), start));
+        } else {
+            let mut word = String::new();
+            while let Some(&(_, c)) = chars.peek() {
+                if c.is_whitespace() || c == '(' || c == ')' || c == '"' {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+
+            let field = word.split_once(':').and_then(|(name, _)| QueryField::parse(name));
+            let token = match (word.as_str(), field) {
+                ("AND", _) => Token::And,
+                ("OR", _) => Token::Or,
+                ("NOT", _) => Token::Not,
+                (_, Some(field)) => {
+                    let (name, value) = word.split_once(':').unwrap_or((&word, ""));
+                    let value = if value.is_empty() && matches!(chars.peek(), Some((_, '"'))) {
+                        let (quote, _) = chars.next().unwrap();
+                        read_quoted(&mut chars, quote)?
+                    } else {
+                        value.to_string()
+                    };
+                    if value.trim().is_empty() {
+                        return Err(error(format!("missing value for '{}:'", name), start));
+                    }
+                    Token::Field(field, value)
+                }
+                // Unknown prefixes (URLs, "note:") are ordinary words
+                _ => Token::Word(word),
+            };
+            tokens.push((token, start));
+        }
+    }
+    Ok(tokens)
+}
+
+/// Read up to the closing quote (the opening one is already consumed).
+fn read_quoted(
+    chars: &mut std::iter::Peekable<std::str::CharIndices<'_>>,
+    start: usize,
+) -> Result<String, QueryError> {
+    let mut text = String::new();
+    for (_, c) in chars.by_ref() {
+        if c == '"' {
+            return Ok(text);
+        }
+        text.push(c);
+    }
+    Err(error("unterminated quote", start))
+}
+
+// ============================================================================
+// Parser
+// ============================================================================
+
+/// Recursive descent, loosest binding first:
+///
+/// ```text
+/// or      := group ("OR" group)*
+/// group   := conj+                 -- juxtaposition
+/// conj    := unary ("AND" unary)*
+/// unary   := "NOT" unary | primary
+/// primary := "(" or ")" | phrase | field | word
+/// ```
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    end: usize,
+    /// Open parentheses and `NOT`s around the current position
+    depth: usize,
+}
+
+/// Deepest nesting of parentheses and `NOT`s a query may use. Parsing and
+/// evaluation recurse once per level, so this bounds their stack use.
+const MAX_DEPTH: usize = 64;
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens.get(self.pos).map(|(_, p)| *p).unwrap_or(self.end)
+    }
+
+    fn descend(&mut self, position: usize) -> Result<(), QueryError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(error(format!("query is nested more than {} levels deep", MAX_DEPTH), position));
+        }
+        Ok(())
+    }
+
+    fn parse_or(&mut self) -> Result<Option<QueryNode>, QueryError> {
+        let mut branches = Vec::new();
+        loop {
+            if self.peek() == Some(&Token::Or) {
+                return Err(error("'OR' needs a clause on both sides", self.position()));
+            }
+            // A group of only unsearchable words (e.g. "a") drops out
+            branches.extend(self.parse_group()?);
+            if self.peek() != Some(&Token::Or) {
+                break;
+            }
+            let position = self.position();
+            self.pos += 1;
+            if matches!(self.peek(), None | Some(Token::RParen)) {
+                return Err(error("'OR' needs a clause on both sides", position));
+            }
+        }
+        Ok(combine(branches, QueryNode::Or))
+    }
+
+    fn parse_group(&mut self) -> Result<Option<QueryNode>, QueryError> {
+        let mut optional = Vec::new();
+        let mut required = Vec::new();
+        while !matches!(self.peek(), None | Some(Token::Or) | Some(Token::RParen)) {
+            if self.peek() == Some(&Token::And) {
+                return Err(error("'AND' needs a clause on both sides", self.position()));
+            }
+            let (node, is_bare_word) = self.parse_conjunction()?;
+            match node {
+                Some(node) if is_bare_word => optional.push(node),
+                Some(node) => required.push(node),
+                None => {}
+            }
+        }
+        if let Some(any_word) = combine(optional, QueryNode::Or) {
+            required.push(any_word);
+        }
+        Ok(combine(required, QueryNode::And))
+    }
+
+    /// Returns the clause and whether it is a single bare word.
+    fn parse_conjunction(&mut self) -> Result<(Option<QueryNode>, bool), QueryError> {
+        let first_is_word = matches!(self.peek(), Some(Token::Word(_)));
+        let mut operands = vec![self.parse_unary()?];
+        while self.peek() == Some(&Token::And) {
+            let position = self.position();
+            self.pos += 1;
+            if matches!(self.peek(), None | Some(Token::Or) | Some(Token::RParen) | Some(Token::And)) {
+                return Err(error("'AND' needs a clause on both sides", position));
+            }
+            operands.push(self.parse_unary()?);
+        }
+        let is_bare_word = first_is_word && operands.len() == 1;
+        Ok((combine(operands.into_iter().flatten().collect(), QueryNode::And), is_bare_word))
+    }
+
+    fn parse_unary(&mut self) -> Result<Option<QueryNode>, QueryError> {
+        if self.peek() == Some(&Token::Not) {
+            let position = self.position();
+            self.pos += 1;
+            if matches!(self.peek(), None | Some(Token::Or) | Some(Token::And) | Some(Token::RParen)) {
+                return Err(error("'NOT' needs a clause after it", position));
+            }
+            self.descend(position)?;
+            let inner = self.parse_unary()?;
+            self.depth -= 1;
+            return Ok(inner.map(|inner| QueryNode::Not(Box::new(inner))));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Option<QueryNode>, QueryError> {
+        let Some((token, position)) = self.tokens.get(self.pos).cloned() else {
+            return Err(error("unexpected end of query", self.end));
+        };
+        self.pos += 1;
+        match token {
+            Token::LParen => {
+                self.descend(position)?;
+                let inner = self.parse_or()?;
+                if self.peek() != Some(&Token::RParen) {
+                    return Err(error("unclosed '('", position));
+                }
+                self.pos += 1;
+                self.depth -= 1;
+                Ok(inner)
+            }
+            Token::Phrase(text) => {
+                let words = tokenize(&text);
+                Ok(match words.len() {
+                    0 => None,
+                    1 => Some(QueryNode::Term(words.into_iter().next().unwrap())),
+                    _ => Some(QueryNode::Phrase(words)),
+                })
+            }
+            Token::Word(text) => {
+                let words = tokenize(&text);
+                Ok(match words.len() {
+                    0 => None,
+                    1 => Some(QueryNode::Term(words.into_iter().next().unwrap())),
+                    // "connection-pool" → adjacent words
+                    _ => Some(QueryNode::Phrase(words)),
+                })
+            }
+            Token::Field(QueryField::Type, value) => parse_memory_type(&value)
+                .map(|t| Some(QueryNode::MemoryType(t)))
+                .ok_or_else(|| {
+                    error(
+                        format!(
+                            "unknown memory type '{}' (expected fact, episodic, procedural or semantic)",
+                            value
+                        ),
+                        position,
+                    )
+                }),
+            Token::Field(field, value) => Ok(Some(QueryNode::Field(field, value))),
+            Token::RParen => Err(error("unmatched ')'", position)),
+            Token::And | Token::Or | Token::Not => Err(error("unexpected operator", position)),
+        }
+    }
+}
+
+fn combine(mut nodes: Vec<QueryNode>, wrap: fn(Vec<QueryNode>) -> QueryNode) -> Option<QueryNode> {
+    match nodes.len() {
+        0 => None,
+        1 => nodes.pop(),
+        _ => Some(wrap(nodes)),
+    }
+}
+
+fn parse_memory_type(value: &str) -> Option<MemoryType> {
+    match value.to_ascii_lowercase().as_str() {
+        "fact" => Some(MemoryType::Fact),
+        "episodic" => Some(MemoryType::Episodic),
+        "procedural" => Some(MemoryType::Procedural),
+        "semantic" => Some(MemoryType::Semantic),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn memory(content: &str, memory_type: MemoryType, tags: &[&str]) -> Memory {
+        let now = Utc::now();
+        Memory {
+            id: 1,
+            content: content.into(),
+            memory_type,
+            agent_id: Some("agent-1".into()),
+            user_id: None,
+            session_id: None,
+            confidence: 1.0,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            created_at: now,
+            updated_at: now,
+            valid_from: now,
+            valid_until: None,
+            source: "test".into(),
+            metadata: serde_json::Value::Null,
+        }
+    }
+
+    fn term(t: &str) -> QueryNode {
+        QueryNode::Term(t.into())
+    }
+
+    #[test]
+    fn test_parse_bare_words_are_alternatives() {
+        let q = Query::parse("dark mode").unwrap();
+        assert_eq!(q.root, Some(QueryNode::Or(vec![term("dark"), term("mode")])));
+        assert!(!q.needs_full_scan());
+    }
+
+    #[test]
+    fn test_parse_full_example() {
+        let q = Query::parse("\"connection pool\" AND NOT postgres tag:infra type:procedural").unwrap();
+        assert_eq!(
+            q.root,
+            Some(QueryNode::And(vec![
+                QueryNode::And(vec![
+                    QueryNode::Phrase(vec!["connection".into(), "pool".into()]),
+                    QueryNode::Not(Box::new(term("postgres"))),
+                ]),
+                QueryNode::Field(QueryField::Tag, "infra".into()),
+                QueryNode::MemoryType(MemoryType::Procedural),
+            ]))
+        );
+        assert_eq!(q.scoring_terms(), vec!["connection", "pool"]);
+
+        let hit = memory("Raise the connection pool size", MemoryType::Procedural, &["infra"]);
+        assert!(q.matches(&hit));
+        let pg = memory("Raise the connection pool size for postgres", MemoryType::Procedural, &["infra"]);
+        assert!(!q.matches(&pg));
+        let split = memory("The pool has no connection", MemoryType::Procedural, &["infra"]);
+        assert!(!q.matches(&split));
+        let wrong_type = memory("Raise the connection pool size", MemoryType::Fact, &["infra"]);
+        assert!(!q.matches(&wrong_type));
+    }
+
+    #[test]
+    fn test_or_and_grouping() {
+        let q = Query::parse("(redis OR memcached) agent:agent-1").unwrap();
+        assert!(q.matches(&memory("memcached eviction", MemoryType::Fact, &[])));
+        assert!(!q.matches(&memory("postgres tuning", MemoryType::Fact, &[])));
+
+        // A branch without words forces a full scan
+        assert!(Query::parse("redis OR tag:cache").unwrap().needs_full_scan());
+        assert!(!Query::parse("redis tag:cache").unwrap().needs_full_scan());
+        assert!(Query::parse("tag:\"on call\"").unwrap().needs_full_scan());
+    }
+
+    #[test]
+    fn test_plain_words_ignore_syntax() {
+        let q = Query::words("Why does (the \"cache NOT evict tag:infra");
+        assert_eq!(q, Query::parse("why does the cache not evict tag infra").unwrap());
+        assert!(q.matches(&memory("Cache eviction is NOT broken", MemoryType::Fact, &[])));
+        assert!(Query::words("a").root.is_none());
+    }
+
+    #[test]
+    fn test_deep_nesting_is_rejected() {
+        let nested = |depth: usize| format!("{}redis{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(Query::parse(&nested(MAX_DEPTH)).is_ok());
+        assert!(Query::parse(&format!("{}redis", "NOT ".repeat(MAX_DEPTH))).is_ok());
+
+        let err = Query::parse(&nested(10_000)).unwrap_err();
+        assert_eq!(err.message, "query is nested more than 64 levels deep");
+        assert_eq!(err.position, MAX_DEPTH);
+        let err = Query::parse(&format!("{}redis", "NOT ".repeat(10_000))).unwrap_err();
+        assert_eq!(err.position, MAX_DEPTH * 4);
+    }
+
+    #[test]
+    fn test_unknown_prefix_is_a_word() {
+        let q = Query::parse("see https://example.com").unwrap();
+        assert!(q.scoring_terms().contains(&"example".to_string()));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let cases = [
+            ("\"unterminated", "unterminated quote", 0),
+            ("(redis", "unclosed '('", 0),
+            ("redis)", "unmatched ')'", 5),
+            ("redis AND", "'AND' needs a clause on both sides", 6),
+            ("OR redis", "'OR' needs a clause on both sides", 0),
+            ("redis NOT", "'NOT' needs a clause after it", 6),
+            ("tag:", "missing value for 'tag:'", 0),
+            ("type:banana", "unknown memory type 'banana' (expected fact, episodic, procedural or semantic)", 0),
+        ];
+        for (input, message, position) in cases {
+            let err = Query::parse(input).unwrap_err();
+            assert_eq!(err.message, message, "{}", input);
+            assert_eq!(err.position, position, "{}", input);
+        }
+    }
+}
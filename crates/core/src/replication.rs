@@ -0,0 +1,473 @@
+//! Inbound replication.
+//!
+//! Applies events other nodes committed through RaftTimeDB to the local
+//! stores and announces them on the local WebSocket channels, so agents
+//! connected to any node see the same pushes. Events are applied without
+//! being replicated again; the node's own writes are dropped before they
+//! get here (see `persistence::decode_inbound`), and events a peer resends
+//! after a reconnect are skipped by their outbox sequence number, which is
+//! logged with each event so it survives a restart. Events from a node that
+//! mints IDs from a range another node already uses are refused.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::{mpsc, watch};
+use tracing::{debug, error, info, warn};
+
+use crate::channels::ChannelHub;
+use crate::memory_engine::MemoryEngine;
+use crate::persistence::{ReplicationEnvelope, ReplicationEvent};
+use crate::types::*;
+
+/// Apply one event from another node and broadcast it locally.
+pub fn apply_inbound(engine: &MemoryEngine, channels: &ChannelHub, envelope: ReplicationEnvelope) {
+    let receipt = (envelope.seq != 0).then_some(ReplicationEvent::PeerEventApplied {
+        origin: envelope.origin,
+        seq: envelope.seq,
+    });
+    let event = envelope.event;
+    // Reassignment notifications name the agent that lost the task, which
+    // only the state before the event knows.
+    let previous_agent = match &event {
+        ReplicationEvent::TaskReassigned { task } => engine
+            .get_task(task.id)
+            .and_then(|t| t.assigned_agent)
+            .filter(|agent| task.assigned_agent.as_ref() != Some(agent)),
+        _ => None,
+    };
+    let notification = notification_for(&event, previous_agent);
+
+    // Channel state lives in the hub, so it is logged here rather than by
+    // the engine's apply_inbound_event.
+    match event {
+        ReplicationEvent::ChannelCreated { channel } => {
+            let logged = ReplicationEvent::ChannelCreated { channel: channel.clone() };
+            engine.commit_inbound(logged, receipt, || channels.apply_channel_created(channel))
+        }
+        ReplicationEvent::ChannelSubscribed { channel_id, agent_id } => {
+            let logged = ReplicationEvent::ChannelSubscribed { channel_id, agent_id: agent_id.clone() };
+            engine.commit_inbound(logged, receipt, || channels.apply_channel_subscribed(channel_id, &agent_id))
+        }
+        // Liveness isn't state, so heartbeats aren't logged
+        ReplicationEvent::NodeHeartbeat { node_number } => engine.record_node_heartbeat(node_number),
+        ReplicationEvent::MemoryInvalidated {
+            memory_id,
+            reason,
+            invalidated_at,
+        } => {
+            engine.apply_inbound_event(ReplicationEvent::MemoryInvalidated {
+                memory_id,
+                reason: reason.clone(),
+                invalidated_at,
+            }, receipt);
+            if let Some(user_id) = engine.get_memory(memory_id).and_then(|m| m.user_id) {
+                let channel_name = format!("user:{}", user_id);
+                channels.broadcast_to_channel_by_name(
+                    &channel_name,
+                    WsServerMessage::MemoryInvalidated {
+                        channel: channel_name.clone(),
+                        memory_id,
+                        reason,
+                    },
+                );
+            }
+        }
+        other => engine.apply_inbound_event(other, receipt),
+    }
+
+    match notification {
+        Some(Notification::Task(msg)) => channels.broadcast_to_channel_by_name("tasks", msg),
+        Some(Notification::MemoryAdded(memory)) => {
+            if let Some(ref user_id) = memory.user_id {
+                let channel_name = format!("user:{}", user_id);
+                channels.broadcast_to_channel_by_name(
+                    &channel_name,
+                    WsServerMessage::MemoryAdded {
+                        channel: channel_name.clone(),
+                        memory: memory.clone(),
+                    },
+                );
+            }
+            channels.broadcast_to_channel_by_name(
+                "global",
+                WsServerMessage::MemoryAdded {
+                    channel: "global".into(),
+                    memory,
+                },
+            );
+        }
+        Some(Notification::MemoryUpdated(memory)) => {
+            if let Some(ref user_id) = memory.user_id {
+                let channel_name = format!("user:{}", user_id);
+                channels.broadcast_to_channel_by_name(
+                    &channel_name,
+                    WsServerMessage::MemoryUpdated {
+                        channel: channel_name.clone(),
+                        memory,
+                    },
+                );
+            }
+        }
+        None => {}
+    }
+}
+
+/// What to push locally for an inbound event, mirroring the REST handlers.
+enum Notification {
+    MemoryAdded(Memory),
+    MemoryUpdated(Memory),
+    Task(WsServerMessage),
+}
+
+fn notification_for(event: &ReplicationEvent, previous_agent: Option<String>) -> Option<Notification> {
+    let task_msg = |msg: WsServerMessage| Some(Notification::Task(msg));
+    match event {
+        ReplicationEvent::MemoryAdded { memory } => Some(Notification::MemoryAdded(memory.clone())),
+        ReplicationEvent::MemoryUpdated { memory } => {
+            Some(Notification::MemoryUpdated(memory.clone()))
+        }
+        ReplicationEvent::TaskCreated { task } => {
+            task_msg(WsServerMessage::TaskCreated { task: task.clone() })
+        }
+        ReplicationEvent::TaskClaimed { task } => {
+            task_msg(WsServerMessage::TaskClaimed { task: task.clone() })
+        }
+        ReplicationEvent::TaskStarted { task } => {
+            task_msg(WsServerMessage::TaskUpdated { task: task.clone() })
+        }
+        ReplicationEvent::TaskCompleted { task } => {
+            task_msg(WsServerMessage::TaskCompleted { task: task.clone() })
+        }
+        ReplicationEvent::TaskFailed { task } => {
+            task_msg(WsServerMessage::TaskFailed { task: task.clone() })
+        }
+        ReplicationEvent::TaskCancelled { task } => {
+            task_msg(WsServerMessage::TaskCancelled { task: task.clone() })
+        }
+        ReplicationEvent::TaskReassigned { task } => task_msg(WsServerMessage::TaskReassigned {
+            task: task.clone(),
+            previous_agent,
+        }),
+        ReplicationEvent::TaskUnblocked { task } => {
+            task_msg(WsServerMessage::TaskUnblocked { task: task.clone() })
+        }
+        ReplicationEvent::TaskUpdated { task } => {
+            task_msg(WsServerMessage::TaskUpdated { task: task.clone() })
+        }
+        _ => None,
+    }
+}
+
+/// Whether a peer already delivered this event: its sequence number is no
+/// higher than the last one applied from that origin. Unsequenced events
+/// always apply.
+fn is_resend(engine: &MemoryEngine, envelope: &ReplicationEnvelope) -> bool {
+    envelope.seq != 0 && envelope.seq <= engine.peer_seq(&envelope.origin)
+}
+
+/// The other node already minting IDs from the envelope's node number, if
+/// any. `owners` maps node numbers to the first origin seen using them
+/// (and this node's own number to itself). Older nodes send no number.
+fn node_number_owner(owners: &mut HashMap<u16, String>, envelope: &ReplicationEnvelope) -> Option<String> {
+    if envelope.node_number == 0 {
+        return None;
+    }
+    let owner = owners
+        .entry(envelope.node_number)
+        .or_insert_with(|| envelope.origin.clone());
+    (*owner != envelope.origin).then(|| owner.clone())
+}
+
+/// Apply inbound events as the replication client receives them.
+pub async fn inbound_loop(
+    engine: Arc<MemoryEngine>,
+    channels: Arc<ChannelHub>,
+    mut rx: mpsc::UnboundedReceiver<ReplicationEnvelope>,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    info!("Applying replicated events from other nodes");
+    let config = engine.config();
+    let mut owners = HashMap::from([(config.node_number, config.node_id.clone())]);
+    loop {
+        tokio::select! {
+            envelope = rx.recv() => match envelope {
+                Some(envelope) => {
+                    if let Some(owner) = node_number_owner(&mut owners, &envelope) {
+                        error!(
+                            origin = %envelope.origin,
+                            node_number = envelope.node_number,
+                            owner = %owner,
+                            "Refusing replicated event from a node using another node's number; \
+                             both would mint the same IDs, give each node its own --node-number"
+                        );
+                        continue;
+                    }
+                    if is_resend(&engine, &envelope) {
+                        debug!(origin = %envelope.origin, seq = envelope.seq, "Skipping resent replication event");
+                        continue;
+                    }
+                    debug!(origin = %envelope.origin, seq = envelope.seq, "Applying replicated event");
+                    apply_inbound(&engine, &channels, envelope);
+                }
+                None => break,
+            },
+            _ = shutdown.changed() => break,
+        }
+    }
+}
+
+/// How often to tell the other nodes this one is alive, well within
+/// [`NODE_LIVENESS_SECS`](crate::memory_engine::NODE_LIVENESS_SECS).
+pub const NODE_HEARTBEAT_SECS: u64 = 10;
+
+/// Periodically announce this node, so the others know it can run the
+/// task sweeps (see `MemoryEngine::is_maintainer`).
+pub async fn node_heartbeat_loop(
+    engine: Arc<MemoryEngine>,
+    interval_secs: u64,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let interval = std::time::Duration::from_secs(interval_secs);
+
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {
+                if let Err(e) = engine.send_node_heartbeat() {
+                    warn!(error = %e, "Failed to queue node heartbeat");
+                }
+            }
+            _ = shutdown.changed() => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{HiveMindConfig, OverdueAction};
+    use crate::persistence::{ReplicationOutbox, WriteAheadLog};
+    use crate::vector_index::VectorIndexConfig;
+
+    fn test_config() -> HiveMindConfig {
+        HiveMindConfig {
+            listen_addr: "0.0.0.0:8100".into(),
+            rtdb_url: "ws://localhost:3001".into(),
+            llm_provider: "test".into(),
+            llm_api_key: None,
+            llm_model: "test".into(),
+            embedding_model: "none:disabled".into(),
+            embedding_api_key: None,
+            data_dir: "/tmp/hivemind-test".into(),
+            snapshot_interval: 60,
+            replication_enabled: true,
+            node_id: "node-b".into(),
+            node_number: 0,
+            bm25_k1: 1.2,
+            bm25_b: 0.75,
+            vector_index: VectorIndexConfig::default(),
+            entity_name_matching: true,
+            task_lease_secs: 300,
+            task_max_attempts: 3,
+            task_overdue_action: OverdueAction::Notify,
+            task_rollup_policy: RollupPolicy::FailFast,
+        }
+    }
+
+    fn channel(hub: &ChannelHub, name: &str) -> tokio::sync::broadcast::Receiver<WsServerMessage> {
+        hub.create_channel(CreateChannelRequest {
+            name: name.into(),
+            description: None,
+            channel_type: ChannelType::Public,
+            created_by: "system".into(),
+        }).unwrap();
+        hub.subscribe_by_name(name, "observer").unwrap()
+    }
+
+    fn search(query: &str) -> SearchRequest {
+        SearchRequest {
+            query: query.into(),
+            agent_id: None,
+            user_id: None,
+            tags: vec![],
+            limit: 10,
+            include_graph: false,
+            structured: false,
+        }
+    }
+
+    /// An unsequenced event from node-a.
+    fn from_peer(event: ReplicationEvent) -> ReplicationEnvelope {
+        ReplicationEnvelope {
+            origin: "node-a".into(),
+            node_number: 1,
+            seq: 0,
+            event,
+        }
+    }
+
+    #[test]
+    fn test_is_resend() {
+        let envelope = |origin: &str, seq| ReplicationEnvelope {
+            origin: origin.into(),
+            seq,
+            ..from_peer(ReplicationEvent::MemoryInvalidated {
+                memory_id: 1,
+                reason: "test".into(),
+                invalidated_at: None,
+            })
+        };
+        let dir = std::env::temp_dir().join(format!("hivemind-resend-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let open = || {
+            let wal = Arc::new(WriteAheadLog::open(dir.to_str().unwrap()).unwrap());
+            let mut engine = MemoryEngine::new(test_config());
+            engine.set_wal(wal.clone());
+            (engine, wal)
+        };
+        let (engine, _) = open();
+        let hub = ChannelHub::new();
+        for seq in [1, 2] {
+            assert!(!is_resend(&engine, &envelope("node-a", seq)));
+            apply_inbound(&engine, &hub, envelope("node-a", seq));
+        }
+        // node-a reconnected and resent what it hadn't seen acknowledged
+        assert!(is_resend(&engine, &envelope("node-a", 2)));
+        assert!(!is_resend(&engine, &envelope("node-a", 3)));
+        // Sequences are per origin
+        assert!(!is_resend(&engine, &envelope("node-c", 1)));
+        assert!(!is_resend(&engine, &envelope("node-c", 0)));
+        apply_inbound(&engine, &hub, envelope("node-c", 0));
+        assert!(!is_resend(&engine, &envelope("node-c", 0)));
+
+        // What was applied is remembered after a restart, whether it is
+        // replayed from the log or restored from a snapshot
+        let snapshot = engine.create_snapshot();
+        drop(engine);
+        let (restarted, wal) = open();
+        for event in wal.replay(0).unwrap() {
+            restarted.apply_replication_event(event);
+        }
+        assert!(is_resend(&restarted, &envelope("node-a", 2)));
+        assert!(!is_resend(&restarted, &envelope("node-a", 3)));
+        let mut restored = MemoryEngine::new(test_config());
+        restored.restore_from_snapshot(snapshot);
+        assert!(is_resend(&restored, &envelope("node-a", 2)));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_node_number_owner() {
+        let envelope = |origin: &str, node_number| ReplicationEnvelope {
+            origin: origin.into(),
+            node_number,
+            ..from_peer(ReplicationEvent::NodeHeartbeat { node_number })
+        };
+        let mut owners = HashMap::from([(3, "node-b".to_string())]);
+
+        assert_eq!(node_number_owner(&mut owners, &envelope("node-a", 1)), None);
+        assert_eq!(node_number_owner(&mut owners, &envelope("node-a", 1)), None);
+        // Another node whose number collides with node-a's or this node's
+        assert_eq!(node_number_owner(&mut owners, &envelope("node-c", 1)).as_deref(), Some("node-a"));
+        assert_eq!(node_number_owner(&mut owners, &envelope("node-c", 3)).as_deref(), Some("node-b"));
+        // Older nodes don't send a number
+        assert_eq!(node_number_owner(&mut owners, &envelope("node-c", 0)), None);
+    }
+
+    #[test]
+    fn test_apply_inbound_memory() {
+        // A memory written on node A...
+        let origin = MemoryEngine::new(test_config());
+        let memory = origin.add_memory(AddMemoryRequest {
+            content: "Deploys happen on Fridays".into(),
+            memory_type: MemoryType::Fact,
+            agent_id: Some("agent-1".into()),
+            user_id: Some("ludde".into()),
+            session_id: None,
+            tags: vec![],
+            metadata: serde_json::Value::Null,
+        }).unwrap();
+
+        // ...arrives on node B, which must not send it back out.
+        let dir = std::env::temp_dir().join(format!("hivemind-inbound-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let outbox = Arc::new(ReplicationOutbox::open(dir.to_str().unwrap()).unwrap());
+        let mut engine = MemoryEngine::new(test_config());
+        engine.set_replication_outbox(outbox.clone());
+        let hub = ChannelHub::new();
+        let mut global = channel(&hub, "global");
+        let mut user = channel(&hub, "user:ludde");
+
+        apply_inbound(&engine, &hub, from_peer(ReplicationEvent::MemoryAdded { memory: memory.clone() }));
+
+        assert_eq!(engine.get_memory(memory.id).unwrap().content, memory.content);
+        assert_eq!(engine.search(&search("fridays")).unwrap().len(), 1);
+        assert!(matches!(global.try_recv(), Ok(WsServerMessage::MemoryAdded { .. })));
+        assert!(matches!(user.try_recv(), Ok(WsServerMessage::MemoryAdded { .. })));
+
+        apply_inbound(
+            &engine,
+            &hub,
+            from_peer(ReplicationEvent::MemoryInvalidated {
+                memory_id: memory.id,
+                reason: "outdated".into(),
+                invalidated_at: None,
+            }),
+        );
+        assert!(engine.get_memory(memory.id).unwrap().valid_until.is_some());
+        assert!(engine.search(&search("fridays")).unwrap().is_empty());
+        assert!(matches!(user.try_recv(), Ok(WsServerMessage::MemoryInvalidated { .. })));
+
+        assert_eq!(outbox.last_seq(), 0);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_apply_inbound_task() {
+        let origin = MemoryEngine::new(test_config());
+        let task = origin
+            .create_task(CreateTaskRequest {
+                title: "Rotate keys".into(),
+                description: String::new(),
+                priority: 0,
+                required_capabilities: vec![],
+                created_by: "user-1".into(),
+                dependencies: vec![],
+                deadline: None,
+                metadata: serde_json::Value::Null,
+                parent_id: None,
+                rollup: None,
+                template_id: None,
+            })
+            .unwrap();
+
+        let engine = MemoryEngine::new(test_config());
+        let hub = ChannelHub::new();
+        let mut tasks = channel(&hub, "tasks");
+
+        apply_inbound(&engine, &hub, from_peer(ReplicationEvent::TaskCreated { task: task.clone() }));
+        assert_eq!(engine.get_task(task.id).unwrap().title, "Rotate keys");
+        assert!(matches!(tasks.try_recv(), Ok(WsServerMessage::TaskCreated { .. })));
+
+        let claimed = Task {
+            status: TaskStatus::Claimed,
+            assigned_agent: Some("agent-1".into()),
+            ..task.clone()
+        };
+        apply_inbound(&engine, &hub, from_peer(ReplicationEvent::TaskClaimed { task: claimed.clone() }));
+
+        let requeued = Task {
+            status: TaskStatus::Pending,
+            assigned_agent: None,
+            ..claimed
+        };
+        apply_inbound(&engine, &hub, from_peer(ReplicationEvent::TaskReassigned { task: requeued }));
+        assert!(matches!(tasks.try_recv(), Ok(WsServerMessage::TaskClaimed { .. })));
+        match tasks.try_recv() {
+            Ok(WsServerMessage::TaskReassigned { previous_agent, .. }) => {
+                assert_eq!(previous_agent.as_deref(), Some("agent-1"))
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! Background task maintenance.
+//!
+//! Runs periodic sweeps over the task store (expired leases, missed
+//! deadlines, due task templates) and announces what changed on the `tasks`
+//! channel, the same way the REST handlers do.
+
+use std::sync::Arc;
+use tokio::sync::watch;
+use tracing::info;
+
+use crate::channels::ChannelHub;
+use crate::memory_engine::MemoryEngine;
+use crate::types::*;
+
+/// How often to look for tasks past their deadline.
+pub const OVERDUE_SWEEP_SECS: u64 = 15;
+
+/// How often to check task templates for a due run.
+pub const TEMPLATE_TICK_SECS: u64 = 1;
+
+/// Announce tasks changed because another task finished: dependents it
+/// unblocked or cancelled and parents its subtasks rolled up.
+pub fn announce_follow_ups(channels: &ChannelHub, tasks: Vec<Task>) {
+    for task in tasks {
+        let msg = match task.status {
+            TaskStatus::Completed => WsServerMessage::TaskCompleted { task },
+            TaskStatus::Failed => WsServerMessage::TaskFailed { task },
+            TaskStatus::Cancelled => WsServerMessage::TaskCancelled { task },
+            TaskStatus::Pending => WsServerMessage::TaskUnblocked { task },
+            _ => WsServerMessage::TaskUpdated { task },
+        };
+        channels.broadcast_to_channel_by_name("tasks", msg);
+    }
+}
+
+/// How often to look for expired leases: a quarter of the lease, 1–30 seconds.
+pub fn reaper_interval_secs(lease_secs: u64) -> u64 {
+    (lease_secs / 4).clamp(1, 30)
+}
+
+/// Periodically requeue (or fail) tasks whose lease has expired.
+pub async fn task_reaper_loop(
+    engine: Arc<MemoryEngine>,
+    channels: Arc<ChannelHub>,
+    interval_secs: u64,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let interval = std::time::Duration::from_secs(interval_secs);
+
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {
+                let (reaped, follow_ups) = engine.reap_expired_tasks();
+                if !reaped.is_empty() {
+                    info!(count = reaped.len(), "Reaped tasks with expired leases");
+                }
+                for (task, previous_agent) in reaped {
+                    let msg = if task.status == TaskStatus::Failed {
+                        WsServerMessage::TaskFailed { task }
+                    } else {
+                        WsServerMessage::TaskReassigned { task, previous_agent }
+                    };
+                    channels.broadcast_to_channel_by_name("tasks", msg);
+                }
+                announce_follow_ups(&channels, follow_ups);
+            }
+            _ = shutdown.changed() => break,
+        }
+    }
+}
+
+/// Periodically flag tasks that missed their deadline.
+pub async fn overdue_sweeper_loop(
+    engine: Arc<MemoryEngine>,
+    channels: Arc<ChannelHub>,
+    interval_secs: u64,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let interval = std::time::Duration::from_secs(interval_secs);
+
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {
+                let (flagged, follow_ups) = engine.sweep_overdue_tasks();
+                for task in flagged {
+                    if task.status == TaskStatus::Failed {
+                        channels.broadcast_to_channel_by_name(
+                            "tasks",
+                            WsServerMessage::TaskFailed { task: task.clone() },
+                        );
+                    }
+                    channels.broadcast_to_channel_by_name("tasks", WsServerMessage::TaskOverdue { task });
+                }
+                announce_follow_ups(&channels, follow_ups);
+            }
+            _ = shutdown.changed() => break,
+        }
+    }
+}
+
+/// Periodically create tasks from templates whose next run is due.
+pub async fn template_scheduler_loop(
+    engine: Arc<MemoryEngine>,
+    channels: Arc<ChannelHub>,
+    interval_secs: u64,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let interval = std::time::Duration::from_secs(interval_secs);
+
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {
+                for task in engine.run_due_templates() {
+                    channels.broadcast_to_channel_by_name("tasks", WsServerMessage::TaskCreated { task });
+                }
+            }
+            _ = shutdown.changed() => break,
+        }
+    }
+}
@@ -86,6 +86,25 @@ pub struct Relationship {
     pub metadata: serde_json::Value,
 }
 
+/// A link recording that a memory mentions an entity.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EntityMention {
+    pub memory_id: u64,
+    pub entity_id: u64,
+    pub source: MentionSource,
+    pub created_at: DateTime<Utc>,
+}
+
+/// How a mention link was discovered.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MentionSource {
+    /// Reported by the LLM extraction pipeline.
+    Extraction,
+    /// The entity's name appears in the memory content.
+    NameMatch,
+}
+
 // ============================================================================
 // Episode Types
 // ============================================================================
@@ -106,6 +125,17 @@ pub struct Episode {
     pub metadata: serde_json::Value,
 }
 
+/// An open agent session; ending it records an `Episode`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub session_id: String,
+    pub agent_id: String,
+    pub user_id: Option<String>,
+    pub started_at: DateTime<Utc>,
+    #[serde(default)]
+    pub metadata: serde_json::Value,
+}
+
 // ============================================================================
 // Channel Types
 // ============================================================================
@@ -171,6 +201,10 @@ pub struct SearchRequest {
     pub limit: usize,
     #[serde(default)]
     pub include_graph: bool,
+    /// Parse `query` with the search query language instead of treating it
+    /// as plain words
+    #[serde(default)]
+    pub structured: bool,
 }
 
 fn default_limit() -> usize {
@@ -248,6 +282,14 @@ pub struct AddEntityRequest {
     pub metadata: serde_json::Value,
 }
 
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateEntityRequest {
+    pub name: Option<String>,
+    pub entity_type: Option<String>,
+    pub description: Option<String>,
+    pub metadata: Option<serde_json::Value>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct AddRelationshipRequest {
     pub source_entity_id: u64,
@@ -302,6 +344,8 @@ pub struct RegisterAgentRequest {
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum TaskStatus {
+    /// Waiting for dependencies to complete; becomes `Pending` when they do.
+    Blocked,
     Pending,
     Claimed,
     InProgress,
@@ -324,12 +368,69 @@ pub struct Task {
     #[serde(default)]
     pub dependencies: Vec<u64>,
     pub result: Option<String>,
+    /// Structured output attached on completion.
+    #[serde(default)]
+    pub artifacts: Vec<TaskArtifact>,
+    /// Memories the result and artifacts were stored as.
+    #[serde(default)]
+    pub result_memory_ids: Vec<u64>,
+    /// Last reported completion percentage.
+    #[serde(default)]
+    pub progress: Option<u8>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    #[serde(default, deserialize_with = "deserialize_deadline")]
+    pub deadline: Option<DateTime<Utc>>,
+    /// When the overdue sweeper flagged the task as past its deadline.
     #[serde(default)]
-    pub deadline: Option<String>,
+    pub overdue_at: Option<DateTime<Utc>>,
     #[serde(default)]
     pub metadata: serde_json::Value,
+    /// When the current claim lapses unless renewed (claimed/in-progress only).
+    #[serde(default)]
+    pub lease_expires_at: Option<DateTime<Utc>>,
+    /// Number of times the task has been claimed.
+    #[serde(default)]
+    pub attempts: u32,
+    /// Template this task was materialised from by the scheduler.
+    #[serde(default)]
+    pub template_id: Option<u64>,
+    /// Task this is a subtask of.
+    #[serde(default)]
+    pub parent_id: Option<u64>,
+    /// How this task follows its subtasks (the server default if unset).
+    #[serde(default)]
+    pub rollup: Option<RollupPolicy>,
+}
+
+/// How a parent task's status follows its subtasks. Cancelled subtasks are
+/// left out of the roll-up.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RollupPolicy {
+    /// Complete when every subtask completes; fail as soon as one fails
+    #[default]
+    FailFast,
+    /// Complete once every subtask has finished, even if some failed
+    BestEffort,
+    /// Never change the parent automatically
+    Manual,
+}
+
+impl std::str::FromStr for RollupPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fail_fast" | "fail-fast" => Ok(RollupPolicy::FailFast),
+            "best_effort" | "best-effort" => Ok(RollupPolicy::BestEffort),
+            "manual" => Ok(RollupPolicy::Manual),
+            other => Err(format!(
+                "Unknown roll-up policy '{}' (expected fail-fast, best-effort or manual)",
+                other
+            )),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -343,6 +444,8 @@ pub enum TaskEventType {
     Failed,
     Cancelled,
     Reassigned,
+    Unblocked,
+    Overdue,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -352,9 +455,21 @@ pub struct TaskEvent {
     pub event_type: TaskEventType,
     pub agent_id: Option<String>,
     pub details: Option<String>,
+    /// Structured payload, e.g. the details of a progress report.
+    #[serde(default)]
+    pub data: Option<serde_json::Value>,
     pub timestamp: DateTime<Utc>,
 }
 
+/// A named piece of task output.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskArtifact {
+    pub name: String,
+    pub content: String,
+    #[serde(default)]
+    pub metadata: serde_json::Value,
+}
+
 // ============================================================================
 // Task API Request Types
 // ============================================================================
@@ -370,10 +485,185 @@ pub struct CreateTaskRequest {
     pub created_by: String,
     #[serde(default)]
     pub dependencies: Vec<u64>,
+    /// RFC 3339 timestamp or `YYYY-MM-DD`; must be in the future.
     #[serde(default)]
     pub deadline: Option<String>,
     #[serde(default)]
     pub metadata: serde_json::Value,
+    /// Make this a subtask of an unfinished task.
+    #[serde(default)]
+    pub parent_id: Option<u64>,
+    /// Roll-up policy for this task's own subtasks.
+    #[serde(default)]
+    pub rollup: Option<RollupPolicy>,
+    /// Set by the template scheduler; not accepted from clients.
+    #[serde(skip)]
+    pub template_id: Option<u64>,
+}
+
+/// Parse a task deadline: an RFC 3339 timestamp, or a `YYYY-MM-DD` date
+/// meaning the end of that day (UTC).
+pub fn parse_deadline(s: &str) -> Result<DateTime<Utc>, String> {
+    let s = s.trim();
+    if let Ok(ts) = DateTime::parse_from_rfc3339(s) {
+        return Ok(ts.with_timezone(&Utc));
+    }
+    chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(23, 59, 59))
+        .map(|dt| dt.and_utc())
+        .ok_or_else(|| format!("Invalid deadline '{}': expected an RFC 3339 timestamp or YYYY-MM-DD date", s))
+}
+
+/// Tasks stored before deadlines were typed may hold free-form strings;
+/// keep the ones that parse and drop the rest.
+fn deserialize_deadline<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw = Option::<String>::deserialize(deserializer)?;
+    Ok(raw.and_then(|s| parse_deadline(&s).ok()))
+}
+
+// ============================================================================
+// Task Templates (recurring tasks)
+// ============================================================================
+
+/// When a task template materialises a new task.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TaskSchedule {
+    /// Cron expression evaluated in UTC (5 fields, or 6 with leading seconds).
+    Cron { expression: String },
+    /// Fixed interval in seconds.
+    Interval { every_secs: u64 },
+}
+
+/// Longest template interval or task deadline offset: ten years.
+pub const MAX_SCHEDULE_SECS: u64 = 10 * 366 * 24 * 3600;
+
+/// `secs` from `at`, or an error if it leaves chrono's date range.
+fn add_secs(at: DateTime<Utc>, secs: u64) -> Result<DateTime<Utc>, String> {
+    i64::try_from(secs)
+        .ok()
+        .and_then(chrono::TimeDelta::try_seconds)
+        .and_then(|delta| at.checked_add_signed(delta))
+        .ok_or_else(|| format!("{} seconds after {} is out of range", secs, at))
+}
+
+impl TaskSchedule {
+    fn parse_cron(expression: &str) -> Result<croner::Cron, String> {
+        croner::Cron::new(expression)
+            .with_seconds_optional()
+            .parse()
+            .map_err(|e| format!("Invalid cron expression '{}': {}", expression, e))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            TaskSchedule::Cron { expression } => Self::parse_cron(expression).map(|_| ()),
+            TaskSchedule::Interval { every_secs: 0 } => Err("Interval must be at least 1 second".into()),
+            TaskSchedule::Interval { every_secs } if *every_secs > MAX_SCHEDULE_SECS => Err(format!(
+                "Interval must be at most {} seconds",
+                MAX_SCHEDULE_SECS
+            )),
+            TaskSchedule::Interval { .. } => Ok(()),
+        }
+    }
+
+    /// The first run after `after` (or at `after` when `inclusive`).
+    pub fn next_run(&self, after: DateTime<Utc>, inclusive: bool) -> Result<DateTime<Utc>, String> {
+        match self {
+            TaskSchedule::Cron { expression } => Self::parse_cron(expression)?
+                .find_next_occurrence(&after, inclusive)
+                .map_err(|e| format!("Cron expression '{}' has no next run: {}", expression, e)),
+            TaskSchedule::Interval { .. } if inclusive => Ok(after),
+            TaskSchedule::Interval { every_secs } => add_secs(after, *every_secs),
+        }
+    }
+
+    /// The run following one scheduled at `scheduled`, skipping any that are
+    /// already in the past: runs missed while the server was down are not
+    /// replayed. Intervals keep their original cadence.
+    pub fn advance(&self, scheduled: DateTime<Utc>, now: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+        match self {
+            TaskSchedule::Interval { every_secs } => {
+                let every = (*every_secs).max(1);
+                let missed = (now - scheduled).num_seconds().max(0) as u64 / every;
+                let offset = (missed + 1)
+                    .checked_mul(every)
+                    .ok_or_else(|| format!("Interval of {} seconds is out of range", every))?;
+                add_secs(scheduled, offset)
+            }
+            TaskSchedule::Cron { .. } => self.next_run(scheduled.max(now), false),
+        }
+    }
+}
+
+/// A recurring task: the scheduler creates a task from it at every run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskTemplate {
+    pub id: u64,
+    pub title: String,
+    pub description: String,
+    pub priority: u32,
+    #[serde(default)]
+    pub required_capabilities: Vec<String>,
+    pub created_by: String,
+    #[serde(default)]
+    pub metadata: serde_json::Value,
+    pub schedule: TaskSchedule,
+    /// No task is created before this time.
+    #[serde(default)]
+    pub not_before: Option<DateTime<Utc>>,
+    /// Each created task is due this many seconds after it is created.
+    #[serde(default)]
+    pub deadline_secs: Option<u64>,
+    pub enabled: bool,
+    /// When the next task will be created (`None` while disabled).
+    pub next_run_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub last_run_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub last_task_id: Option<u64>,
+    #[serde(default)]
+    pub run_count: u64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTaskTemplateRequest {
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub priority: u32,
+    #[serde(default)]
+    pub required_capabilities: Vec<String>,
+    pub created_by: String,
+    pub schedule: TaskSchedule,
+    #[serde(default)]
+    pub not_before: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub deadline_secs: Option<u64>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub metadata: serde_json::Value,
+}
+
+/// Fields left out are unchanged.
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateTaskTemplateRequest {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub priority: Option<u32>,
+    pub required_capabilities: Option<Vec<String>>,
+    pub schedule: Option<TaskSchedule>,
+    pub not_before: Option<DateTime<Utc>>,
+    pub deadline_secs: Option<u64>,
+    pub enabled: Option<bool>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -381,10 +671,39 @@ pub struct ClaimTaskRequest {
     pub agent_id: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ClaimNextTaskRequest {
+    pub agent_id: String,
+    /// Used only when the agent has not been registered.
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    /// Long-poll for up to this many seconds when nothing is claimable yet.
+    #[serde(default)]
+    pub wait_secs: u64,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct CompleteTaskRequest {
     pub agent_id: String,
+    #[serde(default)]
     pub result: String,
+    #[serde(default)]
+    pub artifacts: Vec<TaskArtifact>,
+    /// Also store the result and each artifact as memories linked to the task.
+    #[serde(default)]
+    pub store_as_memory: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TaskProgressRequest {
+    pub agent_id: String,
+    /// 0–100.
+    #[serde(default)]
+    pub percent: Option<u8>,
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub details: serde_json::Value,
 }
 
 #[derive(Debug, Deserialize)]
@@ -393,6 +712,97 @@ pub struct FailTaskRequest {
     pub reason: String,
 }
 
+/// The dependency graph (pipeline) a task belongs to.
+#[derive(Debug, Serialize)]
+pub struct TaskGraph {
+    pub root: u64,
+    pub nodes: Vec<TaskGraphNode>,
+    pub edges: Vec<TaskGraphEdge>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TaskGraphNode {
+    pub task: Task,
+    /// Dependencies that have not completed yet.
+    pub blocked_by: Vec<u64>,
+}
+
+/// `to` depends on `from`.
+#[derive(Debug, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TaskGraphEdge {
+    pub from: u64,
+    pub to: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CancelTaskRequest {
+    /// Agent (or coordinator) cancelling the task.
+    pub agent_id: String,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReassignTaskRequest {
+    /// Agent (or coordinator) performing the reassignment.
+    pub agent_id: String,
+    /// Agent that takes over the task.
+    pub new_agent_id: String,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StartSessionRequest {
+    pub agent_id: String,
+    pub user_id: Option<String>,
+    /// Caller-chosen session ID (generated if omitted).
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub metadata: serde_json::Value,
+}
+
+/// Ending a session. Fields left empty are derived from the session's
+/// memories, through the LLM pipeline when `summarize` is set.
+#[derive(Debug, Default, Deserialize)]
+pub struct EndSessionRequest {
+    /// Required only when the session was never started through the API.
+    pub agent_id: Option<String>,
+    pub user_id: Option<String>,
+    pub summary: Option<String>,
+    #[serde(default)]
+    pub key_decisions: Vec<String>,
+    #[serde(default)]
+    pub tools_used: Vec<String>,
+    pub outcome: Option<String>,
+    #[serde(default)]
+    pub summarize: bool,
+    #[serde(default)]
+    pub metadata: serde_json::Value,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct EpisodeQuery {
+    pub agent_id: Option<String>,
+    pub user_id: Option<String>,
+    /// Only episodes that ended at or after this time.
+    pub since: Option<DateTime<Utc>>,
+    /// Only episodes that started at or before this time.
+    pub until: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EpisodeSearchRequest {
+    pub query: String,
+    #[serde(flatten)]
+    pub filter: EpisodeQuery,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EpisodeSearchResult {
+    pub episode: Episode,
+    pub score: f32,
+}
+
 // ============================================================================
 // Extraction Response Types
 // ============================================================================
@@ -536,10 +946,55 @@ pub struct SystemConfigResponse {
 #[derive(Debug, Serialize)]
 pub struct SystemTopologyResponse {
     pub node_id: String,
+    pub node_number: u16,
     pub listen_addr: String,
     pub rtdb_url: String,
     pub replication_enabled: bool,
     pub role: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replication: Option<ReplicationStatus>,
+}
+
+/// State of this node's replication outbox.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplicationStatus {
+    /// Sequence number of the newest queued event
+    pub last_seq: u64,
+    /// Highest sequence number RaftTimeDB has acknowledged
+    pub acked_seq: u64,
+    /// Events queued but not yet acknowledged
+    pub backlog: u64,
+    /// Age of the oldest unacknowledged event (0 when caught up)
+    pub lag_seconds: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub oldest_unacked_at: Option<DateTime<Utc>>,
+}
+
+/// How this node caught up with the cluster at startup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BootstrapOutcome {
+    /// No bootstrap source configured; started from local state
+    Skipped,
+    /// Loaded a snapshot and the events after it from the bootstrap source
+    CaughtUp,
+    /// The bootstrap source was unreachable; serving local, possibly stale, state
+    Failed,
+}
+
+/// `/health`: liveness and readiness, reported separately.
+#[derive(Debug, Serialize)]
+pub struct HealthResponse {
+    /// The process is up and answering requests
+    pub live: bool,
+    /// The node has current state and should receive traffic
+    pub ready: bool,
+    pub bootstrap: BootstrapOutcome,
+    /// Whether the last write-ahead log append succeeded
+    pub wal_healthy: bool,
+    /// Whether the RaftTimeDB connection is up (absent when standalone)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replication_connected: Option<bool>,
 }
 
 #[derive(Debug, Serialize)]
@@ -586,10 +1041,13 @@ pub struct KnowledgeGraphHealthInfo {
 #[derive(Debug, Serialize)]
 pub struct TasksHealthInfo {
     pub total: usize,
+    pub blocked: usize,
     pub pending: usize,
     pub in_progress: usize,
     pub completed: usize,
     pub failed: usize,
+    /// Unfinished tasks whose deadline has passed.
+    pub overdue: usize,
 }
 
 #[derive(Debug, Serialize)]
@@ -605,6 +1063,32 @@ pub struct SystemEmbeddingResponse {
     pub indexed_count: usize,
     pub pool_size: usize,
     pub available: bool,
+    /// `provider:model` of the index search is served from (differs from
+    /// `provider:model` above while a model-change reindex is running)
+    pub index_model: String,
+    pub reindex: EmbeddingReindexStatus,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReindexState {
+    Idle,
+    Running,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingReindexStatus {
+    pub state: ReindexState,
+    pub reason: Option<String>,
+    pub from_model: Option<String>,
+    pub to_model: String,
+    pub total: usize,
+    pub processed: usize,
+    pub failed: usize,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
 }
 
 // ============================================================================
@@ -622,10 +1106,15 @@ pub enum WsClientMessage {
     Unsubscribe {
         channels: Vec<String>,
     },
+    /// Task notifications for tasks the agent has every required capability
+    /// for, or is (or was) assigned to. With no capabilities, all tasks.
     SubscribeTasks {
         #[serde(default)]
         capabilities: Vec<String>,
         agent_id: String,
+        /// Only tasks assigned to (or created by) this agent.
+        #[serde(default)]
+        mine_only: bool,
     },
     Ping,
 }
@@ -643,6 +1132,28 @@ pub enum WsServerMessage {
     TaskUpdated { task: Task },
     TaskCompleted { task: Task },
     TaskFailed { task: Task },
+    TaskCancelled { task: Task },
+    TaskReassigned { task: Task, previous_agent: Option<String> },
+    TaskUnblocked { task: Task },
+    TaskOverdue { task: Task },
     Pong,
     Error { message: String },
 }
+
+impl WsServerMessage {
+    /// The task a task notification is about.
+    pub fn task(&self) -> Option<&Task> {
+        match self {
+            WsServerMessage::TaskCreated { task }
+            | WsServerMessage::TaskClaimed { task }
+            | WsServerMessage::TaskUpdated { task }
+            | WsServerMessage::TaskCompleted { task }
+            | WsServerMessage::TaskFailed { task }
+            | WsServerMessage::TaskCancelled { task }
+            | WsServerMessage::TaskReassigned { task, .. }
+            | WsServerMessage::TaskUnblocked { task }
+            | WsServerMessage::TaskOverdue { task } => Some(task),
+            _ => None,
+        }
+    }
+}
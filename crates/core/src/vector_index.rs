@@ -0,0 +1,615 @@
+use dashmap::DashMap;
+use rayon::prelude::*;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::sync::RwLock;
+
+use crate::embeddings::cosine_similarity;
+
+/// Nearest-neighbour index over memory embeddings.
+///
+/// Implementations own the vectors they index. Scores are cosine similarities,
+/// returned sorted descending.
+pub trait AnnIndex: Send + Sync {
+    /// Insert or replace the vector for `id`.
+    fn insert(&self, id: u64, vector: Vec<f32>);
+
+    /// Insert only if `id` has no vector yet. Returns whether it was inserted.
+    fn insert_if_absent(&self, id: u64, vector: Vec<f32>) -> bool;
+
+    /// Remove the vector for `id`. Returns whether it was present.
+    fn remove(&self, id: u64) -> bool;
+
+    fn contains(&self, id: u64) -> bool;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Top `limit` (id, similarity) pairs for `query`.
+    fn search(&self, query: &[f32], limit: usize) -> Vec<(u64, f32)>;
+
+    /// All indexed vectors (for persistence).
+    fn vectors(&self) -> Vec<(u64, Vec<f32>)>;
+}
+
+/// Which [`AnnIndex`] implementation backs semantic search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VectorIndexKind {
+    /// Exact search: scores every vector on each query
+    BruteForce,
+    /// Approximate search over a hierarchical navigable small-world graph
+    Hnsw,
+}
+
+impl std::str::FromStr for VectorIndexKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hnsw" => Ok(VectorIndexKind::Hnsw),
+            "brute-force" | "brute" | "exact" => Ok(VectorIndexKind::BruteForce),
+            other => Err(format!(
+                "Unknown vector index '{}' (expected hnsw or brute-force)",
+                other
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct VectorIndexConfig {
+    pub kind: VectorIndexKind,
+    /// HNSW: links per node and layer (layer 0 keeps twice as many)
+    pub m: usize,
+    /// HNSW: candidate list size while inserting (higher = better graph, slower inserts)
+    pub ef_construction: usize,
+    /// HNSW: candidate list size while searching (higher = better recall, slower queries)
+    pub ef_search: usize,
+    /// HNSW: below this many vectors, search scans exhaustively instead
+    pub exact_threshold: usize,
+}
+
+impl Default for VectorIndexConfig {
+    fn default() -> Self {
+        Self {
+            kind: VectorIndexKind::Hnsw,
+            m: 16,
+            ef_construction: 200,
+            ef_search: 64,
+            exact_threshold: 1_000,
+        }
+    }
+}
+
+/// Create an empty index of the configured kind.
+pub fn build_index(config: &VectorIndexConfig) -> Box<dyn AnnIndex> {
+    match config.kind {
+        VectorIndexKind::BruteForce => Box::new(BruteForceIndex::new()),
+        VectorIndexKind::Hnsw => Box::new(HnswIndex::new(config.clone())),
+    }
+}
+
+// ============================================================================
+// Brute force
+// ============================================================================
+
+/// Exact index: rayon-parallel cosine similarity against every vector.
+#[derive(Default)]
+pub struct BruteForceIndex {
+    vectors: DashMap<u64, Vec<f32>>,
+}
+
+impl BruteForceIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl AnnIndex for BruteForceIndex {
+    fn insert(&self, id: u64, vector: Vec<f32>) {
+        self.vectors.insert(id, vector);
+    }
+
+    fn insert_if_absent(&self, id: u64, vector: Vec<f32>) -> bool {
+        let mut inserted = false;
+        self.vectors.entry(id).or_insert_with(|| {
+            inserted = true;
+            vector
+        });
+        inserted
+    }
+
+    fn remove(&self, id: u64) -> bool {
+        self.vectors.remove(&id).is_some()
+    }
+
+    fn contains(&self, id: u64) -> bool {
+        self.vectors.contains_key(&id)
+    }
+
+    fn len(&self) -> usize {
+        self.vectors.len()
+    }
+
+    fn search(&self, query: &[f32], limit: usize) -> Vec<(u64, f32)> {
+        // Collect keys+vectors into a vec for rayon (DashMap iter isn't Send)
+        let entries: Vec<(u64, Vec<f32>)> = self.vectors();
+
+        // Parallel cosine similarity computation
+        let scores: Vec<(u64, f32)> = entries
+            .par_iter()
+            .map(|(id, vec)| (*id, cosine_similarity(query, vec)))
+            .collect();
+
+        top_k(scores, limit)
+    }
+
+    fn vectors(&self) -> Vec<(u64, Vec<f32>)> {
+        self.vectors
+            .iter()
+            .map(|entry| (*entry.key(), entry.value().clone()))
+            .collect()
+    }
+}
+
+/// Keep the `limit` highest scores, sorted descending.
+fn top_k(mut scores: Vec<(u64, f32)>, limit: usize) -> Vec<(u64, f32)> {
+    // Partial sort: only need top `limit` results
+    if scores.len() > limit {
+        scores.select_nth_unstable_by(limit, |a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+        scores.truncate(limit);
+    }
+    scores.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+    scores
+}
+
+// ============================================================================
+// HNSW
+// ============================================================================
+
+/// Hierarchical navigable small-world graph (Malkov & Yashunin).
+///
+/// Vectors are stored L2-normalised so similarity is a dot product. Inserts and
+/// deletes are incremental: a delete unlinks the node and reconnects each of
+/// its neighbours to the best remaining candidates. Stores smaller than
+/// `exact_threshold` are searched exhaustively.
+pub struct HnswIndex {
+    config: VectorIndexConfig,
+    graph: RwLock<HnswGraph>,
+}
+
+struct HnswNode {
+    vector: Vec<f32>,
+    /// Neighbour ids per layer, `links[0]` is the bottom layer
+    links: Vec<Vec<u64>>,
+}
+
+struct HnswGraph {
+    nodes: HashMap<u64, HnswNode>,
+    entry_point: Option<u64>,
+    max_level: usize,
+    /// xorshift64 state for level assignment
+    rng: u64,
+}
+
+/// Highest layer a node can be assigned to.
+const HNSW_MAX_LEVEL: usize = 16;
+
+/// (similarity, id) ordered by similarity.
+#[derive(Clone, Copy, PartialEq)]
+struct Scored(f32, u64);
+
+impl Eq for Scored {}
+
+impl PartialOrd for Scored {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Scored {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0).then(self.1.cmp(&other.1))
+    }
+}
+
+fn normalize(mut vector: Vec<f32>) -> Vec<f32> {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > f32::EPSILON {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+    vector
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+impl HnswIndex {
+    pub fn new(config: VectorIndexConfig) -> Self {
+        Self {
+            config,
+            graph: RwLock::new(HnswGraph {
+                nodes: HashMap::new(),
+                entry_point: None,
+                max_level: 0,
+                rng: 0x9E37_79B9_7F4A_7C15,
+            }),
+        }
+    }
+
+    fn max_links(&self, level: usize) -> usize {
+        if level == 0 { self.config.m * 2 } else { self.config.m }
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, HnswGraph> {
+        self.graph.read().expect("hnsw lock poisoned")
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, HnswGraph> {
+        self.graph.write().expect("hnsw lock poisoned")
+    }
+
+    fn insert_locked(&self, graph: &mut HnswGraph, id: u64, vector: Vec<f32>) {
+        if graph.nodes.contains_key(&id) {
+            self.remove_locked(graph, id);
+        }
+
+        let vector = normalize(vector);
+        let level = graph.random_level(self.config.m);
+
+        let Some(entry) = graph.entry_point else {
+            graph.nodes.insert(id, HnswNode { vector, links: vec![Vec::new(); level + 1] });
+            graph.entry_point = Some(id);
+            graph.max_level = level;
+            return;
+        };
+
+        // Greedy descent through the layers above the new node's level
+        let mut entry_points = vec![entry];
+        for layer in (level + 1..=graph.max_level).rev() {
+            entry_points = graph
+                .search_layer(&vector, &entry_points, 1, layer)
+                .into_iter()
+                .map(|s| s.1)
+                .collect();
+        }
+
+        // Connect on every layer the node lives on
+        let mut links = vec![Vec::new(); level + 1];
+        for layer in (0..=level.min(graph.max_level)).rev() {
+            let candidates =
+                graph.search_layer(&vector, &entry_points, self.config.ef_construction, layer);
+            links[layer] = candidates
+                .iter()
+                .take(self.max_links(layer))
+                .map(|s| s.1)
+                .collect();
+            entry_points = candidates.into_iter().map(|s| s.1).collect();
+        }
+        graph.nodes.insert(id, HnswNode { vector, links: links.clone() });
+
+        for (layer, neighbours) in links.iter().enumerate() {
+            for &neighbour in neighbours {
+                let Some(node) = graph.nodes.get_mut(&neighbour) else {
+                    continue;
+                };
+                node.links[layer].push(id);
+                if node.links[layer].len() > self.max_links(layer) {
+                    let candidates = node.links[layer].clone();
+                    let pruned = graph.closest(neighbour, candidates, self.max_links(layer));
+                    graph.nodes.get_mut(&neighbour).unwrap().links[layer] = pruned;
+                }
+            }
+        }
+
+        if level > graph.max_level {
+            graph.max_level = level;
+            graph.entry_point = Some(id);
+        }
+    }
+
+    fn remove_locked(&self, graph: &mut HnswGraph, id: u64) -> bool {
+        let Some(removed) = graph.nodes.remove(&id) else {
+            return false;
+        };
+
+        // Reconnect each former neighbour to the best of its remaining links
+        // plus the removed node's other neighbours on that layer.
+        for (layer, neighbours) in removed.links.iter().enumerate() {
+            for &neighbour in neighbours {
+                let Some(node) = graph.nodes.get(&neighbour) else {
+                    continue;
+                };
+                if layer >= node.links.len() {
+                    continue;
+                }
+                let mut candidates: Vec<u64> =
+                    node.links[layer].iter().copied().filter(|&n| n != id).collect();
+                for &other in neighbours {
+                    if other != neighbour
+                        && !candidates.contains(&other)
+                        && graph.nodes.get(&other).is_some_and(|n| n.links.len() > layer)
+                    {
+                        candidates.push(other);
+                    }
+                }
+                let repaired = graph.closest(neighbour, candidates, self.max_links(layer));
+                graph.nodes.get_mut(&neighbour).unwrap().links[layer] = repaired;
+            }
+        }
+
+        if graph.entry_point == Some(id) {
+            let next = graph
+                .nodes
+                .iter()
+                .max_by_key(|(node_id, node)| (node.links.len(), std::cmp::Reverse(**node_id)))
+                .map(|(node_id, node)| (*node_id, node.links.len() - 1));
+            graph.entry_point = next.map(|(node_id, _)| node_id);
+            graph.max_level = next.map(|(_, level)| level).unwrap_or(0);
+        }
+        true
+    }
+}
+
+impl HnswGraph {
+    /// Draw a level from the exponential distribution with mL = 1/ln(M).
+    fn random_level(&mut self, m: usize) -> usize {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        // Uniform in (0, 1]
+        let uniform = ((self.rng >> 11) as f64 + 1.0) / (1u64 << 53) as f64;
+        let ml = 1.0 / (m.max(2) as f64).ln();
+        ((-uniform.ln() * ml) as usize).min(HNSW_MAX_LEVEL)
+    }
+
+    fn similarity(&self, query: &[f32], id: u64) -> Option<f32> {
+        self.nodes.get(&id).map(|n| dot(query, &n.vector))
+    }
+
+    /// The `limit` ids in `candidates` most similar to node `id`.
+    fn closest(&self, id: u64, candidates: Vec<u64>, limit: usize) -> Vec<u64> {
+        let Some(node) = self.nodes.get(&id) else {
+            return candidates;
+        };
+        let mut scored: Vec<Scored> = candidates
+            .into_iter()
+            .filter_map(|c| self.similarity(&node.vector, c).map(|s| Scored(s, c)))
+            .collect();
+        scored.sort_unstable_by(|a, b| b.cmp(a));
+        scored.into_iter().take(limit).map(|s| s.1).collect()
+    }
+
+    /// Best-first search on one layer; returns up to `ef` results, best first.
+    fn search_layer(&self, query: &[f32], entry_points: &[u64], ef: usize, layer: usize) -> Vec<Scored> {
+        let ef = ef.max(1);
+        let mut visited: HashSet<u64> = HashSet::new();
+        // Max-heap of candidates to expand, min-heap (via Reverse) of results
+        let mut candidates: BinaryHeap<Scored> = BinaryHeap::new();
+        let mut results: BinaryHeap<std::cmp::Reverse<Scored>> = BinaryHeap::new();
+
+        for &ep in entry_points {
+            if visited.insert(ep)
+                && let Some(sim) = self.similarity(query, ep)
+            {
+                candidates.push(Scored(sim, ep));
+                results.push(std::cmp::Reverse(Scored(sim, ep)));
+            }
+        }
+        while results.len() > ef {
+            results.pop();
+        }
+
+        while let Some(Scored(sim, id)) = candidates.pop() {
+            let worst = results.peek().map(|r| r.0.0).unwrap_or(f32::MIN);
+            if sim < worst && results.len() >= ef {
+                break;
+            }
+            let Some(node) = self.nodes.get(&id) else {
+                continue;
+            };
+            let Some(neighbours) = node.links.get(layer) else {
+                continue;
+            };
+            for &neighbour in neighbours {
+                if !visited.insert(neighbour) {
+                    continue;
+                }
+                // Links may still point at a deleted node until it is repaired
+                let Some(sim) = self.similarity(query, neighbour) else {
+                    continue;
+                };
+                let worst = results.peek().map(|r| r.0.0).unwrap_or(f32::MIN);
+                if results.len() < ef || sim > worst {
+                    candidates.push(Scored(sim, neighbour));
+                    results.push(std::cmp::Reverse(Scored(sim, neighbour)));
+                    if results.len() > ef {
+                        results.pop();
+                    }
+                }
+            }
+        }
+
+        let mut out: Vec<Scored> = results.into_iter().map(|r| r.0).collect();
+        out.sort_unstable_by(|a, b| b.cmp(a));
+        out
+    }
+}
+
+impl AnnIndex for HnswIndex {
+    fn insert(&self, id: u64, vector: Vec<f32>) {
+        let mut graph = self.write();
+        self.insert_locked(&mut graph, id, vector);
+    }
+
+    fn insert_if_absent(&self, id: u64, vector: Vec<f32>) -> bool {
+        let mut graph = self.write();
+        if graph.nodes.contains_key(&id) {
+            return false;
+        }
+        self.insert_locked(&mut graph, id, vector);
+        true
+    }
+
+    fn remove(&self, id: u64) -> bool {
+        let mut graph = self.write();
+        self.remove_locked(&mut graph, id)
+    }
+
+    fn contains(&self, id: u64) -> bool {
+        self.read().nodes.contains_key(&id)
+    }
+
+    fn len(&self) -> usize {
+        self.read().nodes.len()
+    }
+
+    fn search(&self, query: &[f32], limit: usize) -> Vec<(u64, f32)> {
+        let graph = self.read();
+        let query = normalize(query.to_vec());
+
+        if graph.nodes.len() <= self.config.exact_threshold {
+            let scores = graph
+                .nodes
+                .iter()
+                .map(|(id, node)| (*id, dot(&query, &node.vector)))
+                .collect();
+            return top_k(scores, limit);
+        }
+
+        let Some(entry) = graph.entry_point else {
+            return vec![];
+        };
+        let mut entry_points = vec![entry];
+        for layer in (1..=graph.max_level).rev() {
+            entry_points = graph
+                .search_layer(&query, &entry_points, 1, layer)
+                .into_iter()
+                .map(|s| s.1)
+                .collect();
+        }
+        graph
+            .search_layer(&query, &entry_points, self.config.ef_search.max(limit), 0)
+            .into_iter()
+            .take(limit)
+            .map(|s| (s.1, s.0))
+            .collect()
+    }
+
+    fn vectors(&self) -> Vec<(u64, Vec<f32>)> {
+        self.read()
+            .nodes
+            .iter()
+            .map(|(id, node)| (*id, node.vector.clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic pseudo-random vectors.
+    fn random_vectors(count: usize, dims: usize, seed: u64) -> Vec<Vec<f32>> {
+        let mut state = seed;
+        (0..count)
+            .map(|_| {
+                (0..dims)
+                    .map(|_| {
+                        state ^= state << 13;
+                        state ^= state >> 7;
+                        state ^= state << 17;
+                        (state >> 40) as f32 / (1u64 << 24) as f32 - 0.5
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn hnsw(exact_threshold: usize) -> HnswIndex {
+        HnswIndex::new(VectorIndexConfig {
+            exact_threshold,
+            ..VectorIndexConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_hnsw_recall_against_brute_force() {
+        let vectors = random_vectors(1_000, 16, 7);
+        let exact = BruteForceIndex::new();
+        let approx = HnswIndex::new(VectorIndexConfig {
+            ef_construction: 100,
+            exact_threshold: 0,
+            ..VectorIndexConfig::default()
+        });
+        for (id, v) in vectors.iter().enumerate() {
+            exact.insert(id as u64, v.clone());
+            approx.insert(id as u64, v.clone());
+        }
+
+        let queries = random_vectors(50, 16, 99);
+        let mut hits = 0;
+        for q in &queries {
+            let truth: HashSet<u64> = exact.search(q, 10).into_iter().map(|r| r.0).collect();
+            hits += approx.search(q, 10).iter().filter(|r| truth.contains(&r.0)).count();
+        }
+        let recall = hits as f32 / (queries.len() * 10) as f32;
+        assert!(recall >= 0.9, "recall@10 = {}", recall);
+    }
+
+    #[test]
+    fn test_hnsw_insert_replace_and_remove() {
+        let index = hnsw(0);
+        let vectors = random_vectors(300, 8, 3);
+        for (id, v) in vectors.iter().enumerate() {
+            index.insert(id as u64, v.clone());
+        }
+        assert_eq!(index.len(), 300);
+
+        // Exact self-match is found through the graph
+        assert_eq!(index.search(&vectors[42], 1)[0].0, 42);
+
+        // Replacing keeps a single entry
+        index.insert(42, vectors[7].clone());
+        assert_eq!(index.len(), 300);
+        assert!(!index.insert_if_absent(42, vectors[42].clone()));
+
+        // Removing every other node (including the entry point) keeps the rest reachable
+        for id in (0..300u64).step_by(2) {
+            assert!(index.remove(id));
+        }
+        assert!(!index.remove(0));
+        assert_eq!(index.len(), 150);
+        for id in [1u64, 99, 151, 299] {
+            let results = index.search(&vectors[id as usize], 5);
+            assert_eq!(results[0].0, id);
+            assert!(results.iter().all(|r| r.0 % 2 == 1));
+        }
+    }
+
+    #[test]
+    fn test_exact_search_below_threshold() {
+        let index = hnsw(1_000);
+        index.insert(1, vec![1.0, 0.0, 0.0]);
+        index.insert(2, vec![0.0, 1.0, 0.0]);
+        index.insert(3, vec![0.9, 0.1, 0.0]);
+
+        let results = index.search(&[1.0, 0.0, 0.0], 2);
+        assert_eq!(results.iter().map(|r| r.0).collect::<Vec<_>>(), vec![1, 3]);
+        assert!((results[0].1 - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_vector_index_kind_parse() {
+        assert_eq!("hnsw".parse::<VectorIndexKind>().unwrap(), VectorIndexKind::Hnsw);
+        assert_eq!("brute-force".parse::<VectorIndexKind>().unwrap(), VectorIndexKind::BruteForce);
+        assert!("ivf".parse::<VectorIndexKind>().is_err());
+    }
+}
@@ -5,8 +5,40 @@ use tokio::sync::broadcast;
 use tracing::{debug, info, warn};
 
 use crate::channels::ChannelHub;
+use crate::memory_engine::missing_capabilities;
 use crate::types::*;
 
+/// Which task notifications a `subscribe_tasks` connection receives.
+#[derive(Debug, Clone)]
+struct TaskFilter {
+    agent_id: String,
+    capabilities: Vec<String>,
+    mine_only: bool,
+}
+
+impl TaskFilter {
+    /// Whether to forward `msg`. Messages that aren't about a task always pass.
+    fn allows(&self, msg: &WsServerMessage) -> bool {
+        let Some(task) = msg.task() else {
+            return true;
+        };
+        let agent = Some(self.agent_id.as_str());
+        // Agents always hear about their own tasks, including ones taken from them
+        let previous_agent = match msg {
+            WsServerMessage::TaskReassigned { previous_agent, .. } => previous_agent.as_deref(),
+            _ => None,
+        };
+        if task.assigned_agent.as_deref() == agent || previous_agent == agent {
+            return true;
+        }
+        if self.mine_only {
+            return task.created_by == self.agent_id;
+        }
+        self.capabilities.is_empty()
+            || missing_capabilities(&task.required_capabilities, &self.capabilities).is_empty()
+    }
+}
+
 /// Handle a WebSocket client connection.
 ///
 /// Clients send JSON messages to subscribe/unsubscribe to channels
@@ -95,15 +127,21 @@ async fn handle_client_message(
 
             for channel_name in &channel_names {
                 // Auto-create channels if they don't exist
-                if channels.get_channel_by_name(channel_name).is_none() {
-                    channels.create_channel(CreateChannelRequest {
+                if channels.get_channel_by_name(channel_name).is_none()
+                    && let Err(e) = channels.create_channel(CreateChannelRequest {
                         name: channel_name.clone(),
                         description: None,
                         channel_type: ChannelType::Public,
                         created_by: agent_id
                             .clone()
                             .unwrap_or_else(|| "ws-client".into()),
-                    });
+                    })
+                {
+                    let err = WsServerMessage::Error {
+                        message: format!("Could not create channel {}: {}", channel_name, e),
+                    };
+                    let _ = tx.send(serde_json::to_string(&err).unwrap());
+                    continue;
                 }
 
                 let aid = agent_id.clone().unwrap_or_else(|| "anonymous".into());
@@ -111,7 +149,7 @@ async fn handle_client_message(
                     subscribed.push(channel_name.clone());
                     // Spawn a task to forward channel messages to this client
                     let tx_clone = tx.clone();
-                    let handle = tokio::spawn(forward_channel_messages(rx, tx_clone));
+                    let handle = tokio::spawn(forward_channel_messages(rx, tx_clone, None));
                     active_receivers.lock().await.push(handle);
                 }
             }
@@ -123,23 +161,35 @@ async fn handle_client_message(
         }
 
         WsClientMessage::SubscribeTasks {
-            capabilities: _capabilities,
+            capabilities,
             agent_id,
+            mine_only,
         } => {
             // Auto-subscribe to the "tasks" channel for real-time task events
             let channel_name = "tasks";
-            if channels.get_channel_by_name(channel_name).is_none() {
-                channels.create_channel(CreateChannelRequest {
+            if channels.get_channel_by_name(channel_name).is_none()
+                && let Err(e) = channels.create_channel(CreateChannelRequest {
                     name: channel_name.to_string(),
                     description: Some("Task event broadcast channel".into()),
                     channel_type: ChannelType::Public,
                     created_by: agent_id.clone(),
-                });
+                })
+            {
+                let err = WsServerMessage::Error {
+                    message: format!("Could not create channel {}: {}", channel_name, e),
+                };
+                let _ = tx.send(serde_json::to_string(&err).unwrap());
+                return;
             }
 
             if let Some(rx) = channels.subscribe_by_name(channel_name, &agent_id) {
+                let filter = TaskFilter {
+                    agent_id: agent_id.clone(),
+                    capabilities,
+                    mine_only,
+                };
                 let tx_clone = tx.clone();
-                let handle = tokio::spawn(forward_channel_messages(rx, tx_clone));
+                let handle = tokio::spawn(forward_channel_messages(rx, tx_clone, Some(filter)));
                 active_receivers.lock().await.push(handle);
             }
 
@@ -147,7 +197,7 @@ async fn handle_client_message(
                 channels: vec![channel_name.to_string()],
             };
             let _ = tx.send(serde_json::to_string(&resp).unwrap());
-            info!(agent_id = %agent_id, "Agent subscribed to tasks");
+            info!(agent_id = %agent_id, mine_only, "Agent subscribed to tasks");
         }
 
         WsClientMessage::Unsubscribe { channels: _ } => {
@@ -165,10 +215,14 @@ async fn handle_client_message(
 async fn forward_channel_messages(
     mut rx: broadcast::Receiver<WsServerMessage>,
     tx: tokio::sync::mpsc::UnboundedSender<String>,
+    filter: Option<TaskFilter>,
 ) {
     loop {
         match rx.recv().await {
             Ok(msg) => {
+                if filter.as_ref().is_some_and(|f| !f.allows(&msg)) {
+                    continue;
+                }
                 if let Ok(json) = serde_json::to_string(&msg) {
                     if tx.send(json).is_err() {
                         break; // Client disconnected
@@ -246,4 +300,73 @@ mod tests {
         let json = serde_json::to_string(&msg).unwrap();
         assert!(json.contains("\"type\":\"subscribed\""));
     }
+
+    #[test]
+    fn test_ws_client_message_parse_subscribe_tasks() {
+        let json = r#"{"type":"subscribe_tasks","agent_id":"agent-1","capabilities":["rust"]}"#;
+        match serde_json::from_str::<WsClientMessage>(json).unwrap() {
+            WsClientMessage::SubscribeTasks { capabilities, agent_id, mine_only } => {
+                assert_eq!(capabilities, vec!["rust"]);
+                assert_eq!(agent_id, "agent-1");
+                assert!(!mine_only);
+            }
+            _ => panic!("Wrong message type"),
+        }
+    }
+
+    fn task(required: &[&str], assigned: Option<&str>, created_by: &str) -> Task {
+        Task {
+            id: 1,
+            title: "Task".into(),
+            description: String::new(),
+            status: TaskStatus::Pending,
+            priority: 0,
+            required_capabilities: required.iter().map(|c| c.to_string()).collect(),
+            assigned_agent: assigned.map(String::from),
+            created_by: created_by.into(),
+            dependencies: vec![],
+            result: None,
+            artifacts: vec![],
+            result_memory_ids: vec![],
+            progress: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            deadline: None,
+            overdue_at: None,
+            metadata: serde_json::Value::Null,
+            lease_expires_at: None,
+            attempts: 0,
+            template_id: None,
+            parent_id: None,
+            rollup: None,
+        }
+    }
+
+    #[test]
+    fn test_task_filter() {
+        let worker = TaskFilter {
+            agent_id: "worker".into(),
+            capabilities: vec!["rust".into(), "git".into()],
+            mine_only: false,
+        };
+        let created = |t: Task| WsServerMessage::TaskCreated { task: t };
+        assert!(worker.allows(&created(task(&["rust"], None, "planner"))));
+        assert!(worker.allows(&created(task(&[], None, "planner"))));
+        assert!(!worker.allows(&created(task(&["rust", "python"], None, "planner"))));
+        // Own tasks pass regardless of capabilities, including ones taken away
+        assert!(worker.allows(&created(task(&["python"], Some("worker"), "planner"))));
+        assert!(worker.allows(&WsServerMessage::TaskReassigned {
+            task: task(&["python"], Some("other"), "planner"),
+            previous_agent: Some("worker".into()),
+        }));
+        assert!(worker.allows(&WsServerMessage::Pong));
+
+        let everything = TaskFilter { capabilities: vec![], ..worker.clone() };
+        assert!(everything.allows(&created(task(&["python"], None, "planner"))));
+
+        let mine = TaskFilter { mine_only: true, ..worker };
+        assert!(!mine.allows(&created(task(&["rust"], None, "planner"))));
+        assert!(mine.allows(&created(task(&["rust"], Some("worker"), "planner"))));
+        assert!(mine.allows(&created(task(&["python"], None, "worker"))));
+    }
 }
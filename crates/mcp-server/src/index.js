@@ -138,6 +138,10 @@ const TOOLS = [
         },
         agent_id: { type: "string", description: "Agent that created this" },
         user_id: { type: "string", description: "Associated user" },
+        session_id: {
+          type: "string",
+          description: "Session the memory belongs to (summarized by session_end)",
+        },
         tags: {
           type: "array",
           items: { type: "string" },
@@ -154,7 +158,7 @@ const TOOLS = [
     inputSchema: {
       type: "object",
       properties: {
-        query: { type: "string", description: "Search query" },
+        query: { type: "string", description: "Search query. Supports \"quoted phrases\", AND/OR/NOT, parentheses and tag:/type:/agent:/user:/session: filters" },
         agent_id: { type: "string", description: "Filter by agent" },
         user_id: { type: "string", description: "Filter by user" },
         tags: {
@@ -337,6 +341,79 @@ const TOOLS = [
     inputSchema: { type: "object", properties: {} },
   },
 
+  // --- Session & episode tools ---
+  {
+    name: "session_start",
+    description:
+      "Start a work session. Memories added with its session_id are summarized into an episode when the session ends.",
+    inputSchema: {
+      type: "object",
+      properties: {
+        session_id: {
+          type: "string",
+          description: "Session ID (generated if omitted)",
+        },
+        user_id: { type: "string", description: "Associated user" },
+      },
+    },
+  },
+  {
+    name: "session_end",
+    description:
+      "End a session and record what happened as an episode. Fields left out are derived from the session's memories.",
+    inputSchema: {
+      type: "object",
+      properties: {
+        session_id: { type: "string", description: "Session to end" },
+        summary: { type: "string", description: "What the session accomplished" },
+        key_decisions: {
+          type: "array",
+          items: { type: "string" },
+          description: "Decisions made during the session",
+        },
+        tools_used: {
+          type: "array",
+          items: { type: "string" },
+          description: "Tools used during the session",
+        },
+        outcome: {
+          type: "string",
+          description: 'Outcome (e.g., "success", "partial", "failed")',
+        },
+        summarize: {
+          type: "boolean",
+          description: "Summarize the session's memories with the LLM pipeline",
+        },
+      },
+      required: ["session_id"],
+    },
+  },
+  {
+    name: "episode_search",
+    description:
+      "List or search past session episodes by text, agent, user, and time range.",
+    inputSchema: {
+      type: "object",
+      properties: {
+        query: {
+          type: "string",
+          description: "Search text (lists recent episodes if omitted)",
+        },
+        agent_id: { type: "string", description: "Filter by agent" },
+        user_id: { type: "string", description: "Filter by user" },
+        since: {
+          type: "string",
+          description: "Only episodes that ended at or after this time (RFC 3339)",
+        },
+        until: {
+          type: "string",
+          description: "Only episodes that started at or before this time (RFC 3339)",
+        },
+        limit: { type: "number", description: "Max results (default: 10)" },
+      },
+    },
+  },
+
   // --- Task management tools ---
   {
     name: "task_create",
@@ -363,7 +440,16 @@ const TOOLS = [
         },
         deadline: {
           type: "string",
-          description: "Deadline for the task (ISO 8601 string)",
+          description: "Deadline for the task (RFC 3339 timestamp, or YYYY-MM-DD for the end of that day); must be in the future",
+        },
+        parent_id: {
+          type: "number",
+          description: "Make this a subtask of an unfinished task; the parent completes or fails with its subtasks",
+        },
+        rollup: {
+          type: "string",
+          enum: ["fail_fast", "best_effort", "manual"],
+          description: "How this task follows its own subtasks (default: server setting)",
         },
         metadata: {
           type: "object",
@@ -383,7 +469,7 @@ const TOOLS = [
         status: {
           type: "string",
           description:
-            'Filter by task status (e.g., "pending", "claimed", "in_progress", "completed", "failed")',
+            'Filter by task status (e.g., "blocked", "pending", "claimed", "in_progress", "completed", "failed")',
         },
         agent_id: {
           type: "string",
@@ -407,7 +493,7 @@ const TOOLS = [
   {
     name: "task_claim",
     description:
-      "Claim a pending task for this agent. The task must be in pending status.",
+      "Claim a pending task for this agent. The task must be in pending status. The claim is a lease: renew it with task_heartbeat while working or the task is requeued.",
     inputSchema: {
       type: "object",
       properties: {
@@ -416,6 +502,20 @@ const TOOLS = [
       required: ["task_id"],
     },
   },
+  {
+    name: "task_claim_next",
+    description:
+      "Claim the highest-priority pending task this agent has the capabilities for, optionally waiting for one to appear.",
+    inputSchema: {
+      type: "object",
+      properties: {
+        wait_secs: {
+          type: "number",
+          description: "Seconds to wait for a task if none is available (default: 0, max: 60)",
+        },
+      },
+    },
+  },
   {
     name: "task_start",
     description:
@@ -443,6 +543,23 @@ const TOOLS = [
           type: "string",
           description: "The result or output of the completed task",
         },
+        artifacts: {
+          type: "array",
+          items: {
+            type: "object",
+            properties: {
+              name: { type: "string", description: "Short name, e.g. 'design notes'" },
+              content: { type: "string", description: "The artifact itself" },
+              metadata: { type: "object", description: "Optional structured data" },
+            },
+            required: ["name", "content"],
+          },
+          description: "Named pieces of output produced by the task",
+        },
+        store_as_memory: {
+          type: "boolean",
+          description: "Also store the result and artifacts as memories other agents can recall (default: false)",
+        },
       },
       required: ["task_id", "result"],
     },
@@ -463,12 +580,84 @@ const TOOLS = [
       required: ["task_id", "reason"],
     },
   },
+  {
+    name: "task_progress",
+    description:
+      "Report progress on a task this agent is working on. Also renews the task's lease.",
+    inputSchema: {
+      type: "object",
+      properties: {
+        task_id: { type: "string", description: "ID of the task being worked on" },
+        percent: { type: "number", description: "Completion percentage (0-100)" },
+        message: { type: "string", description: "What was just done or is happening now" },
+        details: { type: "object", description: "Optional structured details" },
+      },
+      required: ["task_id"],
+    },
+  },
+  {
+    name: "task_heartbeat",
+    description:
+      "Renew the lease on a task this agent has claimed so it is not requeued while work continues.",
+    inputSchema: {
+      type: "object",
+      properties: {
+        task_id: { type: "string", description: "ID of the task being worked on" },
+      },
+      required: ["task_id"],
+    },
+  },
+  {
+    name: "task_cancel",
+    description:
+      "Cancel a task that has not finished yet (e.g., stale or no longer needed work).",
+    inputSchema: {
+      type: "object",
+      properties: {
+        task_id: { type: "string", description: "ID of the task to cancel" },
+        reason: { type: "string", description: "Why the task is cancelled" },
+      },
+      required: ["task_id"],
+    },
+  },
+  {
+    name: "task_reassign",
+    description:
+      "Move a claimed or in-progress task to another agent. The new agent must start it again.",
+    inputSchema: {
+      type: "object",
+      properties: {
+        task_id: { type: "string", description: "ID of the task to reassign" },
+        new_agent_id: {
+          type: "string",
+          description: "Agent that takes over the task",
+        },
+        reason: { type: "string", description: "Why the task is reassigned" },
+      },
+      required: ["task_id", "new_agent_id"],
+    },
+  },
 ];
 
 // ============================================================================
 // Tool Handlers
 // ============================================================================
 
+function formatEpisode(episode) {
+  const lines = [
+    `#${episode.id} [${episode.agent_id}] session ${episode.session_id} (${episode.started_at} → ${episode.ended_at})`,
+    `  Summary: ${episode.summary}`,
+    `  Outcome: ${episode.outcome}`,
+  ];
+  if (episode.key_decisions.length > 0) {
+    lines.push(`  Decisions: ${episode.key_decisions.join("; ")}`);
+  }
+  if (episode.tools_used.length > 0) {
+    lines.push(`  Tools: ${episode.tools_used.join(", ")}`);
+  }
+  return lines.join("\n");
+}
+
 async function handleTool(name, args) {
   switch (name) {
     // --- AgentCore-compatible ---
@@ -562,6 +751,7 @@ async function handleTool(name, args) {
         memory_type: args.memory_type || "fact",
         agent_id: args.agent_id,
         user_id: args.user_id,
+        session_id: args.session_id,
         tags: args.tags || [],
       });
       return JSON.stringify(result, null, 2);
@@ -742,6 +932,60 @@ async function handleTool(name, args) {
       ].join("\n");
     }
 
+    // --- Session & episode tools ---
+    case "session_start": {
+      const agentId = process.env.AGENT_ID || "default";
+      const session = await apiCall("POST", "/api/v1/sessions/start", {
+        agent_id: agentId,
+        user_id: args.user_id,
+        session_id: args.session_id,
+      });
+      return `Session ${session.session_id} started for agent "${session.agent_id}"`;
+    }
+
+    case "session_end": {
+      const agentId = process.env.AGENT_ID || "default";
+      const episode = await apiCall(
+        "POST",
+        `/api/v1/sessions/${encodeURIComponent(args.session_id)}/end`,
+        {
+          agent_id: agentId,
+          summary: args.summary,
+          key_decisions: args.key_decisions || [],
+          tools_used: args.tools_used || [],
+          outcome: args.outcome,
+          summarize: args.summarize || false,
+        }
+      );
+      return `Episode #${episode.id} recorded\n${formatEpisode(episode)}`;
+    }
+
+    case "episode_search": {
+      const filter = {
+        agent_id: args.agent_id,
+        user_id: args.user_id,
+        since: args.since,
+        until: args.until,
+        limit: args.limit || 10,
+      };
+      let episodes;
+      if (args.query) {
+        const results = await apiCall("POST", "/api/v1/episodes/search", {
+          query: args.query,
+          ...filter,
+        });
+        episodes = results.map((r) => r.episode);
+      } else {
+        const params = new URLSearchParams();
+        for (const [key, value] of Object.entries(filter)) {
+          if (value !== undefined) params.set(key, value);
+        }
+        episodes = await apiCall("GET", `/api/v1/episodes?${params}`);
+      }
+      if (episodes.length === 0) return "No episodes found.";
+      return episodes.map(formatEpisode).join("\n\n");
+    }
+
     // --- Task management tools ---
     case "task_create": {
       const agentId = process.env.AGENT_ID || "default";
@@ -753,6 +997,8 @@ async function handleTool(name, args) {
         created_by: agentId,
         dependencies: args.dependencies || [],
         deadline: args.deadline,
+        parent_id: args.parent_id,
+        rollup: args.rollup,
         metadata: args.metadata || {},
       });
       return (
@@ -820,6 +1066,21 @@ async function handleTool(name, args) {
       return `Task #${args.task_id} claimed by agent "${agentId}"`;
     }
 
+    case "task_claim_next": {
+      const agentId = process.env.AGENT_ID || "default";
+      const task = await apiCall("POST", "/api/v1/tasks/claim-next", {
+        agent_id: agentId,
+        wait_secs: args.wait_secs || 0,
+      });
+      if (!task) return "No claimable tasks available.";
+      return (
+        `Claimed task #${task.id} for agent "${agentId}"\n` +
+        `  Title: ${task.title}\n` +
+        `  Priority: ${task.priority}\n` +
+        `  Description: ${task.description}`
+      );
+    }
+
     case "task_start": {
       const agentId = process.env.AGENT_ID || "default";
       await apiCall("POST", `/api/v1/tasks/${args.task_id}/start`, {
@@ -833,6 +1094,8 @@ async function handleTool(name, args) {
       await apiCall("POST", `/api/v1/tasks/${args.task_id}/complete`, {
         agent_id: agentId,
         result: args.result,
+        artifacts: args.artifacts || [],
+        store_as_memory: args.store_as_memory || false,
       });
       return `Task #${args.task_id} completed by agent "${agentId}"`;
     }
@@ -846,6 +1109,45 @@ async function handleTool(name, args) {
       return `Task #${args.task_id} marked as failed by agent "${agentId}": ${args.reason}`;
     }
 
+    case "task_progress": {
+      const agentId = process.env.AGENT_ID || "default";
+      const task = await apiCall("POST", `/api/v1/tasks/${args.task_id}/progress`, {
+        agent_id: agentId,
+        percent: args.percent,
+        message: args.message,
+        details: args.details,
+      });
+      const percent = task.progress != null ? ` (${task.progress}%)` : "";
+      return `Progress recorded for task #${task.id}${percent}`;
+    }
+
+    case "task_heartbeat": {
+      const agentId = process.env.AGENT_ID || "default";
+      const task = await apiCall("POST", `/api/v1/tasks/${args.task_id}/heartbeat`, {
+        agent_id: agentId,
+      });
+      return `Task #${task.id} lease renewed until ${task.lease_expires_at || "(no expiry)"}`;
+    }
+
+    case "task_cancel": {
+      const agentId = process.env.AGENT_ID || "default";
+      await apiCall("POST", `/api/v1/tasks/${args.task_id}/cancel`, {
+        agent_id: agentId,
+        reason: args.reason,
+      });
+      return `Task #${args.task_id} cancelled by agent "${agentId}"`;
+    }
+
+    case "task_reassign": {
+      const agentId = process.env.AGENT_ID || "default";
+      const task = await apiCall("POST", `/api/v1/tasks/${args.task_id}/reassign`, {
+        agent_id: agentId,
+        new_agent_id: args.new_agent_id,
+        reason: args.reason,
+      });
+      return `Task #${task.id} reassigned to agent "${task.assigned_agent}"`;
+    }
+
     default:
       throw new Error(`Unknown tool: ${name}`);
   }